@@ -0,0 +1,59 @@
+/* IPASIR, the reentrant incremental SAT solver API, as exported by theorem_prover.
+ *
+ * Build the crate with `cargo build --release` and link against
+ * target/release/libtheorem_prover.a (with -lpthread -ldl -lm) or the shared
+ * libtheorem_prover.so.
+ *
+ * Variables are positive integers and literals are nonzero integers, negative ones being
+ * negated, like in DIMACS. A solver is in one of three states: INPUT after init and after
+ * adding, SAT or UNSAT after solve returned 10 or 20. `ipasir_val` is only defined in SAT,
+ * `ipasir_failed` only in UNSAT.
+ */
+#ifndef IPASIR_H
+#define IPASIR_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Name and version of the solver. */
+const char *ipasir_signature(void);
+
+/* A new solver in state INPUT. */
+void *ipasir_init(void);
+
+/* Free a solver and everything it holds. */
+void ipasir_release(void *solver);
+
+/* Add a literal to the clause being built, 0 closes the clause and adds it. */
+void ipasir_add(void *solver, int32_t lit_or_zero);
+
+/* Assume `lit` for the next call to `ipasir_solve` only. */
+void ipasir_assume(void *solver, int32_t lit);
+
+/* 10 if satisfiable, 20 if unsatisfiable, 0 if interrupted by the terminate callback.
+ * Clears the assumptions. */
+int ipasir_solve(void *solver);
+
+/* `lit` if it is true in the model, `-lit` if it is false, 0 if its value does not
+ * matter. */
+int32_t ipasir_val(void *solver, int32_t lit);
+
+/* 1 if assumption `lit` took part in showing unsatisfiability, 0 otherwise. */
+int ipasir_failed(void *solver, int32_t lit);
+
+/* `terminate(data)` is polled during search, a nonzero result stops it. NULL removes the
+ * callback. */
+void ipasir_set_terminate(void *solver, void *data, int (*terminate)(void *data));
+
+/* `learn(data, clause)` gets every learnt clause with at most `max_length` literals, as a
+ * zero terminated array only valid during the call. NULL removes the callback. */
+void ipasir_set_learn(void *solver, void *data, int max_length, void (*learn)(void *data, int32_t *clause));
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
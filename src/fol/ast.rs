@@ -1,6 +1,7 @@
 use std::fmt::{self};
 use std::hash::Hash;
 use std::marker::PhantomData;
+use crate::fol::parser::{is_identifier, quote};
 
 
 #[derive(Debug, Clone)] pub struct Raw;
@@ -11,7 +12,7 @@ use std::marker::PhantomData;
 #[derive(Debug, Clone)] pub struct Cnf;
 
 
-#[derive(Hash, PartialEq, Eq, Clone)]
+#[derive(Hash, PartialEq, Eq, Clone, Default)]
 pub enum Formula<S: 'static> {
     Pred(Pred<S>),
     Not(Not<S>),
@@ -21,14 +22,16 @@ pub enum Formula<S: 'static> {
     Iff(Iff<S>),
     ForAll(ForAll<S>),
     Exists(Exists<S>),
+    #[default]
     Dummy,
 }
 
 
-#[derive(Hash, PartialEq, Eq, Clone)]
+#[derive(Hash, PartialEq, Eq, Clone, Default)]
 pub enum Term {
     Var(Var),
     Func(Func),
+    #[default]
     Dummy,
 }
 
@@ -109,21 +112,19 @@ pub struct Exists<S: 'static> {
 }
 
 
-// Treat grounded predicate as an unique atom
+// Treat grounded predicate as an unique atom, named by its canonical form `P(t1,…,tn)`:
+// terms are written the way they are displayed, which tells variables from constants and
+// quotes names that would not read back, so different atoms never share a name.
 impl<S> Pred<S> {
     pub fn unique(&self) -> String {
-        fn term_name(term: &Term) -> String {
-            match term {
-                Term::Var(v) => v.name.clone(),
-                Term::Func(f) => {
-                    let inner = f.terms.iter().map(term_name).collect::<Vec<_>>().join("");
-                    format!("{}{}", f.name, if inner.is_empty() { "".to_string() } else { format!("_{}", inner) })
-                }
-                Term::Dummy => "dummy".to_string(),
-            }
+        // a nullary predicate is already a propositional symbol: keep its name, so the
+        // symbols of `Clauses::to_formula` read back as the same literals.
+        if self.terms.is_empty() {
+            return self.name.clone();
         }
-        let args = self.terms.iter().map(term_name).collect::<Vec<_>>().join("_");
-        format!("{}_{}", self.name, args)
+        let name = if is_identifier(&self.name) { self.name.clone() } else { quote(&self.name) };
+        let args = self.terms.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(",");
+        format!("{}({})", name, args)
     }
 }
 
@@ -150,15 +151,17 @@ impl Term {
         Term::Func(Func { name: name.to_string(), terms })
     }
 
-    pub fn take(&mut self) -> Self {
-        std::mem::take(self)
+    /* A constant is a function of no arguments, so it is never a free variable. */
+    pub fn constant(name: &str) -> Term {
+        Term::func(name, vec![])
     }
-}
 
+    pub fn is_constant(&self) -> bool {
+        matches!(self, Term::Func(f) if f.terms.is_empty())
+    }
 
-impl Default for Term {
-    fn default() -> Self {
-        Term::Dummy
+    pub fn take(&mut self) -> Self {
+        std::mem::take(self)
     }
 }
 
@@ -218,6 +221,7 @@ impl<S> Formula<S> {
         })
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn not(formula: Formula<S>) -> Formula<S> {
         Formula::Not(Not {
             formula: Box::new(formula),
@@ -273,10 +277,3 @@ impl<S: 'static> fmt::Display for Formula<S> {
         self.display(f)
     }
 }
-
-
-impl<S> Default for Formula<S> {
-    fn default() -> Self {
-        Formula::Dummy
-    }
-}
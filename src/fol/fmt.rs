@@ -1,7 +1,18 @@
 use crate::fol::ast::*;
+use crate::fol::parser::{is_identifier, is_numeral, quote};
 use std::fmt::{self};
 
 
+// a name the parser reads back as itself.
+fn write_name(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+    if is_identifier(name) || is_numeral(name) {
+        write!(f, "{}", name)
+    } else {
+        write!(f, "{}", quote(name))
+    }
+}
+
+
 impl Term {
     pub fn fmt_with_indent(&self, f: &mut fmt::Formatter, indent: usize) -> fmt::Result {
         let indent_str = "  ".repeat(indent);
@@ -16,8 +27,11 @@ impl Term {
     pub fn dislay(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Term::Var(var) => write!(f, "{}", var.name),
+            // numerals and quoted names are constants without the parentheses.
+            Term::Func(func) if func.terms.is_empty() && !is_identifier(&func.name) => write_name(f, &func.name),
             Term::Func(func) => {
-                write!(f, "{}(", func.name)?;
+                write_name(f, &func.name)?;
+                write!(f, "(")?;
                 for (idx, param) in func.terms.iter().enumerate() {
                     write!(f, "{}", param)?;
                     if idx < func.terms.len() - 1 {
@@ -47,37 +61,37 @@ impl<S: 'static> Formula<S> {
                 write!(f, "{}(Not {:?}", indent_str, not.formula)?;
             },
             Formula::And(and) => {
-                write!(f, "{}(And\n", indent_str)?;
+                writeln!(f, "{}(And", indent_str)?;
                 and.formula1.fmt_with_indent(f, indent + 1)?;
-                write!(f, "\n")?;
+                writeln!(f)?;
                 and.formula2.fmt_with_indent(f, indent + 1)?;
             },
             Formula::Or(or) => {
-                write!(f, "{}(Or\n", indent_str)?;
+                writeln!(f, "{}(Or", indent_str)?;
                 or.formula1.fmt_with_indent(f, indent + 1)?;
-                write!(f, "\n")?;
+                writeln!(f)?;
                 or.formula2.fmt_with_indent(f, indent + 1)?;
             },
             Formula::Implies(imp) => {
-                write!(f, "{}(=>\n", indent_str)?;
+                writeln!(f, "{}(=>", indent_str)?;
                 imp.formula1.fmt_with_indent(f, indent + 1)?;
-                write!(f, "\n")?;
+                writeln!(f)?;
                 imp.formula2.fmt_with_indent(f, indent + 1)?;
             },
             Formula::Iff(iff) => {
-                write!(f, "{}(<=>\n", indent_str)?;
+                writeln!(f, "{}(<=>", indent_str)?;
                 iff.formula1.fmt_with_indent(f, indent + 1)?;
-                write!(f, "\n")?;
+                writeln!(f)?;
                 iff.formula2.fmt_with_indent(f, indent + 1)?;
             },
             Formula::ForAll(forall) => {
-                write!(f, "{}(Forall {:?}\n", indent_str, forall.var)?;
+                writeln!(f, "{}(Forall {:?}", indent_str, forall.var)?;
                 forall.formula.fmt_with_indent(f, indent + 1)?;
             },
             Formula::Exists(exists) => {
-                write!(f, "{}(Exists\n", indent_str)?;
+                writeln!(f, "{}(Exists", indent_str)?;
                 exists.var.to_term().fmt_with_indent(f, indent + 1)?;
-                write!(f, "\n")?;
+                writeln!(f)?;
                 exists.formula.fmt_with_indent(f, indent + 1)?;
             }
             Formula::Dummy => {
@@ -94,10 +108,11 @@ impl<S: 'static> Formula<S> {
                 if std::any::TypeId::of::<S>() == std::any::TypeId::of::<Grounded>() {
                     write!(f, "{}", pred.unique())
                 } else {
-                    if pred.terms.len() == 0 {
-                        write!(f, "{}", pred.name)
+                    if pred.terms.is_empty() {
+                        write_name(f, &pred.name)
                     } else {
-                        write!(f, "{}(", pred.name)?;
+                        write_name(f, &pred.name)?;
+                        write!(f, "(")?;
                         for (idx, param) in pred.terms.iter().enumerate() {
                             write!(f, "{}", param)?;
                             if idx < pred.terms.len() - 1 {
@@ -1,7 +1,10 @@
 pub mod ast;
 pub mod parser;
+pub mod problem;
 pub mod fmt;
 pub mod skolem;
+pub mod szs;
+pub mod tptp;
 use std::collections::HashSet;
 use crate::fol::ast::*;
 
@@ -188,7 +191,7 @@ fn formula_substitute<T>(formula: &mut Formula<T>, from: Var, to: Term) {
                     forall.formula.substitute(from, to);
                 }
             }
-            *formula = (&mut taken).take()
+            *formula = taken.take()
         },
         Formula::Exists(_) => {
             let mut taken = formula.take();
@@ -203,7 +206,7 @@ fn formula_substitute<T>(formula: &mut Formula<T>, from: Var, to: Term) {
                     exists.formula.substitute(from, to);
                 }
             }
-            *formula = (&mut taken).take()
+            *formula = taken.take()
         },
         Formula::Dummy => {},
     }
@@ -352,7 +355,7 @@ fn to_cnf(formula: Formula<Grounded>) -> Formula<Grounded> {
         match formula {
             Formula::Or(Or { formula1: mut o1, formula2: mut o2, .. }) => {
                 if let Formula::And(And { formula1: a1, formula2: a2, .. }) = &mut *o2 {
-                    let p = Formula::or(*o1.clone(), (&mut *a1).take());
+                    let p = Formula::or(*o1.clone(), (*a1).take());
                     let q = Formula::or(*o1, (*a2).take());
                     Formula::and(distribute_or(p), distribute_or(q))
                 } else if let Formula::And(And { formula1: a1, formula2: a2, .. }) = &mut *o1 {
@@ -1,5 +1,6 @@
-use std::iter::Peekable;
 use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
 use crate::fol::ast::*;
 
 
@@ -14,329 +15,508 @@ const KEYWORDS: [&str; 7] = [
 ];
 
 
-#[derive(Debug)]
-pub enum ParserError {
+pub(crate) const CONNECTIVES: [&str; 4] = ["and", "or", "=>", "<=>"];
+
+
+/* Where a token is in the input: byte offsets `start..end`, and the line and column of its
+ * start, both counted from 1, columns in characters. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub span: Span,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
     EndOfTokens,
+    /* A function term or constant where a variable is required. */
     WrongTerm,
-    LexerError,
+    /* A character no token starts with, or a quote that is not closed. */
+    LexerError(char),
     Unexpected(String)
 }
 
 
+/* A parse error at `span`, with what would have been accepted there. Tokens are listed as
+ * they are written and classes of tokens in angle brackets, like in the `bnf` file:
+ * `["(", "<identifier>"]`. Lexer errors expect nothing in particular, except a quoted name
+ * that is not closed, which expects its closing `'`.
+ * */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserError {
+    pub kind: ErrorKind,
+    pub span: Span,
+    pub expected: Vec<String>,
+}
+
+
+impl ParserError {
+    pub fn new(kind: ErrorKind, span: Span, expected: &[&str]) -> Self {
+        let mut expected = expected.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        expected.sort();
+        expected.dedup();
+        ParserError { kind, span, expected }
+    }
+
+    // the same error with other expected tokens.
+    pub(crate) fn expecting(self, expected: &[&str]) -> Self {
+        ParserError::new(self.kind, self.span, expected)
+    }
+
+    /* What went wrong, without the location. */
+    pub fn message(&self) -> String {
+        let found = match self.kind {
+            ErrorKind::EndOfTokens => "unexpected end of input".to_string(),
+            ErrorKind::WrongTerm => "only a variable can be bound".to_string(),
+            ErrorKind::LexerError(c) => format!("unexpected character {:?}", c),
+            ErrorKind::Unexpected(ref token) => format!("unexpected {:?}", token),
+        };
+        // classes of tokens like `<identifier>` are not quoted, the `<=>` token is.
+        let quote = |e: &String| if e.starts_with('<') && e.ends_with('>') && e.len() > 3 { e.clone() } else { format!("{:?}", e) };
+        match self.expected.as_slice() {
+            [] => found,
+            [one] => format!("{}, expected {}", found, quote(one)),
+            many => format!("{}, expected one of {}", found, many.iter().map(quote).collect::<Vec<_>>().join(", ")),
+        }
+    }
+
+    /* The error with the line of `source` it is on and a caret under the offending token:
+     *
+     *     error: unexpected "Q", expected one of "<=>", <end of input>, "=>", "and", "or"
+     *       --> line 1, column 6
+     *       |
+     *     1 | P(x) Q(x)
+     *       |      ^
+     * */
+    pub fn render(&self, source: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+        // tabs are kept so the caret lines up however they are displayed.
+        let indent = source[line_start..start].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+        let width = source[start..self.span.end.clamp(start, line_end)].chars().count().max(1);
+        let number = self.span.line.to_string();
+        let margin = " ".repeat(number.len());
+        format!(
+            "error: {}\n{} --> line {}, column {}\n{} |\n{} | {}\n{} | {}{}\n",
+            self.message(), margin, self.span.line, self.span.column, margin, number, line, margin, indent, "^".repeat(width)
+        )
+    }
+}
+
+
 impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "{}:{}: {}", self.span.line, self.span.column, self.message())
     }
 }
 
 
-struct Lexer<'a> {
-    tokens: Vec<&'a str>,
+/* Splits input into tokens, keeping track of where they are. */
+pub(crate) struct Lexer<'a> {
+    tokens: Vec<Token<'a>>,
     input: &'a str,
+    line: usize,
+    column: usize,
 }
 
 
 impl<'a> Lexer<'a> {
-    fn new(input: &'a str) -> Self {
+    pub(crate) fn new(input: &'a str) -> Self {
         Self {
             tokens: vec![],
             input,
+            line: 1,
+            column: 1,
         }
     }
 
-    fn push(&mut self, from: usize, to: usize) {
-        self.tokens.push(&self.input[from..to]);
+    // span of `from..to`, which starts where the last token or skipped input ended.
+    pub(crate) fn span(&self, from: usize, to: usize) -> Span {
+        Span { start: from, end: to, line: self.line, column: self.column }
+    }
+
+    pub(crate) fn push(&mut self, from: usize, to: usize) {
+        let span = self.span(from, to);
+        self.tokens.push(Token { text: &self.input[from..to], span });
+        self.advance(from, to);
     }
 
-    fn lex(&mut self) -> Result<Vec<&'a str>, String> {
-        let mut it = self.input.chars().enumerate().peekable();
+    // skip `from..to`, whitespace or a comment.
+    pub(crate) fn advance(&mut self, from: usize, to: usize) {
+        for c in self.input[from..to].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+
+    fn lex(mut self) -> Result<(Vec<Token<'a>>, Span), ParserError> {
+        let mut it = self.input.char_indices().peekable();
         while let Some((idx, c)) = it.next() {
             match c {
-                _ if c.is_whitespace() => {},
-                '.' | ',' | '(' | ')' => {
+                _ if c.is_whitespace() => {
+                    self.advance(idx, idx + c.len_utf8());
+                },
+                '.' | ',' | '(' | ')' | ':' => {
                     self.push(idx, idx+1);
                 },
-                _ if c.is_alphabetic() || ['=', '>', '<'].contains(&c) => {
-                    let mut end = idx + 1;
-                    while let Some(&(e, c)) = it.peek() {
-                        if c.is_alphabetic() || ['=', '>', '<'].contains(&c) {
-                            it.next();
-                            end = e + 1;
-                        }
-                        else {
-                            break;
-                        }
-                    }
+                // a comment up to the end of the line.
+                '%' => {
+                    let end = take_while(&mut it, idx, c, |c| c != '\n');
+                    self.advance(idx, end);
+                },
+                _ if c.is_ascii_alphabetic() || c == '_' => {
+                    let end = take_while(&mut it, idx, c, |c| c.is_ascii_alphanumeric() || c == '_' || c == '\'');
                     self.push(idx, end);
-                }
+                },
+                _ if c.is_ascii_digit() => {
+                    let end = take_while(&mut it, idx, c, |c| c.is_ascii_digit());
+                    self.push(idx, end);
+                },
+                '=' | '<' | '>' => {
+                    let end = take_while(&mut it, idx, c, |c| ['=', '>', '<'].contains(&c));
+                    self.push(idx, end);
+                },
+                '\'' => {
+                    let mut escaped = false;
+                    let close = it.find(|&(_, c)| {
+                        let closes = c == '\'' && !escaped;
+                        escaped = c == '\\' && !escaped;
+                        closes
+                    });
+                    let Some((end, _)) = close else {
+                        let span = self.span(idx, self.input.len());
+                        return Err(ParserError::new(ErrorKind::LexerError(c), span, &["'"]));
+                    };
+                    self.push(idx, end + 1);
+                },
                 _ => {
-                    return Err(format!("unexpected character {}", c));
+                    let span = self.span(idx, idx + c.len_utf8());
+                    return Err(ParserError::new(ErrorKind::LexerError(c), span, &[]));
                 }
             }
         }
-        Ok(self.tokens.clone())
+        Ok(self.finish())
+    }
+
+    // the tokens and the empty span at the end of the input.
+    pub(crate) fn finish(self) -> (Vec<Token<'a>>, Span) {
+        let end = self.input.len();
+        let span = self.span(end, end);
+        (self.tokens, span)
+    }
+}
+
+
+// end of the token starting with `first` at `start` and going on while `more`.
+pub(crate) fn take_while(it: &mut Peekable<CharIndices>, start: usize, first: char, more: impl Fn(char) -> bool) -> usize {
+    let mut end = start + first.len_utf8();
+    while let Some(&(e, c)) = it.peek() {
+        if !more(c) {
+            break;
+        }
+        it.next();
+        end = e + c.len_utf8();
     }
+    end
 }
 
 
-pub fn lex(input: &str) -> Result<TokenStream, String> {
-    Ok(TokenStream {
-       tokens: Lexer::new(input).lex()?,
-    })
+/* The tokens of `input`, and the empty span at its end. */
+pub fn lex(input: &str) -> Result<(Vec<Token<'_>>, Span), ParserError> {
+    Lexer::new(input).lex()
 }
 
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct TokenStream<'a> {
-    tokens: Vec<&'a str>
+/* A position in the tokens. */
+#[derive(Debug)]
+pub(crate) struct Tokens<'a> {
+    tokens: &'a [Token<'a>],
+    pub(crate) i: usize,
+    end: Span,
 }
 
 
-impl TokenStream<'_> {
-    fn new(tokens: Vec<&str>) -> TokenStream {
-        TokenStream {
-            tokens
-        }
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(tokens: &'a [Token<'a>], end: Span) -> Self {
+        Tokens { tokens, i: 0, end }
+    }
+
+    pub(crate) fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.i).map(|t| t.text)
     }
 
-    fn iter(&mut self) -> TokenStreamIterator<'_> {
-        TokenStreamIterator {
-            stream: self,
-            i: 0
+    pub(crate) fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek()?;
+        self.i += 1;
+        Some(token)
+    }
+
+    // span of the next token, or the end of the input.
+    pub(crate) fn span(&self) -> Span {
+        self.tokens.get(self.i).map_or(self.end, |t| t.span)
+    }
+
+    // span of the tokens from position `from` up to here.
+    pub(crate) fn since(&self, from: usize) -> Span {
+        match (self.tokens.get(from), self.i.checked_sub(1).and_then(|i| self.tokens.get(i))) {
+            (Some(first), Some(last)) if from < self.i => Span { end: last.span.end, ..first.span },
+            _ => self.span(),
         }
     }
+
+    // the next token was not one of `expected`.
+    pub(crate) fn error(&self, expected: &[&str]) -> ParserError {
+        let kind = match self.peek() {
+            Some(token) => ErrorKind::Unexpected(token.to_string()),
+            None => ErrorKind::EndOfTokens,
+        };
+        ParserError::new(kind, self.span(), expected)
+    }
 }
 
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct TokenStreamIterator<'a> {
-    stream: &'a TokenStream<'a>,
-    i: usize,
+/* A name that can be written without quotes: a letter or `_`, then letters, digits, `_`
+ * and `'`, and not a keyword. */
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '\'')
+        && !KEYWORDS.contains(&name)
 }
 
 
-impl<'a> Iterator for TokenStreamIterator<'a> {
-    type Item = &'a str;
+pub fn is_numeral(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_digit())
+}
+
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.i >= self.stream.tokens.len() {
-            None
-        } else {
-            let i = self.i;
-            self.i += 1;
-            Some(self.stream.tokens[i])
+/* `name` in single quotes, with `'` and `\` escaped by a backslash. */
+pub fn quote(name: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in name.chars() {
+        if c == '\'' || c == '\\' {
+            quoted.push('\\');
         }
+        quoted.push(c);
     }
+    quoted.push('\'');
+    quoted
 }
 
 
-type TokenIter<'a> = Peekable<TokenStreamIterator<'a>>;
-
-fn parse_identifier(it: &mut TokenIter) -> Result<String, ParserError> {
-    if let Some(&token) = it.peek() {
-        if token.chars().all(|c| c.is_ascii_alphabetic()) {
-            if KEYWORDS.contains(&token) {
-                return Err(ParserError::Unexpected(token.to_string()))
-            }
-
-            it.next();
-            Ok(token.to_string())
-        } else {
-            Err(ParserError::Unexpected(token.to_string()))
-        }
-    } else {
-        Err(ParserError::EndOfTokens)
+pub(crate) fn unquote(token: &str) -> String {
+    let mut name = String::new();
+    let mut chars = token[1..token.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        name.push(if c == '\\' { chars.next().unwrap_or(c) } else { c });
     }
+    name
 }
 
 
-fn parse_var(it: &mut TokenIter) -> Result<Var, ParserError> {
-    let id = parse_identifier(it)?;
-    Ok(Var { name: id })
+fn is_name(token: &str) -> bool {
+    is_identifier(token) || token.starts_with('\'')
 }
 
 
-fn parse_func(it: &mut TokenIter) -> Result<Func, ParserError> {
-    let id = parse_identifier(it)?;
-    let terms = parse_list(it)?;
-    Ok(Func { name: id, terms })
+// an identifier or a quoted name, as a predicate or function symbol.
+pub(crate) fn parse_identifier(it: &mut Tokens) -> Result<String, ParserError> {
+    match it.peek() {
+        Some(token) if is_name(token) => {
+            it.next();
+            Ok(if is_identifier(token) { token.to_string() } else { unquote(token) })
+        },
+        _ => Err(it.error(&["<identifier>"]))
+    }
 }
 
 
-fn parse_term(it: &mut TokenIter) -> Result<Term, ParserError> {
-    let it1 = it.clone();
-    match parse_func(it) {
-        Ok(s) => Ok(Term::Func(s)),
-        Err(_) => {
-            *it = it1;
-            parse_var(it).map(|var| Term::Var(var))
-        }
+/* A bare identifier is a variable. Constants are applications to no arguments, `a()`, or
+ * numerals and quoted names, which are never variables. */
+fn parse_term(it: &mut Tokens) -> Result<Term, ParserError> {
+    match it.peek() {
+        Some(token) if is_numeral(token) => {
+            it.next();
+            Ok(Term::constant(token))
+        },
+        Some(token) if is_name(token) => {
+            let id = parse_identifier(it)?;
+            if it.peek() == Some("(") {
+                let terms = parse_list(it)?;
+                Ok(Term::Func(Func { name: id, terms }))
+            } else if is_identifier(token) {
+                Ok(Term::Var(Var { name: id }))
+            } else {
+                Ok(Term::constant(&id))
+            }
+        },
+        _ => Err(it.error(&["<term>"]))
     }
 }
 
 
-fn parse_list(it: &mut TokenIter) -> Result<Vec<Term>, ParserError> {
-    if let Some(&"(") = it.peek() {
+fn parse_list(it: &mut Tokens) -> Result<Vec<Term>, ParserError> {
+    parse_keyword(it, "(")?;
+    let mut terms: Vec<Term> = vec![];
+    if it.peek() == Some(")") {
         it.next();
-    } else {
-        match it.peek() {
-            Some(&token) => return Err(ParserError::Unexpected(token.to_string())),
-            None => return Err(ParserError::EndOfTokens)
-        };
+        return Ok(terms);
     }
-
-    let mut c = 0;
-    let mut terms: Vec<Term> = vec![];
-
-    while let Some(&token) = it.peek() {
-        if token == ")" {
-            it.next();
-            return Ok(terms);
-        }
-
-        if c % 2 == 0 { // term
-            let term = parse_term(it)?;
-            terms.push(term);
-        } else { // ,
-            if token == "," {
+    loop {
+        let at = it.span();
+        let term = parse_term(it).map_err(|e| if terms.is_empty() && e.span == at { e.expecting(&["<term>", ")"]) } else { e })?;
+        terms.push(term);
+        match it.peek() {
+            Some(",") => { it.next(); },
+            Some(")") => {
                 it.next();
-            } else {
-                return Err(ParserError::Unexpected(token.to_string()));
-            }
+                return Ok(terms);
+            },
+            _ => return Err(it.error(&[",", ")"]))
         }
-        c += 1;
     }
-    Err(ParserError::EndOfTokens)
 }
 
 
-fn parse_keyword(it: &mut TokenIter, keyword: &str) -> Result<(), ParserError> {
-    if let Some(&token) = it.peek() {
-        if token == keyword {
-            it.next();
-            Ok(())
-        } else {
-            Err(ParserError::Unexpected(token.to_string()))
-        }
+pub(crate) fn parse_keyword(it: &mut Tokens, keyword: &str) -> Result<(), ParserError> {
+    if it.peek() == Some(keyword) {
+        it.next();
+        Ok(())
     } else {
-        Err(ParserError::EndOfTokens)
+        Err(it.error(&[keyword]))
     }
 }
 
 
-fn parse_forall(it: &mut TokenIter) -> Result<Formula<Raw>, ParserError> {
-    let _ = parse_keyword(it, "forall");
-    let var = {
-        match parse_term(it)? {
-            Term::Var(v) => v,
-            _ => return Err(ParserError::WrongTerm)
-        }
+// `<variable> .` after a quantifier.
+fn parse_bound(it: &mut Tokens) -> Result<Var, ParserError> {
+    let (from, at) = (it.i, it.span());
+    let var = match parse_term(it).map_err(|e| if e.span == at { e.expecting(&["<variable>"]) } else { e })? {
+        Term::Var(v) => v,
+        _ => return Err(ParserError::new(ErrorKind::WrongTerm, it.since(from), &["<variable>"]))
     };
-    let _ = parse_keyword(it, ".");
+    parse_keyword(it, ".")?;
+    Ok(var)
+}
+
+
+fn parse_forall(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    parse_keyword(it, "forall")?;
+    let var = parse_bound(it)?;
     let formula = parse_formula(it)?;
     Ok(Formula::forall(var, formula))
 }
 
 
-fn parse_exists(it: &mut TokenIter) -> Result<Formula<Raw>, ParserError> {
-    let _ = parse_keyword(it, "exists");
-
-    let var = {
-        match parse_term(it)? {
-            Term::Var(v) => v,
-            _ => return Err(ParserError::WrongTerm)
-        }
-    };
-
-    let _ = parse_keyword(it, ".");
+fn parse_exists(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    parse_keyword(it, "exists")?;
+    let var = parse_bound(it)?;
     let formula = parse_formula(it)?;
     Ok(Formula::exists(var, formula))
 }
 
 
-fn parse_not(it: &mut TokenIter) -> Result<Formula<Raw>, ParserError> {
-    let _ = parse_keyword(it, "not");
-    let formula = parse_formula(it)?;
+fn parse_not(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    parse_keyword(it, "not")?;
+    let formula = parse_unary(it)?;
     Ok(Formula::not(formula))
 }
 
 
-fn parse_pred(it: &mut TokenIter) -> Result<Formula<Raw>, ParserError> {
+/* A predicate and its arguments, a nullary one with or without the empty parentheses. */
+fn parse_pred(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
     let id = parse_identifier(it)?;
-    let terms = parse_list(it)?;
+    let terms = if it.peek() == Some("(") { parse_list(it)? } else { Vec::new() };
     Ok(Formula::pred(&id, terms))
 }
 
 
-fn parse_simple_formula(it: &mut TokenIter) -> Result<Formula<Raw>, ParserError> {
-    if let Some(&token) = it.peek() {
-        match token {
-            "forall" => parse_forall(it),
-            "exists" => parse_exists(it),
-            "not" => parse_not(it),
-            _ => parse_pred(it)
-        }
-    } else {
-        Err(ParserError::EndOfTokens)
+fn parse_parenthesized(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    parse_keyword(it, "(")?;
+    let formula = parse_formula(it)?;
+    if it.peek() != Some(")") {
+        let mut expected = CONNECTIVES.to_vec();
+        expected.push(")");
+        return Err(it.error(&expected));
     }
+    it.next();
+    Ok(formula)
 }
 
 
-type ToFormula = Box<dyn FnOnce (Formula<Raw>) -> Formula<Raw>>;
-
-fn parse_formula_tail(it: &mut TokenIter) -> Result<Option<ToFormula>, ParserError> {
-    type Op = fn(Formula<Raw>, Formula<Raw>) -> Formula<Raw>;
+/* A formula without a binary connective at the top, unless in parentheses. `not` applies
+ * to the unary formula after it, quantifiers to the whole formula after the dot. */
+fn parse_unary(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    match it.peek() {
+        Some("forall") => parse_forall(it),
+        Some("exists") => parse_exists(it),
+        Some("not") => parse_not(it),
+        Some("(") => parse_parenthesized(it),
+        Some(token) if is_name(token) => parse_pred(it),
+        _ => Err(it.error(&["not", "forall", "exists", "(", "<identifier>"]))
+    }
+}
 
-    let parse_tail = |it: &mut TokenIter, op: Op| -> Result<Option<ToFormula>, ParserError> {
-        it.next();
-        let formula2 = parse_formula(it)?;
-        Ok(Some(Box::new(move |formula1| {
-            op(formula1, formula2)
-        })))
-    };
 
-    if let Some(&token) = it.peek() {
-        match token {
-            "and" => parse_tail(it, Formula::and),
-            "or" => parse_tail(it, Formula::or),
-            "=>" => parse_tail(it, Formula::implies),
-            "<=>" => parse_tail(it, Formula::iff),
-            ")" => Ok(None),
-            _ => return Err(ParserError::Unexpected(token.to_string()))
-        }
+type Op = fn(Formula<Raw>, Formula<Raw>) -> Formula<Raw>;
 
-    } else {
-        Ok(None)
+/* Binding power of a binary connective, tightest highest, and whether it associates to the
+ * right. */
+fn binary(token: &str) -> Option<(u8, bool, Op)> {
+    match token {
+        "and" => Some((4, false, Formula::and)),
+        "or" => Some((3, false, Formula::or)),
+        "=>" => Some((2, true, Formula::implies)),
+        "<=>" => Some((1, false, Formula::iff)),
+        _ => None
     }
 }
 
 
-fn parse_formula(it: &mut TokenIter) -> Result<Formula<Raw>, ParserError> {
-    if let Some(&"(") = it.peek() {
-        let _ = parse_keyword(it, "(")?;
-        let simple = parse_formula(it)?;
-        let _ = parse_keyword(it, ")")?;
-        let tail = parse_formula_tail(it)?;
-        match tail {
-            Some(f) => Ok(f(simple)),
-            None => Ok(simple)
-        }
-    } else {
-        let simple = parse_simple_formula(it)?;
-        let tail = parse_formula_tail(it)?;
-        match tail {
-            Some(f) => Ok(f(simple)),
-            None => Ok(simple)
+/* Precedence climbing: the operands of connectives binding at least `min` tight. */
+fn parse_binary(it: &mut Tokens, min: u8) -> Result<Formula<Raw>, ParserError> {
+    let mut formula = parse_unary(it)?;
+    while let Some((power, right, op)) = it.peek().and_then(binary) {
+        if power < min {
+            break;
         }
+        it.next();
+        let operand = parse_binary(it, if right { power } else { power + 1 })?;
+        formula = op(formula, operand);
     }
+    Ok(formula)
 }
 
 
-pub fn parse(input: &str) -> Result<Formula<Raw>, ParserError> {
-    let mut s = match lex(input) {
-        Ok(s) => s,
-        Err(_) => return Err(ParserError::LexerError)
-    };
+pub(crate) fn parse_formula(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    parse_binary(it, 0)
+}
 
-    parse_formula(&mut s.iter().peekable())
+
+pub fn parse(input: &str) -> Result<Formula<Raw>, ParserError> {
+    let (tokens, end) = lex(input)?;
+    let mut it = Tokens::new(&tokens, end);
+    let formula = parse_formula(&mut it)?;
+    if it.peek().is_some() {
+        let mut expected = CONNECTIVES.to_vec();
+        expected.push("<end of input>");
+        return Err(it.error(&expected));
+    }
+    Ok(formula)
 }
@@ -0,0 +1,287 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::fol::ast::*;
+use crate::fol::parser::{is_numeral, lex, parse_formula, parse_identifier, parse_keyword, unquote, ParserError, Span, Tokens, CONNECTIVES};
+
+
+/* A problem file: named formulas, each with a role, ended by a dot.
+ *
+ *     % comments run to the end of the line.
+ *     include 'set_theory.p'.
+ *     axiom subset: forall x . A(x) => B(x).
+ *     hypothesis a_in_A: A(a()).
+ *     conjecture goal: B(a()).
+ *
+ * An included path is relative to the including file, and its statements take the place of
+ * the `include`. Names are unique across the problem and its includes.
+ * */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Axiom,
+    Hypothesis,
+    Definition,
+    Lemma,
+    Conjecture,
+    /* The negation of a conjecture, an assumption to refute. */
+    NegatedConjecture,
+}
+
+
+impl Role {
+    pub const ALL: [Role; 6] = [
+        Role::Axiom,
+        Role::Hypothesis,
+        Role::Definition,
+        Role::Lemma,
+        Role::Conjecture,
+        Role::NegatedConjecture,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Axiom => "axiom",
+            Role::Hypothesis => "hypothesis",
+            Role::Definition => "definition",
+            Role::Lemma => "lemma",
+            Role::Conjecture => "conjecture",
+            Role::NegatedConjecture => "negated_conjecture",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Role> {
+        Role::ALL.iter().copied().find(|r| r.name() == name)
+    }
+}
+
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub role: Role,
+    pub name: String,
+    pub formula: Formula<Raw>,
+    pub span: Span,
+    /* The file the statement is in, `None` if it was parsed from a string. */
+    pub file: Option<PathBuf>,
+}
+
+
+/* An entry of a problem file, before its includes are read: a statement, or a file to
+ * include with the names of the statements to take from it, all of them if `None`. */
+pub(crate) enum Item {
+    Statement(Statement),
+    Include(String, Option<Vec<String>>),
+}
+
+
+/* How to read a kind of problem file. */
+pub(crate) struct Syntax {
+    pub(crate) parse: fn(&str) -> Result<Vec<Item>, ParserError>,
+    // directories an include is looked up in when it is not next to the including file.
+    pub(crate) roots: Vec<PathBuf>,
+}
+
+
+#[derive(Debug)]
+pub enum ProblemError {
+    Io { path: PathBuf, error: io::Error },
+    Parse { file: Option<PathBuf>, error: ParserError },
+    /* A file that includes itself, directly or not. */
+    Cycle(PathBuf),
+    Duplicate { name: String, file: Option<PathBuf>, span: Span },
+    /* A name selected from an included file that has no statement with it. */
+    Missing { name: String, path: PathBuf },
+}
+
+
+impl fmt::Display for ProblemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let at = |file: &Option<PathBuf>, span: &Span| match file {
+            Some(path) => format!("{}:{}:{}", path.display(), span.line, span.column),
+            None => format!("{}:{}", span.line, span.column),
+        };
+        match self {
+            ProblemError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ProblemError::Parse { file, error } => write!(f, "{}: {}", at(file, &error.span), error.message()),
+            ProblemError::Cycle(path) => write!(f, "{}: included by itself", path.display()),
+            ProblemError::Duplicate { name, file, span } => write!(f, "{}: duplicate name {:?}", at(file, span), name),
+            ProblemError::Missing { name, path } => write!(f, "{}: no statement named {:?}", path.display(), name),
+        }
+    }
+}
+
+
+impl std::error::Error for ProblemError {}
+
+
+#[derive(Debug, Clone, Default)]
+pub struct Problem {
+    pub statements: Vec<Statement>,
+}
+
+
+impl Problem {
+    /* Parse a problem from a string, reading includes relative to the working directory. */
+    pub fn parse(source: &str) -> Result<Problem, ProblemError> {
+        Problem::parse_with(source, &Syntax { parse: parse_items, roots: vec![] })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Problem, ProblemError> {
+        Problem::load_with(path.as_ref(), &Syntax { parse: parse_items, roots: vec![] })
+    }
+
+    pub(crate) fn parse_with(source: &str, syntax: &Syntax) -> Result<Problem, ProblemError> {
+        let mut problem = Problem::default();
+        problem.read(source, None, Path::new(""), syntax, &mut vec![])?;
+        problem.check_names()?;
+        Ok(problem)
+    }
+
+    pub(crate) fn load_with(path: &Path, syntax: &Syntax) -> Result<Problem, ProblemError> {
+        let mut problem = Problem::default();
+        problem.include(path, None, syntax, &mut vec![])?;
+        problem.check_names()?;
+        Ok(problem)
+    }
+
+    // `including` are the files being read, innermost last, to catch cycles.
+    fn include(&mut self, path: &Path, selection: Option<&[String]>, syntax: &Syntax, including: &mut Vec<PathBuf>) -> Result<(), ProblemError> {
+        let io = |error| ProblemError::Io { path: path.to_path_buf(), error };
+        let canonical = fs::canonicalize(path).map_err(io)?;
+        if including.contains(&canonical) {
+            return Err(ProblemError::Cycle(path.to_path_buf()));
+        }
+        let source = fs::read_to_string(path).map_err(io)?;
+        including.push(canonical);
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut included = Problem::default();
+        included.read(&source, Some(path), dir, syntax, including)?;
+        including.pop();
+        if let Some(names) = selection {
+            if let Some(name) = names.iter().find(|&n| included.get(n).is_none()) {
+                return Err(ProblemError::Missing { name: name.clone(), path: path.to_path_buf() });
+            }
+            included.statements.retain(|s| names.contains(&s.name));
+        }
+        self.statements.extend(included.statements);
+        Ok(())
+    }
+
+    fn read(&mut self, source: &str, file: Option<&Path>, dir: &Path, syntax: &Syntax, including: &mut Vec<PathBuf>) -> Result<(), ProblemError> {
+        let items = (syntax.parse)(source).map_err(|error| ProblemError::Parse { file: file.map(Path::to_path_buf), error })?;
+        for item in items {
+            match item {
+                Item::Statement(statement) => self.statements.push(Statement { file: file.map(Path::to_path_buf), ..statement }),
+                Item::Include(path, selection) => {
+                    let near = dir.join(&path);
+                    let found = syntax.roots.iter().map(|root| root.join(&path)).find(|p| !near.exists() && p.exists());
+                    self.include(&found.unwrap_or(near), selection.as_deref(), syntax, including)?
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn check_names(&self) -> Result<(), ProblemError> {
+        let mut seen = HashSet::new();
+        for statement in self.statements.iter() {
+            if !seen.insert(statement.name.as_str()) {
+                let Statement { name, file, span, .. } = statement.clone();
+                return Err(ProblemError::Duplicate { name, file, span });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Statement> {
+        self.statements.iter().find(|s| s.name == name)
+    }
+
+    /* Every statement but the conjectures. */
+    pub fn assumptions(&self) -> impl Iterator<Item = &Statement> {
+        self.statements.iter().filter(|s| s.role != Role::Conjecture)
+    }
+
+    pub fn conjectures(&self) -> impl Iterator<Item = &Statement> {
+        self.statements.iter().filter(|s| s.role == Role::Conjecture)
+    }
+
+    /* The problem as one formula: the conjunction of the assumptions implying the
+     * conjunction of the conjectures, valid exactly when the conjectures follow. Without
+     * conjectures it is the conjunction of the assumptions, to be checked for satisfiability.
+     * `None` for an empty problem.
+     * */
+    pub fn to_formula(&self) -> Option<Formula<Raw>> {
+        let conjoin = |statements: Vec<&Statement>| statements.into_iter().map(|s| s.formula.clone()).reduce(Formula::and);
+        let assumptions = conjoin(self.assumptions().collect());
+        match (assumptions, conjoin(self.conjectures().collect())) {
+            (Some(assumptions), Some(conjecture)) => Some(Formula::implies(assumptions, conjecture)),
+            (assumptions, None) => assumptions,
+            (None, conjecture) => conjecture,
+        }
+    }
+}
+
+
+// `<role> <name> : <formula> .` or `include '<path>' .`
+fn parse_item(it: &mut Tokens) -> Result<Item, ParserError> {
+    let from = it.i;
+    if it.peek() == Some("include") {
+        it.next();
+        let path = match it.peek() {
+            Some(token) if token.starts_with('\'') => {
+                it.next();
+                unquote(token)
+            },
+            _ => return Err(it.error(&["<quoted>"])),
+        };
+        parse_keyword(it, ".")?;
+        return Ok(Item::Include(path, None));
+    }
+    let role = match it.peek().and_then(Role::from_name) {
+        Some(role) => role,
+        None => {
+            let mut expected = Role::ALL.iter().map(|r| r.name()).collect::<Vec<_>>();
+            expected.push("include");
+            return Err(it.error(&expected));
+        }
+    };
+    it.next();
+    let name = match it.peek() {
+        Some(token) if is_numeral(token) => {
+            it.next();
+            token.to_string()
+        },
+        _ => parse_identifier(it).map_err(|e| e.expecting(&["<name>"]))?,
+    };
+    parse_keyword(it, ":")?;
+    let formula = parse_formula(it)?;
+    if it.peek() != Some(".") {
+        let mut expected = CONNECTIVES.to_vec();
+        expected.push(".");
+        return Err(it.error(&expected));
+    }
+    it.next();
+    Ok(Item::Statement(Statement { role, name, formula, span: it.since(from), file: None }))
+}
+
+
+fn parse_items(input: &str) -> Result<Vec<Item>, ParserError> {
+    let (tokens, end) = lex(input)?;
+    let mut it = Tokens::new(&tokens, end);
+    let mut items = vec![];
+    while it.peek().is_some() {
+        items.push(parse_item(&mut it)?);
+    }
+    Ok(items)
+}
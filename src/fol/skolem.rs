@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use crate::fol::ast::*;
 
 
@@ -5,6 +6,13 @@ pub struct SkolemState {
     counter: usize,
 }
 
+impl Default for SkolemState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
 impl SkolemState {
     pub fn new() -> Self {
         SkolemState { counter: 0 }
@@ -26,6 +34,8 @@ impl SkolemState {
  * `forall a. forall b. exists c P(a) and P(b) and P(c)`
  * becomes
  * `forall a. forall b. P(a) and P(b) and P(sk1(a, b))`
+ * and without universal quantifiers the skolem term is a constant `sk1()`, never a free
+ * variable. Skolem symbols are named apart from the functions and constants in `formula`.
  *
  * The resulting formula is Equisatisfiable to the original one.
  * */
@@ -33,12 +43,14 @@ pub fn skolemize(mut formula: Formula<Pnf>) -> Formula<Skolemized> {
     let mut uvars = vec![];
     collect_universal_vars(&formula, &mut uvars);
     let uvars = uvars;
+    let mut taken = HashSet::new();
+    collect_functions(&formula, &mut taken);
     let mut state = SkolemState::new();
     loop {
         match formula {
             Formula::Exists(mut exists) => {
                 let var = exists.var.clone();
-                let skolem = fresh_skolem(&uvars, &mut state);
+                let skolem = fresh_skolem(&uvars, &mut state, &taken);
                 first_non_quantified(&mut exists.formula).substitute(var, skolem);
                 formula = *exists.formula;
             },
@@ -68,15 +80,37 @@ fn first_non_quantified(formula: &mut Formula<Pnf>) -> &mut Formula<Pnf> {
 }
 
 
-fn fresh_skolem(uvars: &[String], state: &mut SkolemState) -> Term {
-    if uvars.len() == 0 {
-        let name = state.fresh_skolem();
-        Term::var(&name)
+// a skolem constant, or function of the universal variables, named apart from the
+// function symbols and constants already in the formula.
+fn fresh_skolem(uvars: &[String], state: &mut SkolemState, taken: &HashSet<String>) -> Term {
+    let mut fname = state.fresh_skolem();
+    while taken.contains(&fname) {
+        fname = state.fresh_skolem();
+    }
+    let params = uvars.iter().map(|str| Term::var(str)).collect::<Vec<Term>>();
+    Term::func(&fname, params)
+}
 
-    } else {
-        let fname = state.fresh_skolem();
-        let params = uvars.iter().map(|str| Term::var(str)).collect::<Vec<Term>>();
-        Term::func(&fname, params)
+
+fn collect_functions<S>(formula: &Formula<S>, out: &mut HashSet<String>) {
+    fn term(t: &Term, out: &mut HashSet<String>) {
+        if let Term::Func(func) = t {
+            out.insert(func.name.clone());
+            func.terms.iter().for_each(|t| term(t, out));
+        }
+    }
+    match formula {
+        Formula::Pred(pred) => pred.terms.iter().for_each(|t| term(t, out)),
+        Formula::Not(not) => collect_functions(&not.formula, out),
+        Formula::And(And { formula1, formula2, .. })
+        | Formula::Or(Or { formula1, formula2, .. })
+        | Formula::Implies(Implies { formula1, formula2, .. })
+        | Formula::Iff(Iff { formula1, formula2, .. }) => {
+            collect_functions(formula1, out);
+            collect_functions(formula2, out);
+        },
+        Formula::ForAll(ForAll { formula, .. }) | Formula::Exists(Exists { formula, .. }) => collect_functions(formula, out),
+        Formula::Dummy => {}
     }
 }
 
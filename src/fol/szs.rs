@@ -0,0 +1,94 @@
+use std::fmt;
+
+use crate::fol::problem::Problem;
+
+
+/* SZS statuses, as TPTP based tools report the outcome of an attempt on a problem:
+ *
+ *     % SZS status Theorem for SET001+1
+ *
+ * and SZS output blocks around what they print to back it, like a refutation or a model.
+ * */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    /* The conjectures follow from the axioms. */
+    Theorem,
+    /* Some model of the axioms falsifies the conjectures. */
+    CounterSatisfiable,
+    /* A problem without conjectures has no model. */
+    Unsatisfiable,
+    Satisfiable,
+    Timeout,
+    /* Stopped without an answer for another reason. */
+    GaveUp,
+}
+
+
+impl Status {
+    pub const ALL: [Status; 6] = [
+        Status::Theorem,
+        Status::CounterSatisfiable,
+        Status::Unsatisfiable,
+        Status::Satisfiable,
+        Status::Timeout,
+        Status::GaveUp,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Theorem => "Theorem",
+            Status::CounterSatisfiable => "CounterSatisfiable",
+            Status::Unsatisfiable => "Unsatisfiable",
+            Status::Satisfiable => "Satisfiable",
+            Status::Timeout => "Timeout",
+            Status::GaveUp => "GaveUp",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Status> {
+        Status::ALL.iter().copied().find(|s| s.name() == name)
+    }
+
+    /* The status of `problem` given whether `Problem::to_formula` holds: whether it is
+     * valid when there are conjectures, satisfiable when there are none. `None` if that was
+     * not decided.
+     * */
+    pub fn of(problem: &Problem, holds: Option<bool>) -> Status {
+        let conjectures = problem.conjectures().next().is_some();
+        match (holds, conjectures) {
+            (None, _) => Status::GaveUp,
+            (Some(true), true) => Status::Theorem,
+            (Some(false), true) => Status::CounterSatisfiable,
+            (Some(true), false) => Status::Satisfiable,
+            (Some(false), false) => Status::Unsatisfiable,
+        }
+    }
+
+    /* True if the problem was decided. */
+    pub fn is_success(&self) -> bool {
+        !matches!(self, Status::Timeout | Status::GaveUp)
+    }
+
+    /* `% SZS status <status> for <problem>` */
+    pub fn line(&self, problem: &str) -> String {
+        format!("% SZS status {} for {}", self, problem)
+    }
+}
+
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+
+/* `body` between the SZS output start and end lines, `dataform` saying what it is, like
+ * `CNFRefutation`, `FiniteModel` or `ListOfFormulae`. */
+pub fn output(dataform: &str, problem: &str, body: &str) -> String {
+    let newline = if body.is_empty() || body.ends_with('\n') { "" } else { "\n" };
+    format!(
+        "% SZS output start {} for {}\n{}{}% SZS output end {} for {}\n",
+        dataform, problem, body, newline, dataform, problem
+    )
+}
@@ -0,0 +1,790 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::fol::ast::*;
+use crate::fol::parser::{quote, unquote, ErrorKind, Lexer, ParserError, Span, Token, Tokens};
+use crate::fol::problem::{Item, Problem, ProblemError, Role, Statement, Syntax};
+use crate::sat::clauses::Clauses;
+
+
+/* A reader and writer for the first-order forms of the TPTP language, `fof(...)` and `cnf(...)`:
+ *
+ *     % the subset relation is transitive.
+ *     include('Axioms/SET001-0.ax', [subset_def]).
+ *     fof(trans, conjecture, ! [A, B, C] : ((subset(A, B) & subset(B, C)) => subset(A, C))).
+ *     cnf(a_in_b, axiom, member(a, b) | ~ member(c, b)).
+ *
+ * Terms follow the convention of `fol::parser`: TPTP variables, the words starting with an
+ * upper case letter, are variables, and every other name that is not applied to arguments is
+ * a constant, `a()`. Numbers are constants named by their text and distinct objects constants
+ * named by their text with the double quotes, so they stay apart from names.
+ *
+ * Each formula is universally closed over its free variables, which are implicit in `cnf`.
+ * Equality is the predicate `=` and `X != Y` stands for `¬(X = Y)`, with no axioms of its
+ * own. `$true` and `$false` have no counterpart in the formulas, so they are read as the
+ * tautology and the contradiction `truth` builds.
+ *
+ * TPTP roles map to the closest `Role`: `plain` and `unknown` are axioms, `assumption` a
+ * hypothesis, and `theorem` and `corollary` lemmas. An include not found next to the
+ * including file is looked up in the directory named by the `TPTP` environment variable.
+ * */
+
+
+// operators, each before the ones it starts with.
+const OPERATORS: [&str; 13] = ["<~>", "<=>", "=>", "<=", "~|", "~&", "!=", "~", "&", "|", "!", "?", "="];
+
+
+const BINARY: [&str; 8] = ["&", "|", "=>", "<=", "<=>", "<~>", "~|", "~&"];
+
+
+const ROLES: [&str; 11] = [
+    "axiom",
+    "hypothesis",
+    "definition",
+    "assumption",
+    "lemma",
+    "theorem",
+    "corollary",
+    "conjecture",
+    "negated_conjecture",
+    "plain",
+    "unknown",
+];
+
+
+/* The atom `truth` builds `$true` and `$false` from. */
+pub const TRUTH: &str = "$truth";
+
+
+/* `$true` as `$truth ∨ ¬$truth`, `$false` as `$truth ∧ ¬$truth`. */
+pub fn truth<S>(value: bool) -> Formula<S> {
+    let atom = || Formula::pred(TRUTH, vec![]);
+    if value {
+        Formula::or(atom(), Formula::not(atom()))
+    } else {
+        Formula::and(atom(), Formula::not(atom()))
+    }
+}
+
+
+pub fn parse(source: &str) -> Result<Problem, ProblemError> {
+    Problem::parse_with(source, &syntax())
+}
+
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Problem, ProblemError> {
+    Problem::load_with(path.as_ref(), &syntax())
+}
+
+
+/* A single `fof` formula, closed like the formulas of a problem. */
+pub fn parse_formula(input: &str) -> Result<Formula<Raw>, ParserError> {
+    let (tokens, end) = lex(input)?;
+    let mut it = Tokens::new(&tokens, end);
+    let formula = parse_fof(&mut it)?;
+    if it.peek().is_some() {
+        let mut expected = BINARY.to_vec();
+        expected.push("<end of input>");
+        return Err(it.error(&expected));
+    }
+    Ok(close(formula))
+}
+
+
+fn syntax() -> Syntax {
+    Syntax { parse: parse_items, roots: env::var_os("TPTP").map(PathBuf::from).into_iter().collect() }
+}
+
+
+/* The tokens of TPTP `input`, and the empty span at its end. Comments are `%` to the end of
+ * the line and `/* ... */`. */
+pub fn lex(input: &str) -> Result<(Vec<Token<'_>>, Span), ParserError> {
+    let bytes = input.as_bytes();
+    let scan = |from: usize, more: fn(u8) -> bool| from + bytes[from..].iter().take_while(|&&b| more(b)).count();
+    let word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let mut lexer = Lexer::new(input);
+    let mut i = 0;
+    while let Some(c) = input[i..].chars().next() {
+        let error = |lexer: &Lexer, expected: &[&str]| {
+            let span = lexer.span(i, if expected.is_empty() { i + c.len_utf8() } else { input.len() });
+            Err(ParserError::new(ErrorKind::LexerError(c), span, expected))
+        };
+        let end = match c {
+            _ if c.is_whitespace() => {
+                lexer.advance(i, i + c.len_utf8());
+                i += c.len_utf8();
+                continue;
+            },
+            '%' => {
+                let end = scan(i, |b| b != b'\n');
+                lexer.advance(i, end);
+                i = end;
+                continue;
+            },
+            '/' if input[i..].starts_with("/*") => {
+                let Some(close) = input[i + 2..].find("*/") else {
+                    return error(&lexer, &["*/"]);
+                };
+                lexer.advance(i, i + close + 4);
+                i += close + 4;
+                continue;
+            },
+            '(' | ')' | '[' | ']' | ',' | '.' | ':' => i + 1,
+            '\'' | '"' => {
+                let quote = c as u8;
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != quote {
+                    j += if bytes[j] == b'\\' { 2 } else { 1 };
+                }
+                if j >= bytes.len() {
+                    return error(&lexer, &[if c == '"' { "\"" } else { "'" }]);
+                }
+                j + 1
+            },
+            '$' => scan(i + 1, |b| b == b'$' || b.is_ascii_alphanumeric() || b == b'_'),
+            _ if c.is_ascii_alphabetic() => scan(i, word),
+            _ if c.is_ascii_digit() || ((c == '+' || c == '-') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) => number_end(bytes, i),
+            _ => match OPERATORS.iter().find(|op| input[i..].starts_with(**op)) {
+                Some(op) => i + op.len(),
+                None => return error(&lexer, &[]),
+            },
+        };
+        lexer.push(i, end);
+        i = end;
+    }
+    Ok(lexer.finish())
+}
+
+
+// end of the integer, rational `n/d` or real `n.f` with an exponent starting at `start`.
+fn number_end(bytes: &[u8], start: usize) -> usize {
+    let digits = |from: usize| from + bytes[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let digit_at = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+    let mut end = digits(start + usize::from(!bytes[start].is_ascii_digit()));
+    if matches!(bytes.get(end), Some(b'.' | b'/')) && digit_at(end + 1) {
+        end = digits(end + 1);
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        if digit_at(end + 1 + sign) {
+            end = digits(end + 1 + sign);
+        }
+    }
+    end
+}
+
+
+fn is_lower_word(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_lowercase())
+}
+
+
+fn is_upper_word(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+
+fn is_number(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-')
+}
+
+
+fn is_integer(token: &str) -> bool {
+    token.chars().all(|c| c.is_ascii_digit())
+}
+
+
+fn role(name: &str) -> Option<Role> {
+    match name {
+        "axiom" | "plain" | "unknown" => Some(Role::Axiom),
+        "hypothesis" | "assumption" => Some(Role::Hypothesis),
+        "definition" => Some(Role::Definition),
+        "lemma" | "theorem" | "corollary" => Some(Role::Lemma),
+        "conjecture" => Some(Role::Conjecture),
+        "negated_conjecture" => Some(Role::NegatedConjecture),
+        _ => None
+    }
+}
+
+
+fn expect(it: &mut Tokens, token: &str) -> Result<(), ParserError> {
+    if it.peek() == Some(token) {
+        it.next();
+        Ok(())
+    } else {
+        Err(it.error(&[token]))
+    }
+}
+
+
+pub(crate) fn parse_items(input: &str) -> Result<Vec<Item>, ParserError> {
+    let (tokens, end) = lex(input)?;
+    let mut it = Tokens::new(&tokens, end);
+    let mut items = vec![];
+    while it.peek().is_some() {
+        items.push(parse_item(&mut it)?);
+    }
+    Ok(items)
+}
+
+
+// `include('<path>'[, [<name>, ...]]).`, `fof(<name>, <role>, <formula>[, ...]).` or the
+// same with `cnf`.
+fn parse_item(it: &mut Tokens) -> Result<Item, ParserError> {
+    let from = it.i;
+    let language = match it.peek() {
+        Some(token @ ("include" | "fof" | "cnf")) => token,
+        _ => return Err(it.error(&["cnf", "fof", "include"])),
+    };
+    it.next();
+    expect(it, "(")?;
+    if language == "include" {
+        let path = match it.peek() {
+            Some(token) if token.starts_with('\'') => {
+                it.next();
+                unquote(token)
+            },
+            _ => return Err(it.error(&["<quoted>"])),
+        };
+        let selection = match it.peek() {
+            Some(",") => {
+                it.next();
+                Some(parse_names(it)?)
+            },
+            Some(")") => None,
+            _ => return Err(it.error(&[",", ")"])),
+        };
+        expect(it, ")")?;
+        expect(it, ".")?;
+        return Ok(Item::Include(path, selection));
+    }
+    let name = parse_name(it)?;
+    expect(it, ",")?;
+    let role = match it.peek().and_then(role) {
+        Some(role) => role,
+        None => return Err(it.error(&ROLES)),
+    };
+    it.next();
+    expect(it, ",")?;
+    let (formula, mut expected) = if language == "fof" {
+        (parse_fof(it)?, BINARY.to_vec())
+    } else {
+        (parse_cnf(it)?, vec!["|"])
+    };
+    match it.peek() {
+        Some(",") => skip_annotations(it)?,
+        Some(")") => {},
+        _ => {
+            expected.extend([",", ")"]);
+            return Err(it.error(&expected));
+        },
+    }
+    expect(it, ")")?;
+    expect(it, ".")?;
+    Ok(Item::Statement(Statement { role, name, formula: close(formula), span: it.since(from), file: None }))
+}
+
+
+fn parse_name(it: &mut Tokens) -> Result<String, ParserError> {
+    match it.peek() {
+        Some(token) if is_lower_word(token) || is_integer(token) => {
+            it.next();
+            Ok(token.to_string())
+        },
+        Some(token) if token.starts_with('\'') => {
+            it.next();
+            Ok(unquote(token))
+        },
+        _ => Err(it.error(&["<name>"])),
+    }
+}
+
+
+// `[<name>, ...]`
+fn parse_names(it: &mut Tokens) -> Result<Vec<String>, ParserError> {
+    expect(it, "[")?;
+    let mut names = vec![];
+    if it.peek() == Some("]") {
+        it.next();
+        return Ok(names);
+    }
+    loop {
+        names.push(parse_name(it)?);
+        match it.peek() {
+            Some(",") => { it.next(); },
+            Some("]") => {
+                it.next();
+                return Ok(names);
+            },
+            _ => return Err(it.error(&[",", "]"])),
+        }
+    }
+}
+
+
+// the source and useful info after a formula, up to the closing parenthesis.
+fn skip_annotations(it: &mut Tokens) -> Result<(), ParserError> {
+    let mut depth = 0;
+    loop {
+        match it.peek() {
+            Some(")") if depth == 0 => return Ok(()),
+            Some("(" | "[") => depth += 1,
+            Some(")" | "]") => depth -= 1,
+            Some(_) => {},
+            None => return Err(it.error(&[")"])),
+        }
+        it.next();
+    }
+}
+
+
+// the universal closure of `formula`, its free variables by name.
+fn close(formula: Formula<Raw>) -> Formula<Raw> {
+    let mut vars = formula.free_vars().into_iter().collect::<Vec<_>>();
+    vars.sort_by(|a, b| a.name.cmp(&b.name));
+    vars.into_iter().rev().fold(formula, |formula, var| Formula::forall(var, formula))
+}
+
+
+/* A binary formula or a unitary one. `&` and `|` associate, to the left, but only with
+ * themselves; the other connectives need parentheses around a binary operand. */
+fn parse_fof(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    let mut formula = parse_unitary(it)?;
+    let Some(op) = it.peek().filter(|op| BINARY.contains(op)) else {
+        return Ok(formula);
+    };
+    it.next();
+    if op == "&" || op == "|" {
+        loop {
+            let operand = parse_unitary(it)?;
+            formula = if op == "&" { Formula::and(formula, operand) } else { Formula::or(formula, operand) };
+            if it.peek() != Some(op) {
+                return Ok(formula);
+            }
+            it.next();
+        }
+    }
+    let right = parse_unitary(it)?;
+    Ok(match op {
+        "=>" => Formula::implies(formula, right),
+        "<=" => Formula::implies(right, formula),
+        "<=>" => Formula::iff(formula, right),
+        "<~>" => Formula::not(Formula::iff(formula, right)),
+        "~|" => Formula::not(Formula::or(formula, right)),
+        _ => Formula::not(Formula::and(formula, right)),
+    })
+}
+
+
+fn parse_unitary(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    match it.peek() {
+        Some(quantifier @ ("!" | "?")) => {
+            it.next();
+            let vars = parse_vars(it)?;
+            expect(it, ":")?;
+            let formula = parse_unitary(it)?;
+            Ok(vars.into_iter().rev().fold(formula, |formula, var| {
+                if quantifier == "!" { Formula::forall(var, formula) } else { Formula::exists(var, formula) }
+            }))
+        },
+        Some("~") => {
+            it.next();
+            Ok(Formula::not(parse_unitary(it)?))
+        },
+        Some("(") => {
+            it.next();
+            let formula = parse_fof(it)?;
+            if it.peek() != Some(")") {
+                let mut expected = BINARY.to_vec();
+                expected.push(")");
+                return Err(it.error(&expected));
+            }
+            it.next();
+            Ok(formula)
+        },
+        _ => {
+            let at = it.span();
+            parse_atomic(it).map_err(|e| if e.span == at { e.expecting(&["!", "(", "<atom>", "?", "~"]) } else { e })
+        }
+    }
+}
+
+
+// `[<variable>, ...]`
+fn parse_vars(it: &mut Tokens) -> Result<Vec<Var>, ParserError> {
+    expect(it, "[")?;
+    let mut vars = vec![];
+    loop {
+        match it.peek() {
+            Some(token) if is_upper_word(token) => {
+                it.next();
+                vars.push(Var::from_string(token.to_string()));
+            },
+            _ => return Err(it.error(&["<variable>"])),
+        }
+        match it.peek() {
+            Some(",") => { it.next(); },
+            Some("]") => {
+                it.next();
+                return Ok(vars);
+            },
+            _ => return Err(it.error(&[",", "]"])),
+        }
+    }
+}
+
+
+/* A clause, a disjunction of literals in parentheses or not. */
+fn parse_cnf(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    if it.peek() != Some("(") {
+        return parse_disjunction(it);
+    }
+    it.next();
+    let clause = parse_disjunction(it)?;
+    if it.peek() != Some(")") {
+        return Err(it.error(&["|", ")"]));
+    }
+    it.next();
+    Ok(clause)
+}
+
+
+fn parse_disjunction(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    let mut clause = parse_literal(it)?;
+    while it.peek() == Some("|") {
+        it.next();
+        clause = Formula::or(clause, parse_literal(it)?);
+    }
+    Ok(clause)
+}
+
+
+fn parse_literal(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    if it.peek() == Some("~") {
+        it.next();
+        return Ok(Formula::not(parse_atomic(it)?));
+    }
+    let at = it.span();
+    parse_atomic(it).map_err(|e| if e.span == at { e.expecting(&["<atom>", "~"]) } else { e })
+}
+
+
+/* `$true`, `$false`, an equation, or a predicate applied to terms. */
+fn parse_atomic(it: &mut Tokens) -> Result<Formula<Raw>, ParserError> {
+    match it.peek() {
+        Some("$true") => {
+            it.next();
+            return Ok(truth(true));
+        },
+        Some("$false") => {
+            it.next();
+            return Ok(truth(false));
+        },
+        _ => {},
+    }
+    // decided on the token, a quoted name is a predicate whatever it holds.
+    let predicate = it.peek().is_some_and(|token| !is_number(token) && !token.starts_with('"'));
+    let term = parse_term(it)?;
+    match it.peek() {
+        Some(eq @ ("=" | "!=")) => {
+            it.next();
+            let equation = Formula::pred("=", vec![term, parse_term(it)?]);
+            Ok(if eq == "=" { equation } else { Formula::not(equation) })
+        },
+        _ => match term {
+            Term::Func(func) if predicate => Ok(Formula::pred(&func.name, func.terms)),
+            // a variable, number or distinct object, which only an equation can start with.
+            _ => Err(it.error(&["!=", "="])),
+        },
+    }
+}
+
+
+fn parse_term(it: &mut Tokens) -> Result<Term, ParserError> {
+    let token = match it.peek() {
+        Some(token) => token,
+        None => return Err(it.error(&["<term>"])),
+    };
+    if is_upper_word(token) {
+        it.next();
+        return Ok(Term::var(token));
+    }
+    if is_number(token) || token.starts_with('"') {
+        it.next();
+        return Ok(Term::constant(token));
+    }
+    if !(is_lower_word(token) || token.starts_with('\'') || token.starts_with('$')) {
+        return Err(it.error(&["<term>"]));
+    }
+    it.next();
+    let name = if token.starts_with('\'') { unquote(token) } else { token.to_string() };
+    if it.peek() != Some("(") {
+        return Ok(Term::constant(&name));
+    }
+    it.next();
+    let mut terms = vec![];
+    loop {
+        terms.push(parse_term(it)?);
+        match it.peek() {
+            Some(",") => { it.next(); },
+            Some(")") => {
+                it.next();
+                return Ok(Term::func(&name, terms));
+            },
+            _ => return Err(it.error(&[",", ")"])),
+        }
+    }
+}
+
+
+/* Writing */
+
+
+/* `formula` in TPTP syntax, closed over its free variables like the formulas read.
+ *
+ * Variables are renamed to TPTP variables, the first letter upper cased or an `X` put in
+ * front, and numbered apart when two would collide. Other names are written as they are
+ * when TPTP allows it and single quoted otherwise, and constants without the parentheses.
+ * Every binary formula is parenthesized, the encodings of `truth` become `$true` and
+ * `$false`, and `¬(s = t)` becomes `s != t`.
+ * */
+pub fn write_formula<S>(formula: &Formula<S>) -> String {
+    let mut vars = formula.free_vars().into_iter().map(|v| v.name).collect::<Vec<_>>();
+    vars.sort();
+    let writer = Writer::new(formula);
+    let mut out = String::new();
+    if !vars.is_empty() {
+        let vars = vars.iter().map(|v| writer.vars[v].as_str()).collect::<Vec<_>>();
+        out.push_str(&format!("! [{}] : ", vars.join(", ")));
+    }
+    writer.formula(formula, &mut out);
+    out
+}
+
+
+/* `fof(<name>, <role>, <formula>).` */
+pub fn write_annotated<S>(name: &str, role: Role, formula: &Formula<S>) -> String {
+    format!("fof({}, {}, {}).", write_name(name), role, write_formula(formula))
+}
+
+
+/* Every statement of `problem` on a line of its own. */
+pub fn write_problem(problem: &Problem) -> String {
+    problem.statements.iter().map(|s| write_annotated(&s.name, s.role, &s.formula) + "\n").collect()
+}
+
+
+/* Propositional clauses as `cnf(c<i>, <role>, <literals>).`, counting from 1, the literals of
+ * a clause by variable name and the empty clause as `$false`. */
+pub fn write_clauses(clauses: &Clauses, role: Role) -> String {
+    let mut out = String::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        let mut literals = clause.iter().collect::<Vec<_>>();
+        literals.sort_by_key(|l| (l.var_name(), l.is_negated()));
+        let literals = literals
+            .into_iter()
+            .map(|l| format!("{}{}", if l.is_negated() { "~ " } else { "" }, write_functor(l.var_name())))
+            .collect::<Vec<_>>();
+        let body = if literals.is_empty() { "$false".to_string() } else { literals.join(" | ") };
+        out.push_str(&format!("cnf(c{}, {}, {}).\n", i + 1, role, body));
+    }
+    out
+}
+
+
+// a predicate or functor name, quoted unless it is a lower or `$` word: other names do not
+// start an atom or a term with arguments.
+fn write_functor(name: &str) -> String {
+    let word = |s: &str| s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+    let plain = (is_lower_word(name) && word(name)) || (name.len() > 1 && name.starts_with('$') && word(&name[1..]));
+    if plain { name.to_string() } else { quote(name) }
+}
+
+
+// a constant, which may also be a number or a distinct object.
+fn write_constant(name: &str) -> String {
+    let plain = (is_number(name) && number_end(name.as_bytes(), 0) == name.len() && name.bytes().any(|b| b.is_ascii_digit()))
+        || (name.len() > 1 && name.starts_with('"') && name.ends_with('"'));
+    if plain { name.to_string() } else { write_functor(name) }
+}
+
+
+// a formula name, which may also be an integer.
+fn write_name(name: &str) -> String {
+    if !name.is_empty() && is_integer(name) { name.to_string() } else { write_functor(name) }
+}
+
+
+// the variables of a formula and the TPTP names they are written with.
+struct Writer {
+    vars: HashMap<String, String>,
+}
+
+
+impl Writer {
+    fn new<S>(formula: &Formula<S>) -> Self {
+        let mut names = BTreeSet::new();
+        collect_vars(formula, &mut names);
+        let mut vars = HashMap::new();
+        let mut used = HashSet::new();
+        for name in names {
+            let word = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect::<String>();
+            let base = match word.chars().next() {
+                Some(c) if c.is_ascii_uppercase() => word,
+                Some(c) if c.is_ascii_lowercase() => c.to_ascii_uppercase().to_string() + &word[1..],
+                _ => format!("X{}", word),
+            };
+            let mut fresh = base.clone();
+            for i in 1.. {
+                if !used.contains(&fresh) {
+                    break;
+                }
+                fresh = format!("{}{}", base, i);
+            }
+            used.insert(fresh.clone());
+            vars.insert(name, fresh);
+        }
+        Writer { vars }
+    }
+
+    fn term(&self, term: &Term, out: &mut String) {
+        match term {
+            Term::Var(var) => out.push_str(&self.vars[&var.name]),
+            Term::Func(func) => {
+                let name = if func.terms.is_empty() { write_constant(&func.name) } else { write_functor(&func.name) };
+                out.push_str(&name);
+                self.arguments(&func.terms, out);
+            },
+            Term::Dummy => panic!("Expect a term, got a placeholder"),
+        }
+    }
+
+    fn arguments(&self, terms: &[Term], out: &mut String) {
+        if terms.is_empty() {
+            return;
+        }
+        out.push('(');
+        for (i, term) in terms.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            self.term(term, out);
+        }
+        out.push(')');
+    }
+
+    fn formula<S>(&self, formula: &Formula<S>, out: &mut String) {
+        if let Some(value) = truth_value(formula) {
+            out.push_str(if value { "$true" } else { "$false" });
+            return;
+        }
+        let binary = |op: &str, formula1: &Formula<S>, formula2: &Formula<S>, out: &mut String| {
+            out.push('(');
+            self.formula(formula1, out);
+            out.push_str(&format!(" {} ", op));
+            self.formula(formula2, out);
+            out.push(')');
+        };
+        match formula {
+            Formula::Pred(pred) => match equation(formula) {
+                Some((s, t)) => {
+                    self.term(s, out);
+                    out.push_str(" = ");
+                    self.term(t, out);
+                },
+                None => {
+                    out.push_str(&write_functor(&pred.name));
+                    self.arguments(&pred.terms, out);
+                },
+            },
+            Formula::Not(not) => match equation(&not.formula) {
+                Some((s, t)) => {
+                    self.term(s, out);
+                    out.push_str(" != ");
+                    self.term(t, out);
+                },
+                None => {
+                    out.push_str("~ ");
+                    self.formula(&not.formula, out);
+                },
+            },
+            Formula::And(and) => binary("&", &and.formula1, &and.formula2, out),
+            Formula::Or(or) => binary("|", &or.formula1, &or.formula2, out),
+            Formula::Implies(imp) => binary("=>", &imp.formula1, &imp.formula2, out),
+            Formula::Iff(iff) => binary("<=>", &iff.formula1, &iff.formula2, out),
+            Formula::ForAll(_) | Formula::Exists(_) => {
+                // a run of the same quantifier shares one variable list.
+                let universal = matches!(formula, Formula::ForAll(_));
+                let mut vars = vec![];
+                let mut body = formula;
+                loop {
+                    match body {
+                        Formula::ForAll(forall) if universal => {
+                            vars.push(self.vars[&forall.var.name].as_str());
+                            body = &forall.formula;
+                        },
+                        Formula::Exists(exists) if !universal => {
+                            vars.push(self.vars[&exists.var.name].as_str());
+                            body = &exists.formula;
+                        },
+                        _ => break,
+                    }
+                }
+                out.push_str(&format!("{} [{}] : ", if universal { "!" } else { "?" }, vars.join(", ")));
+                self.formula(body, out);
+            },
+            Formula::Dummy => panic!("Expect a formula, got a placeholder"),
+        }
+    }
+}
+
+
+fn collect_vars<S>(formula: &Formula<S>, names: &mut BTreeSet<String>) {
+    fn term(t: &Term, names: &mut BTreeSet<String>) {
+        match t {
+            Term::Var(var) => {
+                names.insert(var.name.clone());
+            },
+            Term::Func(func) => func.terms.iter().for_each(|t| term(t, names)),
+            Term::Dummy => {},
+        }
+    }
+    match formula {
+        Formula::Pred(pred) => pred.terms.iter().for_each(|t| term(t, names)),
+        Formula::Not(not) => collect_vars(&not.formula, names),
+        Formula::And(And { formula1, formula2, .. })
+        | Formula::Or(Or { formula1, formula2, .. })
+        | Formula::Implies(Implies { formula1, formula2, .. })
+        | Formula::Iff(Iff { formula1, formula2, .. }) => {
+            collect_vars(formula1, names);
+            collect_vars(formula2, names);
+        },
+        Formula::ForAll(ForAll { var, formula, .. }) | Formula::Exists(Exists { var, formula, .. }) => {
+            names.insert(var.name.clone());
+            collect_vars(formula, names);
+        },
+        Formula::Dummy => {},
+    }
+}
+
+
+// the two sides of `s = t`.
+fn equation<S>(formula: &Formula<S>) -> Option<(&Term, &Term)> {
+    match formula {
+        Formula::Pred(pred) if pred.name == "=" && pred.terms.len() == 2 => Some((&pred.terms[0], &pred.terms[1])),
+        _ => None,
+    }
+}
+
+
+// the value of the formulas `truth` builds.
+fn truth_value<S>(formula: &Formula<S>) -> Option<bool> {
+    let is_atom = |f: &Formula<S>| matches!(f, Formula::Pred(pred) if pred.name == TRUTH && pred.terms.is_empty());
+    let is_negated = |f: &Formula<S>| matches!(f, Formula::Not(not) if is_atom(&not.formula));
+    match formula {
+        Formula::Or(or) if is_atom(&or.formula1) && is_negated(&or.formula2) => Some(true),
+        Formula::And(and) if is_atom(&and.formula1) && is_negated(&and.formula2) => Some(false),
+        _ => None,
+    }
+}
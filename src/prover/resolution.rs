@@ -1,6 +1,7 @@
 use crate::fol::ast::*;
 
 
-fn resolve<S>(formula: &mut Formula<S>) {
+#[allow(dead_code)]
+fn resolve<S>(_formula: &mut Formula<S>) {
     todo!()
 }
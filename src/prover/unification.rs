@@ -6,6 +6,13 @@ use std::collections::HashMap;
 pub struct Unifier(Vec<HashMap<Term, Term>>);
 
 
+impl Default for Unifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
 impl Unifier {
     pub fn new() -> Self {
         Unifier (vec![])
@@ -21,6 +28,7 @@ impl Unifier {
 }
 
 
-fn unify<S>(formula: &mut Formula<S>) -> Unifier {
+#[allow(dead_code)]
+fn unify<S>(_formula: &mut Formula<S>) -> Unifier {
     todo!()
 }
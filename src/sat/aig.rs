@@ -0,0 +1,279 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::sat::aiger;
+use crate::sat::clauses::*;
+use std::io::{self, Read};
+
+
+/* And-Inverter Graphs.
+ *
+ * Every node is the constant false, an input, a latch or the conjunction of two edges, and an
+ * edge may be complemented. Edges are encoded like in the AIGER format: `2 * node` is the
+ * node itself and `2 * node + 1` its negation, so node 0 gives `FALSE` and `TRUE`.
+ *
+ * `Aig::and` folds constants and trivial cases and hashes structurally, so building the same
+ * gate twice yields the same node.
+ * */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AigLit(pub usize);
+
+
+impl AigLit {
+    pub const FALSE: AigLit = AigLit(0);
+    pub const TRUE: AigLit = AigLit(1);
+
+    pub fn node(self) -> usize { self.0 >> 1 }
+    pub fn is_complemented(self) -> bool { self.0 & 1 == 1 }
+    pub fn negate(self) -> Self { AigLit(self.0 ^ 1) }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    False,
+    Input(usize),
+    Latch(usize),
+    And(AigLit, AigLit),
+}
+
+
+/* A latch with its next state function and reset value, `None` being uninitialized. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Latch {
+    pub lit: AigLit,
+    pub next: AigLit,
+    pub init: Option<bool>,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Aig {
+    nodes: Vec<Node>,
+    strash: HashMap<(AigLit, AigLit), AigLit>,
+    pub inputs: Vec<AigLit>,
+    pub latches: Vec<Latch>,
+    pub outputs: Vec<AigLit>,
+    pub bad: Vec<AigLit>,
+    pub constraints: Vec<AigLit>,
+    // AIGER symbol table, e.g "i0" -> "reset".
+    pub symbols: HashMap<String, String>,
+}
+
+
+impl Default for Aig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl Aig {
+    pub fn new() -> Self {
+        Aig {
+            nodes: vec![Node::False],
+            strash: HashMap::new(),
+            inputs: Vec::new(),
+            latches: Vec::new(),
+            outputs: Vec::new(),
+            bad: Vec::new(),
+            constraints: Vec::new(),
+            symbols: HashMap::new(),
+        }
+    }
+
+    pub fn from_aiger<R: Read>(reader: R) -> io::Result<Self> {
+        aiger::parse(reader)
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn num_ands(&self) -> usize {
+        self.nodes.iter().filter(|n| matches!(n, Node::And(..))).count()
+    }
+
+    pub fn input(&mut self) -> AigLit {
+        let lit = self.push(Node::Input(self.inputs.len()));
+        self.inputs.push(lit);
+        lit
+    }
+
+    /* A latch whose next state is `FALSE` until `set_next` is called. */
+    pub fn latch(&mut self, init: Option<bool>) -> AigLit {
+        let lit = self.push(Node::Latch(self.latches.len()));
+        self.latches.push(Latch { lit, next: AigLit::FALSE, init });
+        lit
+    }
+
+    pub fn set_next(&mut self, latch: usize, next: AigLit) {
+        self.latches[latch].next = next;
+    }
+
+    pub fn and(&mut self, a: AigLit, b: AigLit) -> AigLit {
+        let (a, b) = if a <= b { (a, b) } else { (b, a) };
+        if a == AigLit::FALSE || a == b.negate() {
+            return AigLit::FALSE;
+        }
+        if a == AigLit::TRUE || a == b {
+            return b;
+        }
+        if let Some(&lit) = self.strash.get(&(a, b)) {
+            return lit;
+        }
+        let lit = self.push(Node::And(a, b));
+        self.strash.insert((a, b), lit);
+        lit
+    }
+
+    pub fn or(&mut self, a: AigLit, b: AigLit) -> AigLit {
+        self.and(a.negate(), b.negate()).negate()
+    }
+
+    pub fn xor(&mut self, a: AigLit, b: AigLit) -> AigLit {
+        let left = self.and(a, b.negate());
+        let right = self.and(a.negate(), b);
+        self.or(left, right)
+    }
+
+    pub fn ite(&mut self, cond: AigLit, then: AigLit, otherwise: AigLit) -> AigLit {
+        let left = self.and(cond, then);
+        let right = self.and(cond.negate(), otherwise);
+        self.or(left, right)
+    }
+
+    /* The two inputs of an and gate, `None` for every other node. */
+    pub fn fanins(&self, node: usize) -> Option<(AigLit, AigLit)> {
+        match self.nodes[node] {
+            Node::And(a, b) => Some((a, b)),
+            _ => None,
+        }
+    }
+
+    /* Rebuild the cone of `root` with the nodes in `map` replaced, e.g inputs by constants
+     * or by fresh inputs. Gates are rebuilt through `and`, so constants fold away.
+     * */
+    pub fn compose(&mut self, root: AigLit, map: &HashMap<usize, AigLit>) -> AigLit {
+        let mut built: HashMap<usize, AigLit> = HashMap::new();
+        let mut stack = vec![(root.node(), false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if built.contains_key(&node) {
+                continue;
+            }
+            if let Some(&lit) = map.get(&node) {
+                built.insert(node, lit);
+                continue;
+            }
+            match self.nodes[node] {
+                Node::And(a, b) if expanded => {
+                    let edge = |lit: AigLit| if lit.is_complemented() { built[&lit.node()].negate() } else { built[&lit.node()] };
+                    let (a, b) = (edge(a), edge(b));
+                    let lit = self.and(a, b);
+                    built.insert(node, lit);
+                },
+                Node::And(a, b) => {
+                    stack.push((node, true));
+                    stack.push((a.node(), false));
+                    stack.push((b.node(), false));
+                },
+                _ => {
+                    built.insert(node, AigLit(node << 1));
+                },
+            }
+        }
+        let lit = built[&root.node()];
+        if root.is_complemented() { lit.negate() } else { lit }
+    }
+
+    fn push(&mut self, node: Node) -> AigLit {
+        self.nodes.push(node);
+        AigLit((self.nodes.len() - 1) << 1)
+    }
+
+    /* Name of the CNF variable standing for `node`, in time frame `frame` if given. */
+    pub fn var_name(&self, node: usize, frame: Option<usize>) -> String {
+        let base = match self.nodes[node] {
+            Node::False => "false".to_string(),
+            Node::Input(i) => format!("i{}", i),
+            Node::Latch(i) => format!("l{}", i),
+            Node::And(..) => format!("a{}", node),
+        };
+        match frame {
+            Some(t) => format!("{}@{}", base, t),
+            None => base,
+        }
+    }
+
+    pub fn literal(&self, lit: AigLit, frame: Option<usize>) -> Literal {
+        let name = self.var_name(lit.node(), frame);
+        if lit.is_complemented() { Literal::neg(name) } else { Literal::pos(name) }
+    }
+
+    /* Tseitin encoding of every gate in the cone of influence of `roots`. An and gate
+     * `x = a ∧ b` becomes `(¬x ∨ a) ∧ (¬x ∨ b) ∧ (x ∨ ¬a ∨ ¬b)`, so any model of the
+     * clauses assigns each gate variable the value of its gate. Inputs and latches are left
+     * unconstrained.
+     * */
+    pub fn tseitin(&self, roots: &[AigLit], frame: Option<usize>) -> Clauses {
+        self.tseitin_from(roots, frame, &mut HashSet::new())
+    }
+
+    /* `tseitin` skipping the nodes in `visited` and adding the ones it encodes, so the cones
+     * given to an incremental solver one after the other share their gates. */
+    pub fn tseitin_from(&self, roots: &[AigLit], frame: Option<usize>, visited: &mut HashSet<usize>) -> Clauses {
+        let mut clauses = Clauses::new();
+        let mut stack = roots.iter().map(|l| l.node()).collect::<Vec<_>>();
+        while let Some(node) = stack.pop() {
+            if !visited.insert(node) {
+                continue;
+            }
+            match self.nodes[node] {
+                Node::False => {
+                    clauses.push(std::iter::once(self.literal(AigLit::TRUE, frame)).collect());
+                },
+                Node::And(a, b) => {
+                    let x = self.literal(AigLit(node << 1), frame);
+                    let la = self.literal(a, frame);
+                    let lb = self.literal(b, frame);
+                    clauses.push(vec![x.negate(), la.clone()].into_iter().collect());
+                    clauses.push(vec![x.negate(), lb.clone()].into_iter().collect());
+                    clauses.push(vec![x, la.negate(), lb.negate()].into_iter().collect());
+                    stack.push(a.node());
+                    stack.push(b.node());
+                },
+                Node::Input(_) | Node::Latch(_) => {},
+            }
+        }
+        clauses
+    }
+
+    /* Tseitin encoding of the whole graph. */
+    pub fn to_clauses(&self) -> Clauses {
+        let mut roots = self.outputs.clone();
+        roots.extend(self.bad.iter().cloned());
+        roots.extend(self.constraints.iter().cloned());
+        roots.extend(self.latches.iter().map(|l| l.next));
+        self.tseitin(&roots, None)
+    }
+
+    /* Values of all nodes for one time step, given the latch and input values. */
+    pub fn simulate(&self, latches: &[bool], inputs: &[bool]) -> Vec<bool> {
+        let mut values = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let value = match *node {
+                Node::False => false,
+                Node::Input(i) => inputs[i],
+                Node::Latch(i) => latches[i],
+                Node::And(a, b) => value(&values, a) && value(&values, b),
+            };
+            values.push(value);
+        }
+        values
+    }
+}
+
+
+/* Value of an edge in the output of `Aig::simulate`. */
+pub fn value(values: &[bool], lit: AigLit) -> bool {
+    values[lit.node()] != lit.is_complemented()
+}
@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read};
+
+use crate::sat::aig::*;
+
+
+/* Reader for the AIGER format, both the ASCII `aag` and the binary `aig` flavour.
+ *
+ * The header is `aag M I L O A [B C J F]`: maximum variable index, number of inputs,
+ * latches, outputs, and gates, optionally followed by the number of bad state properties,
+ * invariant constraints, justice and fairness properties. Justice and fairness are
+ * liveness features we do not support, so they must be zero.
+ *
+ * File literals are mapped onto a fresh `Aig`, which may merge gates through structural
+ * hashing, so node numbers do not survive the round trip.
+ * */
+pub fn parse<R: Read>(mut reader: R) -> io::Result<Aig> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let mut input = Input { bytes: &bytes, pos: 0 };
+
+    let header = input.line()?;
+    let fields = header.split_whitespace().collect::<Vec<_>>();
+    let binary = match fields.first() {
+        Some(&"aag") => false,
+        Some(&"aig") => true,
+        _ => return Err(invalid(format!("invalid AIGER header {:?}", header))),
+    };
+    if fields.len() < 6 || fields.len() > 10 {
+        return Err(invalid(format!("invalid AIGER header {:?}", header)));
+    }
+    let mut counts = [0usize; 9];
+    for (i, field) in fields[1..].iter().enumerate() {
+        counts[i] = number(field)?;
+    }
+    let [max_var, n_inputs, n_latches, n_outputs, n_ands, n_bad, n_constraints, n_justice, n_fairness] = counts;
+    if n_justice > 0 || n_fairness > 0 {
+        return Err(invalid("justice and fairness properties are not supported".to_string()));
+    }
+
+    let mut aig = Aig::new();
+    let mut lits: HashMap<usize, AigLit> = HashMap::new();
+    lits.insert(0, AigLit::FALSE);
+
+    for i in 0..n_inputs {
+        let var = if binary { i + 1 } else { defined_var(&input.line()?, max_var)? };
+        if lits.contains_key(&var) {
+            return Err(invalid(format!("variable {} defined twice", var)));
+        }
+        lits.insert(var, aig.input());
+    }
+
+    let mut nexts = Vec::with_capacity(n_latches);
+    for i in 0..n_latches {
+        let line = input.line()?;
+        let fields = line.split_whitespace().map(number).collect::<io::Result<Vec<_>>>()?;
+        let (lit, rest) = if binary {
+            (2 * (n_inputs + i + 1), &fields[..])
+        } else {
+            match fields.split_first() {
+                Some((&lit, rest)) => (lit, rest),
+                None => return Err(invalid(format!("invalid latch {:?}", line))),
+            }
+        };
+        let var = defined_var(&lit.to_string(), max_var)?;
+        if lits.contains_key(&var) {
+            return Err(invalid(format!("variable {} defined twice", var)));
+        }
+        let (next, init) = match *rest {
+            [next] => (next, Some(false)),
+            [next, 0] => (next, Some(false)),
+            [next, 1] => (next, Some(true)),
+            [next, reset] if reset == lit => (next, None),
+            _ => return Err(invalid(format!("invalid latch {:?}", line))),
+        };
+        lits.insert(var, aig.latch(init));
+        nexts.push(next);
+    }
+
+    let mut section = |n: usize| -> io::Result<Vec<usize>> {
+        (0..n).map(|_| input.line().and_then(|l| number(l.trim()))).collect()
+    };
+    let outputs = section(n_outputs)?;
+    let bad = section(n_bad)?;
+    let constraints = section(n_constraints)?;
+
+    let mut gates: HashMap<usize, (usize, usize)> = HashMap::new();
+    for i in 0..n_ands {
+        let (lhs, rhs0, rhs1) = if binary {
+            let lhs = 2 * (n_inputs + n_latches + i + 1);
+            let rhs0 = lhs.checked_sub(input.delta()?).ok_or_else(|| invalid("invalid gate delta".to_string()))?;
+            let rhs1 = rhs0.checked_sub(input.delta()?).ok_or_else(|| invalid("invalid gate delta".to_string()))?;
+            (lhs, rhs0, rhs1)
+        } else {
+            let line = input.line()?;
+            match line.split_whitespace().map(number).collect::<io::Result<Vec<_>>>()?[..] {
+                [lhs, rhs0, rhs1] => (lhs, rhs0, rhs1),
+                _ => return Err(invalid(format!("invalid and gate {:?}", line))),
+            }
+        };
+        let var = defined_var(&lhs.to_string(), max_var)?;
+        if lits.contains_key(&var) || gates.insert(var, (rhs0, rhs1)).is_some() {
+            return Err(invalid(format!("variable {} defined twice", var)));
+        }
+    }
+
+    aig.outputs = outputs.into_iter().map(|l| resolve(&mut aig, &mut lits, &gates, l)).collect::<io::Result<_>>()?;
+    aig.bad = bad.into_iter().map(|l| resolve(&mut aig, &mut lits, &gates, l)).collect::<io::Result<_>>()?;
+    aig.constraints = constraints.into_iter().map(|l| resolve(&mut aig, &mut lits, &gates, l)).collect::<io::Result<_>>()?;
+    for (i, next) in nexts.into_iter().enumerate() {
+        let next = resolve(&mut aig, &mut lits, &gates, next)?;
+        aig.set_next(i, next);
+    }
+
+    // optional symbol table, ended by the comment section or the end of the file.
+    while !input.at_end() {
+        let line = input.line()?;
+        if line.trim() == "c" {
+            break;
+        }
+        match line.split_once(' ') {
+            Some((key, name)) if key.len() > 1 && b"ilobc".contains(&key.as_bytes()[0]) => {
+                aig.symbols.insert(key.to_string(), name.to_string());
+            },
+            _ => return Err(invalid(format!("invalid symbol {:?}", line))),
+        }
+    }
+    Ok(aig)
+}
+
+
+struct Input<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+
+impl Input<'_> {
+    fn at_end(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn line(&mut self) -> io::Result<String> {
+        if self.at_end() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of AIGER input"));
+        }
+        let start = self.pos;
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' {
+            self.pos += 1;
+        }
+        let line = String::from_utf8_lossy(&self.bytes[start..self.pos]).trim_end_matches('\r').to_string();
+        self.pos += 1;
+        Ok(line)
+    }
+
+    /* LEB128 style number used for the binary and gates, 7 bits per byte. */
+    fn delta(&mut self) -> io::Result<usize> {
+        let mut value = 0usize;
+        let mut shift = 0;
+        loop {
+            if self.at_end() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of AIGER input"));
+            }
+            let byte = self.bytes[self.pos];
+            self.pos += 1;
+            let bits = (byte & 0x7f) as usize;
+            // bits shifted past the width of usize are lost, the number is too large.
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return Err(invalid("AIGER delta does not fit in a machine word".to_string()));
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+}
+
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+
+fn number(field: &str) -> io::Result<usize> {
+    field.parse().map_err(|_| invalid(format!("invalid number {:?}", field)))
+}
+
+
+fn defined_var(field: &str, max_var: usize) -> io::Result<usize> {
+    let lit = number(field.trim())?;
+    if lit < 2 || lit & 1 == 1 || lit / 2 > max_var {
+        return Err(invalid(format!("invalid definition literal {}", lit)));
+    }
+    Ok(lit / 2)
+}
+
+
+/* Map a file literal to the graph, building the and gates it depends on first. ASCII files
+ * may list gates in any order, so this walks the definitions with an explicit stack. */
+fn resolve(aig: &mut Aig, lits: &mut HashMap<usize, AigLit>, gates: &HashMap<usize, (usize, usize)>, lit: usize) -> io::Result<AigLit> {
+    let mut pending = HashSet::new();
+    let mut stack = vec![(lit / 2, false)];
+    while let Some((var, expanded)) = stack.pop() {
+        if lits.contains_key(&var) {
+            continue;
+        }
+        let &(rhs0, rhs1) = gates.get(&var).ok_or_else(|| invalid(format!("undefined variable {}", var)))?;
+        if expanded {
+            let a = edge(lits, rhs0);
+            let b = edge(lits, rhs1);
+            let gate = aig.and(a, b);
+            lits.insert(var, gate);
+            pending.remove(&var);
+        } else {
+            // meeting a gate again inside its own cone means the definitions are cyclic.
+            if !pending.insert(var) {
+                return Err(invalid(format!("cyclic definition of variable {}", var)));
+            }
+            stack.push((var, true));
+            stack.push((rhs0 / 2, false));
+            stack.push((rhs1 / 2, false));
+        }
+    }
+    Ok(edge(lits, lit))
+}
+
+
+fn edge(lits: &HashMap<usize, AigLit>, lit: usize) -> AigLit {
+    let base = lits[&(lit / 2)];
+    if lit & 1 == 1 { base.negate() } else { base }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use crate::sat::cdcl::*;
+use crate::sat::clauses::*;
+use crate::sat::gen::Rng;
+
+
+/* Approximate model counting and near uniform sampling by hashing, after ApproxMC and
+ * UniGen (Chakraborty, Meel and Vardi).
+ *
+ * A random xor constraint over the sampling set cuts the models roughly in half, so m of
+ * them leave a cell of about `count / 2^m` models. Cells small enough to enumerate with a
+ * SAT solver give an estimate of the count, or a small set to pick a sample from.
+ *
+ * Counting and sampling are over a sampling set, all variables of the clauses unless
+ * given: two models agreeing on it count once. Hashes and blocking clauses are added to an
+ * incremental solver behind activation literals, so the cells of all numbers of xors are
+ * explored in one solver by assuming more or fewer of them.
+ * */
+struct Cells {
+    solver: Solver,
+    sampling: Vec<Var>,
+}
+
+
+impl Cells {
+    fn new(clauses: &Clauses, sampling: Option<&[String]>) -> Self {
+        let mut solver = Solver::from_clauses(clauses);
+        let sampling = match sampling {
+            Some(names) => names.iter().map(|n| solver.var(n)).collect(),
+            None => {
+                let mut names = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name().to_string())).collect::<Vec<_>>();
+                names.sort();
+                names.dedup();
+                names.iter().map(|n| solver.var(n)).collect()
+            },
+        };
+        Cells { solver, sampling }
+    }
+
+    /* A random xor constraint over the sampling set, which only holds when its returned
+     * activation literal is assumed. Each variable takes part with probability 1/2 and the
+     * right hand side is a coin flip. The xor is chained through fresh variables
+     * `t_i = t_{i-1} ⊕ x_i`, so it costs four clauses per variable.
+     * */
+    fn xor(&mut self, rng: &mut Rng) -> Lit {
+        let vars = self.sampling.iter().cloned().filter(|_| rng.bool()).collect::<Vec<_>>();
+        let parity = rng.bool();
+        let act = self.solver.new_var().pos();
+        let mut sum: Option<Lit> = None;
+        for var in vars {
+            let x = var.pos();
+            sum = Some(match sum {
+                None => x,
+                Some(prev) => {
+                    let t = self.solver.new_var().pos();
+                    self.solver.add_clause(&[t.negate(), prev, x]);
+                    self.solver.add_clause(&[t.negate(), prev.negate(), x.negate()]);
+                    self.solver.add_clause(&[t, prev.negate(), x]);
+                    self.solver.add_clause(&[t, prev, x.negate()]);
+                    t
+                },
+            });
+        }
+        match sum {
+            Some(sum) => self.solver.add_clause(&[act.negate(), if parity { sum } else { sum.negate() }]),
+            // an empty xor is 0, only a false right hand side holds.
+            None if parity => self.solver.add_clause(&[act.negate()]),
+            None => true,
+        };
+        act
+    }
+
+    /* Up to `limit` models of the cell selected by `hashes`, projected on the sampling set.
+     * Blocking clauses are guarded by a literal retired afterwards. */
+    fn enumerate(&mut self, hashes: &[Lit], limit: usize) -> Vec<Vec<bool>> {
+        let block = self.solver.new_var().pos();
+        let mut assumptions = hashes.to_vec();
+        assumptions.push(block);
+        let mut models = Vec::new();
+        while models.len() < limit && self.solver.solve_with(&assumptions) {
+            let values = self.sampling.iter().map(|&v| self.solver.value(v.pos()).unwrap_or(false)).collect::<Vec<_>>();
+            let mut blocking = self.sampling
+                .iter()
+                .zip(values.iter())
+                .map(|(&v, &b)| Lit::new(v, b))
+                .collect::<Vec<_>>();
+            blocking.push(block.negate());
+            self.solver.add_clause(&blocking);
+            models.push(values);
+        }
+        self.solver.add_clause(&[block.negate()]);
+        models
+    }
+
+    fn model(&self, values: &[bool]) -> Model {
+        self.sampling.iter().zip(values.iter()).map(|(&v, &b)| (self.solver.name(v).to_string(), b)).collect()
+    }
+}
+
+
+/* Cell size up to which a cell counts as small, from ApproxMC. */
+fn threshold(epsilon: f64) -> usize {
+    (1.0 + 9.84 * (1.0 + epsilon / (1.0 + epsilon)) * (1.0 + 1.0 / epsilon).powi(2)).ceil() as usize
+}
+
+
+/* A count within a factor `1 + epsilon` of the number of models with probability at least
+ * `1 - delta`. Each round hashes with more and more xors until the cell is small, then
+ * scales the cell size back up; the median of the rounds is the estimate. Counts small
+ * enough are returned exactly, estimates of 2^128 or more saturate at `u128::MAX`.
+ * */
+pub fn approx_count(clauses: &Clauses, epsilon: f64, delta: f64, rng: &mut Rng) -> u128 {
+    approx_count_projected(clauses, None, epsilon, delta, rng)
+}
+
+
+pub fn approx_count_projected(clauses: &Clauses, sampling: Option<&[String]>, epsilon: f64, delta: f64, rng: &mut Rng) -> u128 {
+    let (cell, m) = estimate(clauses, sampling, epsilon, delta, rng);
+    if cell != 0 && m > cell.leading_zeros() as usize {
+        u128::MAX
+    } else {
+        cell << m
+    }
+}
+
+
+// the estimate as a cell size and the number of xors that cut it out, the count being
+// cell * 2^m, which a sampling set of more than 127 variables can take past u128.
+fn estimate(clauses: &Clauses, sampling: Option<&[String]>, epsilon: f64, delta: f64, rng: &mut Rng) -> (u128, usize) {
+    assert!(epsilon > 0.0 && delta > 0.0 && delta < 1.0, "need epsilon > 0 and 0 < delta < 1");
+    let threshold = threshold(epsilon);
+    let all = Cells::new(clauses, sampling).enumerate(&[], threshold);
+    if all.len() < threshold {
+        return (all.len() as u128, 0);
+    }
+
+    let rounds = (17.0 * (3.0 / delta).log2()).ceil() as usize;
+    let mut estimates = Vec::with_capacity(rounds);
+    let mut previous = 1;
+    for _ in 0..rounds {
+        // a fresh solver per round keeps old hashes and blocking clauses out of the way.
+        let mut cells = Cells::new(clauses, sampling);
+        let n = cells.sampling.len();
+        let mut hashes = Vec::new();
+        // cells of a prefix of the hashes, the one without any being too large already.
+        let mut sizes = HashMap::new();
+        sizes.insert(0, threshold);
+        let mut size = |cells: &mut Cells, m: usize| {
+            while hashes.len() < m {
+                hashes.push(cells.xor(rng));
+            }
+            *sizes.entry(m).or_insert_with(|| cells.enumerate(&hashes[..m], threshold).len())
+        };
+        // cells only shrink as xors are added, so the fewest xors giving a small cell are
+        // found by galloping from the number the last round needed, like ApproxMC2 does,
+        // then by bisection: a cell with `lo` xors is large, one with `hi` is small unless
+        // `hi` is `n`.
+        let start = previous.min(n);
+        let mut step = 1;
+        let (mut lo, mut hi);
+        if size(&mut cells, start) >= threshold {
+            lo = start;
+            while lo + step < n && size(&mut cells, lo + step) >= threshold {
+                lo += step;
+                step *= 2;
+            }
+            hi = (lo + step).min(n);
+        } else {
+            hi = start;
+            while hi > step && size(&mut cells, hi - step) < threshold {
+                hi -= step;
+                step *= 2;
+            }
+            lo = hi.saturating_sub(step);
+        }
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if size(&mut cells, mid) >= threshold { lo = mid } else { hi = mid }
+        }
+        let m = hi;
+        let cell = size(&mut cells, m);
+        if 0 < cell && cell < threshold {
+            estimates.push((cell as u128, m));
+            previous = m;
+        }
+    }
+    if estimates.is_empty() {
+        return (0, 0);
+    }
+    let log2 = |&(cell, m): &(u128, usize)| (cell as f64).log2() + m as f64;
+    estimates.sort_by(|a, b| log2(a).total_cmp(&log2(b)));
+    estimates[estimates.len() / 2]
+}
+
+
+/* Near uniform sampling after UniGen: every model is returned with probability within a
+ * factor `1 + epsilon` of uniform, for `epsilon > 1.71`. A rough count picks the number of
+ * xors; a sample is a uniform pick from the first cell whose size lands between the two
+ * thresholds. Formulas with few models are sampled exactly from all of them.
+ * */
+pub struct Sampler {
+    clauses: Clauses,
+    sampling: Option<Vec<String>>,
+    rng: Rng,
+    lo: usize,
+    hi: usize,
+    // number of xors to start from, `None` if all models fit under `hi`.
+    start: Option<usize>,
+    all: Vec<Vec<bool>>,
+}
+
+
+impl Sampler {
+    pub fn new(clauses: &Clauses, epsilon: f64, rng: Rng) -> Self {
+        Sampler::projected(clauses, None, epsilon, rng)
+    }
+
+    pub fn projected(clauses: &Clauses, sampling: Option<&[String]>, epsilon: f64, mut rng: Rng) -> Self {
+        assert!(epsilon > 1.71, "UniGen needs epsilon > 1.71");
+        // kappa solves epsilon = (1 + kappa)(2.23 + 0.48 / (1 - kappa)^2) - 1, increasing
+        // from 1.71 at kappa = 0.
+        let f = |k: f64| (1.0 + k) * (2.23 + 0.48 / (1.0 - k).powi(2)) - 1.0;
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..64 {
+            let mid = (low + high) / 2.0;
+            if f(mid) < epsilon { low = mid } else { high = mid }
+        }
+        let kappa = low;
+        let pivot = (4.03 * (1.0 + 1.0 / kappa).powi(2)).ceil();
+        let hi = (1.0 + 2f64.sqrt() * (1.0 + kappa) * pivot).ceil() as usize;
+        let lo = (pivot / (2f64.sqrt() * (1.0 + kappa))).floor() as usize;
+
+        let all = Cells::new(clauses, sampling).enumerate(&[], hi + 1);
+        let start = if all.len() <= hi {
+            None
+        } else {
+            let (cell, m) = estimate(clauses, sampling, 0.8, 0.2, &mut rng);
+            let log2 = (cell as f64).log2() + m as f64;
+            Some(((log2 + 1.8f64.log2() - pivot.log2()).ceil().max(0.0) as usize).saturating_sub(3))
+        };
+        let sampling = sampling.map(|s| s.to_vec());
+        Sampler { clauses: clauses.clone(), sampling, rng, lo, hi, start, all }
+    }
+
+    /* A near uniform model over the sampling set, `None` if there is none or all hash
+     * attempts missed, which happens with small probability. */
+    pub fn sample(&mut self) -> Option<Model> {
+        let mut cells = Cells::new(&self.clauses, self.sampling.as_deref());
+        let Some(start) = self.start else {
+            if self.all.is_empty() {
+                return None;
+            }
+            let pick = self.rng.below(self.all.len());
+            return Some(cells.model(&self.all[pick]));
+        };
+        let mut hashes = (0..start).map(|_| cells.xor(&mut self.rng)).collect::<Vec<_>>();
+        for _ in 0..4 {
+            if hashes.len() > cells.sampling.len() {
+                break;
+            }
+            let cell = cells.enumerate(&hashes, self.hi + 1);
+            if self.lo <= cell.len() && cell.len() <= self.hi {
+                let pick = self.rng.below(cell.len());
+                return Some(cells.model(&cell[pick]));
+            }
+            hashes.push(cells.xor(&mut self.rng));
+        }
+        None
+    }
+
+    /* `n` draws, failed ones left out. */
+    pub fn samples(&mut self, n: usize) -> Vec<Model> {
+        (0..n).filter_map(|_| self.sample()).collect()
+    }
+}
+
@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use crate::sat::cdcl::*;
+use crate::sat::clauses::*;
+
+
+/* The backbone of a satisfiable set of clauses is the set of literals true in every model.
+ *
+ * We find it with the usual iterative scheme on top of the incremental solver:
+ * take the literals of one model as candidates, then for each candidate L ask for a model
+ * with ¬L assumed. If there is none L is in the backbone, otherwise every candidate the
+ * new model disagrees with is dropped as well, so most candidates never cost a call.
+ * Literals fixed by unit propagation at level 0 are accepted without any call.
+ * */
+pub fn backbone(clauses: &Clauses) -> Option<Vec<Literal>> {
+    Implications::new(clauses).backbone(&[])
+}
+
+
+/* Repeated implication queries against the same clauses. Learnt clauses are kept between
+ * queries, so asking many questions of one `Implications` is much cheaper than building a
+ * solver for each.
+ * */
+pub struct Implications {
+    solver: Solver,
+}
+
+
+impl Implications {
+    pub fn new(clauses: &Clauses) -> Self {
+        Implications { solver: Solver::from_clauses(clauses) }
+    }
+
+    pub fn solver(&mut self) -> &mut Solver {
+        &mut self.solver
+    }
+
+    /* Literals true in every model that satisfies `assumptions`, the assumptions included.
+     * `None` if no model satisfies them.
+     * */
+    pub fn backbone(&mut self, assumptions: &[Literal]) -> Option<Vec<Literal>> {
+        let mut assumed = assumptions.iter().map(|l| self.solver.lit(l)).collect::<Vec<_>>();
+        if !self.solver.solve_with(&assumed) {
+            return None;
+        }
+        let fixed = self.solver.fixed().into_iter().collect::<HashSet<_>>();
+        let mut candidates = (0..self.solver.num_vars())
+            .map(|v| Var(v).pos())
+            .map(|l| if self.solver.value(l) == Some(true) { l } else { l.negate() })
+            .collect::<Vec<_>>();
+
+        let mut backbone = Vec::new();
+        while let Some(lit) = candidates.pop() {
+            if fixed.contains(&lit) {
+                backbone.push(lit);
+                continue;
+            }
+            assumed.push(lit.negate());
+            let sat = self.solver.solve_with(&assumed);
+            assumed.pop();
+            if sat {
+                candidates.retain(|&c| self.solver.value(c) == Some(true));
+            } else {
+                backbone.push(lit);
+                // without caller assumptions the literal holds for good.
+                if assumptions.is_empty() {
+                    self.solver.add_clause(&[lit]);
+                } else {
+                    assumed.push(lit);
+                }
+            }
+        }
+        let mut backbone = backbone.into_iter().map(|l| self.solver.literal(l)).collect::<Vec<_>>();
+        backbone.sort_by(|a, b| a.var_name().cmp(b.var_name()));
+        Some(backbone)
+    }
+
+    /* Whether `literal` holds in every model satisfying `assumptions`. This is vacuously
+     * true when the assumptions are inconsistent with the clauses.
+     * */
+    pub fn is_implied(&mut self, assumptions: &[Literal], literal: &Literal) -> bool {
+        let mut assumed = assumptions.iter().map(|l| self.solver.lit(l)).collect::<Vec<_>>();
+        assumed.push(self.solver.lit(&literal.negate()));
+        !self.solver.solve_with(&assumed)
+    }
+}
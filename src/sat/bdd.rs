@@ -0,0 +1,467 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::fol::ast::*;
+use crate::sat::clauses::*;
+
+
+/* Reduced ordered binary decision diagrams.
+ *
+ * All diagrams live in one `Manager`, and a `Bdd` is just the index of its root node. The
+ * unique table guarantees there is exactly one node per (variable, low, high) triple, so
+ * two functions over the same manager are equal iff their `Bdd`s are equal. That is what
+ * makes equivalence checking O(1) once both sides are built.
+ *
+ * Nodes are never freed. Reordering rewrites nodes in place, so every `Bdd` handed out
+ * keeps denoting the same function across `sift`.
+ * */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Bdd(pub usize);
+
+
+impl Bdd {
+    pub const FALSE: Bdd = Bdd(0);
+    pub const TRUE: Bdd = Bdd(1);
+
+    pub fn is_terminal(self) -> bool {
+        self.0 < 2
+    }
+}
+
+
+// variable index of the terminals, below every real variable.
+const TERMINAL: usize = usize::MAX;
+
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    var: usize,
+    low: Bdd,
+    high: Bdd,
+}
+
+
+pub struct Manager {
+    nodes: Vec<Node>,
+    unique: HashMap<(usize, Bdd, Bdd), Bdd>,
+    cache: HashMap<(Bdd, Bdd, Bdd), Bdd>,
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    // position of each variable in the order, and the variable at each position.
+    levels: Vec<usize>,
+    order: Vec<usize>,
+}
+
+
+impl Default for Manager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl Manager {
+    pub fn new() -> Self {
+        let terminal = |b| Node { var: TERMINAL, low: Bdd(b), high: Bdd(b) };
+        Manager {
+            nodes: vec![terminal(0), terminal(1)],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+            names: Vec::new(),
+            ids: HashMap::new(),
+            levels: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.names.len()
+    }
+
+    /* Number of nodes allocated so far, including unreachable ones. */
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /* Variable names from the top of the order to the bottom. */
+    pub fn order(&self) -> Vec<&str> {
+        self.order.iter().map(|&v| self.names[v].as_str()).collect()
+    }
+
+    /* The function that is true iff variable `name` is. New variables go to the bottom of
+     * the order. */
+    pub fn var(&mut self, name: &str) -> Bdd {
+        let v = match self.ids.get(name) {
+            Some(&v) => v,
+            None => {
+                let v = self.names.len();
+                self.names.push(name.to_string());
+                self.ids.insert(name.to_string(), v);
+                self.levels.push(self.order.len());
+                self.order.push(v);
+                v
+            }
+        };
+        self.mk(v, Bdd::FALSE, Bdd::TRUE)
+    }
+
+    pub fn literal(&mut self, literal: &Literal) -> Bdd {
+        let var = self.var(literal.var_name());
+        if literal.is_negated() { self.not(var) } else { var }
+    }
+
+    fn level(&self, f: Bdd) -> usize {
+        match self.nodes[f.0].var {
+            TERMINAL => TERMINAL,
+            v => self.levels[v],
+        }
+    }
+
+    fn mk(&mut self, var: usize, low: Bdd, high: Bdd) -> Bdd {
+        if low == high {
+            return low;
+        }
+        if let Some(&f) = self.unique.get(&(var, low, high)) {
+            return f;
+        }
+        let f = Bdd(self.nodes.len());
+        self.nodes.push(Node { var, low, high });
+        self.unique.insert((var, low, high), f);
+        f
+    }
+
+    // cofactors of `f` with respect to the variable at `level`.
+    fn cofactors(&self, f: Bdd, level: usize) -> (Bdd, Bdd) {
+        if self.level(f) == level {
+            (self.nodes[f.0].low, self.nodes[f.0].high)
+        } else {
+            (f, f)
+        }
+    }
+
+    /* if f then g else h, every other connective is an instance of it. */
+    pub fn ite(&mut self, f: Bdd, g: Bdd, h: Bdd) -> Bdd {
+        if f == Bdd::TRUE || g == h {
+            return g;
+        }
+        if f == Bdd::FALSE {
+            return h;
+        }
+        if g == Bdd::TRUE && h == Bdd::FALSE {
+            return f;
+        }
+        if let Some(&r) = self.cache.get(&(f, g, h)) {
+            return r;
+        }
+        let level = self.level(f).min(self.level(g)).min(self.level(h));
+        let (f0, f1) = self.cofactors(f, level);
+        let (g0, g1) = self.cofactors(g, level);
+        let (h0, h1) = self.cofactors(h, level);
+        let low = self.ite(f0, g0, h0);
+        let high = self.ite(f1, g1, h1);
+        let r = self.mk(self.order[level], low, high);
+        self.cache.insert((f, g, h), r);
+        r
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(&mut self, f: Bdd) -> Bdd { self.ite(f, Bdd::FALSE, Bdd::TRUE) }
+    pub fn and(&mut self, f: Bdd, g: Bdd) -> Bdd { self.ite(f, g, Bdd::FALSE) }
+    pub fn or(&mut self, f: Bdd, g: Bdd) -> Bdd { self.ite(f, Bdd::TRUE, g) }
+    pub fn implies(&mut self, f: Bdd, g: Bdd) -> Bdd { self.ite(f, g, Bdd::TRUE) }
+
+    pub fn xor(&mut self, f: Bdd, g: Bdd) -> Bdd {
+        let not_g = self.not(g);
+        self.ite(f, not_g, g)
+    }
+
+    pub fn iff(&mut self, f: Bdd, g: Bdd) -> Bdd {
+        let not_g = self.not(g);
+        self.ite(f, g, not_g)
+    }
+
+    /* f with variable `name` fixed to `value`. */
+    pub fn restrict(&mut self, f: Bdd, name: &str, value: bool) -> Bdd {
+        match self.ids.get(name) {
+            Some(&v) => {
+                let level = self.levels[v];
+                self.restrict_rec(f, level, value, &mut HashMap::new())
+            },
+            None => f,
+        }
+    }
+
+    fn restrict_rec(&mut self, f: Bdd, level: usize, value: bool, memo: &mut HashMap<Bdd, Bdd>) -> Bdd {
+        let f_level = self.level(f);
+        if f_level > level {
+            return f;
+        }
+        if let Some(&r) = memo.get(&f) {
+            return r;
+        }
+        let Node { var, low, high } = self.nodes[f.0];
+        let r = if f_level == level {
+            if value { high } else { low }
+        } else {
+            let low = self.restrict_rec(low, level, value, memo);
+            let high = self.restrict_rec(high, level, value, memo);
+            self.mk(var, low, high)
+        };
+        memo.insert(f, r);
+        r
+    }
+
+    /* ∃ names. f */
+    pub fn exists(&mut self, f: Bdd, names: &[&str]) -> Bdd {
+        let levels = names.iter().filter_map(|n| self.ids.get(*n)).map(|&v| self.levels[v]).collect::<HashSet<_>>();
+        self.exists_rec(f, &levels, &mut HashMap::new())
+    }
+
+    fn exists_rec(&mut self, f: Bdd, levels: &HashSet<usize>, memo: &mut HashMap<Bdd, Bdd>) -> Bdd {
+        if f.is_terminal() {
+            return f;
+        }
+        if let Some(&r) = memo.get(&f) {
+            return r;
+        }
+        let Node { var, low, high } = self.nodes[f.0];
+        let low = self.exists_rec(low, levels, memo);
+        let high = self.exists_rec(high, levels, memo);
+        let r = if levels.contains(&self.levels[var]) { self.or(low, high) } else { self.mk(var, low, high) };
+        memo.insert(f, r);
+        r
+    }
+
+    /* ∀ names. f */
+    pub fn forall(&mut self, f: Bdd, names: &[&str]) -> Bdd {
+        let not_f = self.not(f);
+        let r = self.exists(not_f, names);
+        self.not(r)
+    }
+
+    /* Number of models of f over all variables of the manager. Counts of 2^128 or more,
+     * which take more than 127 variables, saturate at `u128::MAX`.
+     * */
+    pub fn count(&self, f: Bdd) -> u128 {
+        // c * 2^n, saturating.
+        fn shift(c: u128, n: usize) -> u128 {
+            match c {
+                0 => 0,
+                _ if n > c.leading_zeros() as usize => u128::MAX,
+                _ => c << n,
+            }
+        }
+        fn go(m: &Manager, f: Bdd, memo: &mut HashMap<Bdd, u128>) -> u128 {
+            // models over the variables strictly below the level of f.
+            if f.is_terminal() {
+                return f.0 as u128;
+            }
+            if let Some(&c) = memo.get(&f) {
+                return c;
+            }
+            let level = m.level(f);
+            let Node { low, high, .. } = m.nodes[f.0];
+            let below = |g: Bdd, c: u128| shift(c, m.level(g).min(m.num_vars()) - level - 1);
+            let c = below(low, go(m, low, memo)).saturating_add(below(high, go(m, high, memo)));
+            memo.insert(f, c);
+            c
+        }
+        let top = self.level(f).min(self.num_vars());
+        shift(go(self, f, &mut HashMap::new()), top)
+    }
+
+    /* One model of f, assigning only the variables on the path to `TRUE`. */
+    pub fn any_model(&self, f: Bdd) -> Option<Model> {
+        if f == Bdd::FALSE {
+            return None;
+        }
+        let mut model = Model::new();
+        let mut f = f;
+        while !f.is_terminal() {
+            let Node { var, low, high } = self.nodes[f.0];
+            if low == Bdd::FALSE {
+                model.insert(self.names[var].clone(), true);
+                f = high;
+            } else {
+                model.insert(self.names[var].clone(), false);
+                f = low;
+            }
+        }
+        Some(model)
+    }
+
+    /* Number of nodes reachable from `roots`, terminals included. */
+    pub fn size(&self, roots: &[Bdd]) -> usize {
+        let mut seen = HashSet::new();
+        let mut stack = roots.to_vec();
+        while let Some(f) = stack.pop() {
+            if seen.insert(f) && !f.is_terminal() {
+                stack.push(self.nodes[f.0].low);
+                stack.push(self.nodes[f.0].high);
+            }
+        }
+        seen.len()
+    }
+
+    /* Exchange the variables at `level` and `level + 1`. Nodes of the upper variable that
+     * depend on the lower one are rewritten in place, so they keep their identity.
+     * */
+    fn swap(&mut self, level: usize) {
+        let x = self.order[level];
+        let y = self.order[level + 1];
+        let xs = (0..self.nodes.len()).filter(|&i| self.nodes[i].var == x).collect::<Vec<_>>();
+        self.levels[x] = level + 1;
+        self.levels[y] = level;
+        self.order.swap(level, level + 1);
+        for i in xs {
+            let Node { low: f0, high: f1, .. } = self.nodes[i];
+            let split = |m: &Manager, g: Bdd| if m.nodes[g.0].var == y { (m.nodes[g.0].low, m.nodes[g.0].high) } else { (g, g) };
+            let (f00, f01) = split(self, f0);
+            let (f10, f11) = split(self, f1);
+            if f00 == f01 && f10 == f11 {
+                continue;
+            }
+            let low = self.mk(x, f00, f10);
+            let high = self.mk(x, f01, f11);
+            self.unique.remove(&(x, f0, f1));
+            self.nodes[i] = Node { var: y, low, high };
+            self.unique.insert((y, low, high), Bdd(i));
+        }
+        self.cache.clear();
+    }
+
+    /* Rudell's sifting: move each variable through every position of the order and leave it
+     * where the diagrams reachable from `roots` are smallest.
+     * */
+    pub fn sift(&mut self, roots: &[Bdd]) {
+        let mut vars = (0..self.num_vars()).collect::<Vec<_>>();
+        let counts = vars.iter().map(|&v| self.nodes.iter().filter(|n| n.var == v).count()).collect::<Vec<_>>();
+        vars.sort_by_key(|&v| std::cmp::Reverse(counts[v]));
+        let last = self.num_vars().saturating_sub(1);
+        for v in vars {
+            let mut best = (self.size(roots), self.levels[v]);
+            while self.levels[v] < last {
+                self.swap(self.levels[v]);
+                best = best.min((self.size(roots), self.levels[v]));
+            }
+            while self.levels[v] > 0 {
+                self.swap(self.levels[v] - 1);
+                best = best.min((self.size(roots), self.levels[v]));
+            }
+            while self.levels[v] < best.1 {
+                self.swap(self.levels[v]);
+            }
+        }
+    }
+
+    pub fn from_clauses(&mut self, clauses: &Clauses) -> Bdd {
+        let mut f = Bdd::TRUE;
+        for clause in clauses.iter() {
+            let mut c = Bdd::FALSE;
+            for literal in clause.iter() {
+                let l = self.literal(literal);
+                c = self.or(c, l);
+            }
+            f = self.and(f, c);
+        }
+        f
+    }
+
+    /* CNF of f: one clause per path to `FALSE`, ruling that path out. */
+    pub fn to_clauses(&self, f: Bdd) -> Clauses {
+        fn go(m: &Manager, f: Bdd, path: &mut Vec<Literal>, out: &mut Clauses) {
+            if f == Bdd::FALSE {
+                out.push(path.iter().cloned().collect());
+                return;
+            }
+            if f == Bdd::TRUE {
+                return;
+            }
+            let Node { var, low, high } = m.nodes[f.0];
+            let name = m.names[var].clone();
+            path.push(Literal::pos(name.clone()));
+            go(m, low, path, out);
+            path.pop();
+            path.push(Literal::neg(name));
+            go(m, high, path, out);
+            path.pop();
+        }
+        let mut clauses = Clauses::new();
+        go(self, f, &mut Vec::new(), &mut clauses);
+        clauses
+    }
+
+    /* Build a propositional formula, every atom `P(t, ...)` being a variable named like
+     * `Clauses::from_formula` names it. Quantifiers are not propositional and panic.
+     * */
+    pub fn from_formula<S>(&mut self, formula: &Formula<S>) -> Bdd {
+        match formula {
+            Formula::Pred(pred) => self.var(&pred.unique()),
+            Formula::Not(not) => {
+                let f = self.from_formula(&not.formula);
+                self.not(f)
+            },
+            Formula::And(and) => {
+                let f = self.from_formula(&and.formula1);
+                let g = self.from_formula(&and.formula2);
+                self.and(f, g)
+            },
+            Formula::Or(or) => {
+                let f = self.from_formula(&or.formula1);
+                let g = self.from_formula(&or.formula2);
+                self.or(f, g)
+            },
+            Formula::Implies(imp) => {
+                let f = self.from_formula(&imp.formula1);
+                let g = self.from_formula(&imp.formula2);
+                self.implies(f, g)
+            },
+            Formula::Iff(iff) => {
+                let f = self.from_formula(&iff.formula1);
+                let g = self.from_formula(&iff.formula2);
+                self.iff(f, g)
+            },
+            _ => panic!("Expect propositional formula, got {:?}", formula)
+        }
+    }
+
+    /* Shannon expansion of f, with the trivial branches folded away. `TRUE` and `FALSE`
+     * become `P ∨ ¬P` and `P ∧ ¬P` of the top variable, or of `true` without variables.
+     * */
+    pub fn to_formula(&self, f: Bdd) -> Formula<Raw> {
+        fn go(m: &Manager, f: Bdd, memo: &mut HashMap<Bdd, Formula<Raw>>) -> Formula<Raw> {
+            if let Some(formula) = memo.get(&f) {
+                return formula.clone();
+            }
+            let Node { var, low, high } = m.nodes[f.0];
+            let x = Formula::pred(&m.names[var], vec![]);
+            let formula = match (low, high) {
+                (Bdd::FALSE, Bdd::TRUE) => x,
+                (Bdd::TRUE, Bdd::FALSE) => Formula::not(x),
+                (Bdd::FALSE, high) => Formula::and(x, go(m, high, memo)),
+                (low, Bdd::FALSE) => Formula::and(Formula::not(x), go(m, low, memo)),
+                (Bdd::TRUE, high) => Formula::or(Formula::not(x), go(m, high, memo)),
+                (low, Bdd::TRUE) => Formula::or(x, go(m, low, memo)),
+                (low, high) => Formula::or(
+                    Formula::and(x.clone(), go(m, high, memo)),
+                    Formula::and(Formula::not(x), go(m, low, memo))),
+            };
+            memo.insert(f, formula.clone());
+            formula
+        }
+        if f.is_terminal() {
+            let name = self.order.first().map(|&v| self.names[v].as_str()).unwrap_or("true");
+            let p = Formula::pred(name, vec![]);
+            return if f == Bdd::TRUE { Formula::or(p.clone(), Formula::not(p)) } else { Formula::and(p.clone(), Formula::not(p)) };
+        }
+        go(self, f, &mut HashMap::new())
+    }
+}
+
+
+/* Whether two propositional formulas are equivalent, e.g two results of `fol::parser`. */
+pub fn equivalent<S, T>(formula1: &Formula<S>, formula2: &Formula<T>) -> bool {
+    let mut manager = Manager::new();
+    let f = manager.from_formula(formula1);
+    let g = manager.from_formula(formula2);
+    f == g
+}
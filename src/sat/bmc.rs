@@ -0,0 +1,132 @@
+use std::fmt::{self, Display};
+
+use crate::sat::aig::*;
+use crate::sat::cdcl::*;
+
+
+/* A counterexample: starting from `init`, feeding `inputs[t]` at step t drives the circuit
+ * into a state where bad state property `property` holds at the last step.
+ * */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub property: usize,
+    pub init: Vec<bool>,
+    pub inputs: Vec<Vec<bool>>,
+}
+
+
+impl Trace {
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /* Simulate the trace and check that it really ends in a bad state while satisfying the
+     * invariant constraints at every step. */
+    pub fn replay(&self, aig: &Aig) -> bool {
+        let properties = properties(aig);
+        let mut state = self.init.clone();
+        for (t, inputs) in self.inputs.iter().enumerate() {
+            let values = aig.simulate(&state, inputs);
+            if !aig.constraints.iter().all(|&c| value(&values, c)) {
+                return false;
+            }
+            if t + 1 == self.inputs.len() {
+                return value(&values, properties[self.property]);
+            }
+            state = aig.latches.iter().map(|l| value(&values, l.next)).collect();
+        }
+        false
+    }
+}
+
+
+/* AIGER witness format: `1`, the violated property, the initial latch values and one line
+ * of input values per step, closed by `.`. */
+impl Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn bits(values: &[bool]) -> String {
+            values.iter().map(|&b| if b { '1' } else { '0' }).collect()
+        }
+        writeln!(f, "1")?;
+        writeln!(f, "b{}", self.property)?;
+        writeln!(f, "{}", bits(&self.init))?;
+        for inputs in self.inputs.iter() {
+            writeln!(f, "{}", bits(inputs))?;
+        }
+        writeln!(f, ".")
+    }
+}
+
+
+// bad state properties, AIGER 1.0 files without a bad section use the outputs instead.
+fn properties(aig: &Aig) -> &[AigLit] {
+    if aig.bad.is_empty() { &aig.outputs } else { &aig.bad }
+}
+
+
+/* Bounded model checking.
+ *
+ * Unroll the transition relation frame by frame into one incremental solver: frame t holds
+ * the Tseitin encoding of the circuit over variables suffixed with `@t`, latches of frame 0
+ * are fixed to their reset values and latches of frame t + 1 are tied to the next state
+ * functions of frame t. After adding frame t every property is checked by assuming it
+ * there, so the first trace found is a shortest one. Returns `None` if no property can be
+ * violated within `bound` steps after reset.
+ * */
+pub fn bmc(aig: &Aig, bound: usize) -> Option<Trace> {
+    let properties = properties(aig);
+    let mut solver = Solver::new();
+    for latch in aig.latches.iter() {
+        if let Some(init) = latch.init {
+            let lit = aig.literal(if init { latch.lit } else { latch.lit.negate() }, Some(0));
+            solver.add(&std::iter::once(lit).collect());
+        }
+    }
+
+    let mut roots = properties.to_vec();
+    roots.extend(aig.constraints.iter().cloned());
+    roots.extend(aig.latches.iter().map(|l| l.next));
+    for t in 0..=bound {
+        solver.add_clauses(&aig.tseitin(&roots, Some(t)));
+        for &constraint in aig.constraints.iter() {
+            solver.add(&std::iter::once(aig.literal(constraint, Some(t))).collect());
+        }
+        if t > 0 {
+            for latch in aig.latches.iter() {
+                let current = aig.literal(latch.lit, Some(t));
+                let next = aig.literal(latch.next, Some(t - 1));
+                solver.add(&vec![current.negate(), next.clone()].into_iter().collect());
+                solver.add(&vec![current, next.negate()].into_iter().collect());
+            }
+        }
+        for (i, &property) in properties.iter().enumerate() {
+            let bad = solver.lit(&aig.literal(property, Some(t)));
+            if solver.solve_with(&[bad]) {
+                return Some(trace(aig, &solver, i, t));
+            }
+        }
+    }
+    None
+}
+
+
+fn trace(aig: &Aig, solver: &Solver, property: usize, last: usize) -> Trace {
+    // variables outside every cone never reach the solver, any value will do for them.
+    let value = |lit: AigLit, t: usize| {
+        let literal = aig.literal(lit, Some(t));
+        match solver.lookup(literal.var_name()) {
+            Some(var) => solver.value(Lit::new(var, literal.is_negated())).unwrap_or(false),
+            None => false,
+        }
+    };
+    Trace {
+        property,
+        init: aig.latches.iter().map(|l| value(l.lit, 0)).collect(),
+        inputs: (0..=last).map(|t| aig.inputs.iter().map(|&i| value(i, t)).collect()).collect(),
+    }
+}
+
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use crate::sat::clauses::*;
+
+
+/* Brute force oracle: try all 2^n assignments. Only meant for cross-checking the real
+ * solvers on small instances, so it refuses more than `MAX_VARS` variables.
+ * */
+pub const MAX_VARS: usize = 24;
+
+
+// clauses as bitmasks over the variables, a clause is satisfied by `bits` if one of its
+// positive variables is set or one of its negative ones is not.
+struct Masks {
+    vars: Vec<String>,
+    clauses: Vec<(u32, u32)>,
+}
+
+
+impl Masks {
+    fn new(clauses: &Clauses) -> Self {
+        let mut vars = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name().to_string())).collect::<Vec<_>>();
+        vars.sort();
+        vars.dedup();
+        assert!(vars.len() <= MAX_VARS, "{} variables are too many for brute force", vars.len());
+        let index = vars.iter().enumerate().map(|(i, v)| (v.as_str(), i)).collect::<HashMap<_, _>>();
+        let clauses = clauses
+            .iter()
+            .map(|c| c.iter().fold((0, 0), |(pos, neg), l| {
+                let bit = 1 << index[l.var_name()];
+                if l.is_negated() { (pos, neg | bit) } else { (pos | bit, neg) }
+            }))
+            .collect();
+        Masks { vars, clauses }
+    }
+
+    fn satisfies(&self, bits: u32) -> bool {
+        self.clauses.iter().all(|&(pos, neg)| bits & pos != 0 || !bits & neg != 0)
+    }
+
+    fn assignments(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..1u32 << self.vars.len()).filter(move |&bits| self.satisfies(bits))
+    }
+
+    fn model(&self, bits: u32) -> Model {
+        self.vars.iter().enumerate().map(|(i, v)| (v.clone(), bits >> i & 1 == 1)).collect()
+    }
+}
+
+
+pub fn satisfiable_brute_force(clauses: Clauses) -> bool {
+    Masks::new(&clauses).assignments().next().is_some()
+}
+
+
+/* The first model in binary counting order, over the variables occurring in `clauses`. */
+pub fn model(clauses: &Clauses) -> Option<Model> {
+    let masks = Masks::new(clauses);
+    let bits = masks.assignments().next()?;
+    Some(masks.model(bits))
+}
+
+
+/* Number of models over the variables occurring in `clauses`. */
+pub fn count(clauses: &Clauses) -> u64 {
+    Masks::new(clauses).assignments().count() as u64
+}
+
+
+/* All models over the variables occurring in `clauses`. */
+pub fn models(clauses: &Clauses) -> Vec<Model> {
+    let masks = Masks::new(clauses);
+    masks.assignments().map(|bits| masks.model(bits)).collect()
+}
@@ -0,0 +1,1686 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::time::Instant;
+
+use crate::sat::checkpoint::{invalid, Decoder, Encoder};
+use crate::sat::clauses::*;
+use crate::sat::fragment;
+use crate::sat::propagator::ExternalPropagator;
+use crate::sat::stats::*;
+
+
+/* Conflict driven clause learning.
+ *
+ * Unlike `dp`, which rewrites a `Clauses` value in place, the CDCL solver keeps its own
+ * integer encoding of the problem so it can be queried again and again: clauses can be
+ * added between calls to `solve_with`, and each call may fix a set of assumption literals.
+ * When a call fails because of the assumptions, `core` tells which of them were used
+ * in the refutation.
+ *
+ * The engine is the textbook one: two watched literals, first UIP learning with clause
+ * minimization, VSIDS branching, phase saving, luby restarts and activity based clause
+ * database reduction. An `ExternalPropagator` can take part in the search.
+ *
+ * Every few thousand conflicts the solver inprocesses its clauses at a restart: it
+ * substitutes equivalent literals, drops binary clauses implied by other binary clauses
+ * and vivifies long clauses. A substituted variable leaves the search, its value in a
+ * model is the one of its representative, and clauses or assumptions mentioning it are
+ * translated on the way in.
+ * */
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Var(pub usize);
+
+
+/* A literal is a variable with a sign bit, `2 * var` is positive and `2 * var + 1` negative. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Lit(pub usize);
+
+
+impl Var {
+    pub fn pos(self) -> Lit { Lit(self.0 << 1) }
+    #[allow(clippy::should_implement_trait)]
+    pub fn neg(self) -> Lit { Lit(self.0 << 1 | 1) }
+    pub fn index(self) -> usize { self.0 }
+}
+
+
+impl Lit {
+    pub fn new(var: Var, negated: bool) -> Self {
+        Lit(var.0 << 1 | negated as usize)
+    }
+
+    pub fn var(self) -> Var { Var(self.0 >> 1) }
+    pub fn is_negated(self) -> bool { self.0 & 1 == 1 }
+    pub fn negate(self) -> Self { Lit(self.0 ^ 1) }
+    pub fn index(self) -> usize { self.0 }
+}
+
+
+// reason of a literal propagated by the external propagator, until it is asked for the clause.
+const EXTERNAL: usize = usize::MAX;
+
+// conflicts between two inprocessing rounds, growing with every round.
+const INPROCESS_INTERVAL: u64 = 2000;
+
+// work limits of a round: dfs steps of transitive reduction, and propagations of
+// vivification as a share of the propagations so far, within fixed bounds.
+const REDUCE_STEPS: usize = 100_000;
+const VIVIFY_SHARE: u64 = 50;
+const VIVIFY_PROPAGATIONS: (u64, u64) = (1_000, 20_000);
+
+
+#[derive(Debug, Clone)]
+struct ClauseData {
+    lits: Vec<Lit>,
+    learnt: bool,
+    activity: f64,
+    removed: bool,
+}
+
+
+#[derive(Debug, Clone, Copy)]
+struct Watch {
+    cref: usize,
+    blocker: Lit,
+}
+
+
+/* Binary max-heap of unassigned variables ordered by activity. */
+#[derive(Debug, Clone, Default)]
+struct VarOrder {
+    heap: Vec<usize>,
+    indices: Vec<Option<usize>>,
+}
+
+
+impl VarOrder {
+    fn grow(&mut self, n: usize) {
+        self.indices.resize(n, None);
+    }
+
+    fn contains(&self, v: usize) -> bool {
+        self.indices[v].is_some()
+    }
+
+    fn insert(&mut self, v: usize, activity: &[f64]) {
+        if self.contains(v) {
+            return;
+        }
+        self.indices[v] = Some(self.heap.len());
+        self.heap.push(v);
+        self.up(self.heap.len() - 1, activity);
+    }
+
+    fn increase(&mut self, v: usize, activity: &[f64]) {
+        if let Some(i) = self.indices[v] {
+            self.up(i, activity);
+        }
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let top = self.heap.swap_remove(0);
+        self.indices[top] = None;
+        if !self.heap.is_empty() {
+            self.indices[self.heap[0]] = Some(0);
+            self.down(0, activity);
+        }
+        Some(top)
+    }
+
+    fn up(&mut self, mut i: usize, activity: &[f64]) {
+        let v = self.heap[i];
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if activity[self.heap[parent]] >= activity[v] {
+                break;
+            }
+            self.heap[i] = self.heap[parent];
+            self.indices[self.heap[i]] = Some(i);
+            i = parent;
+        }
+        self.heap[i] = v;
+        self.indices[v] = Some(i);
+    }
+
+    fn down(&mut self, mut i: usize, activity: &[f64]) {
+        let v = self.heap[i];
+        loop {
+            let left = 2 * i + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < self.heap.len() && activity[self.heap[right]] > activity[self.heap[left]] { right } else { left };
+            if activity[self.heap[child]] <= activity[v] {
+                break;
+            }
+            self.heap[i] = self.heap[child];
+            self.indices[self.heap[i]] = Some(i);
+            i = child;
+        }
+        self.heap[i] = v;
+        self.indices[v] = Some(i);
+    }
+}
+
+
+/* Callbacks asking the search to stop, receiving learnt clauses and taking checkpoints. */
+pub type Terminate = Box<dyn FnMut() -> bool>;
+pub type Learn = Box<dyn FnMut(&[Literal])>;
+pub type Checkpoint = Box<dyn FnMut(&Solver)>;
+
+
+// a call to `solve_limited` under way, what its restart loop needs to go on.
+#[derive(Debug, Clone)]
+struct Progress {
+    assumptions: Vec<Lit>,
+    max_conflicts: Option<u64>,
+    // conflicts when the call started.
+    start: u64,
+    restarts: usize,
+}
+
+
+pub struct Solver {
+    names: Vec<String>,
+    ids: HashMap<String, Var>,
+    clauses: Vec<ClauseData>,
+    watches: Vec<Vec<Watch>>,
+    assigns: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    qhead: usize,
+    activity: Vec<f64>,
+    var_inc: f64,
+    clause_inc: f64,
+    order: VarOrder,
+    phases: Vec<bool>,
+    seen: Vec<bool>,
+    learnts: usize,
+    max_learnts: f64,
+    model: Vec<Option<bool>>,
+    core: Vec<Lit>,
+    ok: bool,
+    pub stats: Stats,
+    sink: Option<Box<dyn EventSink>>,
+    propagator: Option<Box<dyn ExternalPropagator>>,
+    observed: Vec<bool>,
+    // clauses of rejected models not added yet.
+    pending: Vec<Vec<Lit>>,
+    terminate: Option<Terminate>,
+    interrupted: bool,
+    learn: Option<(usize, Learn)>,
+    // literal equivalent to the positive literal of each variable, itself unless substituted.
+    repr: Vec<Lit>,
+    inprocessing: bool,
+    next_inprocess: u64,
+    progress: Option<Progress>,
+    // interval, next conflict count and hook.
+    checkpoint: Option<(u64, u64, Checkpoint)>,
+}
+
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            assigns: Vec::new(),
+            levels: Vec::new(),
+            reasons: Vec::new(),
+            trail: Vec::new(),
+            trail_lim: Vec::new(),
+            qhead: 0,
+            activity: Vec::new(),
+            var_inc: 1.0,
+            clause_inc: 1.0,
+            order: VarOrder::default(),
+            phases: Vec::new(),
+            seen: Vec::new(),
+            learnts: 0,
+            max_learnts: 0.0,
+            model: Vec::new(),
+            core: Vec::new(),
+            ok: true,
+            stats: Stats::new(),
+            sink: None,
+            propagator: None,
+            observed: Vec::new(),
+            pending: Vec::new(),
+            terminate: None,
+            interrupted: false,
+            learn: None,
+            repr: Vec::new(),
+            inprocessing: true,
+            next_inprocess: INPROCESS_INTERVAL,
+            progress: None,
+            checkpoint: None,
+        }
+    }
+
+    pub fn from_clauses(clauses: &Clauses) -> Self {
+        let mut solver = Solver::new();
+        solver.add_clauses(clauses);
+        solver
+    }
+
+    /* Report search events to `sink`. Without a sink no events are built at all. */
+    pub fn set_sink(&mut self, sink: Box<dyn EventSink>) {
+        self.sink = Some(sink);
+    }
+
+    pub fn take_sink(&mut self) -> Option<Box<dyn EventSink>> {
+        self.sink.take()
+    }
+
+    /* Let `propagator` take part in the search. Observed variables already fixed are
+     * notified right away. */
+    pub fn connect_propagator(&mut self, mut propagator: Box<dyn ExternalPropagator>) {
+        for lit in self.fixed() {
+            if self.observed[lit.var().0] {
+                propagator.notify_assignment(lit, true);
+            }
+        }
+        self.propagator = Some(propagator);
+    }
+
+    /* Polled on every conflict, the search gives up as soon as it returns true. Only
+     * `solve_limited` can report that, `solve_with` panics. */
+    pub fn set_terminate(&mut self, terminate: Option<Terminate>) {
+        self.terminate = terminate;
+    }
+
+    /* Called with every learnt clause of at most `max_length` literals. */
+    pub fn set_learn(&mut self, max_length: usize, learn: Option<Learn>) {
+        self.learn = learn.map(|learn| (max_length, learn));
+    }
+
+    pub fn disconnect_propagator(&mut self) -> Option<Box<dyn ExternalPropagator>> {
+        self.propagator.take()
+    }
+
+    /* Notify the propagator of the assignments to `var`. */
+    pub fn add_observed_var(&mut self, var: Var) {
+        self.cancel_until(0);
+        if self.observed[var.0] {
+            return;
+        }
+        self.observed[var.0] = true;
+        if self.is_substituted(var) {
+            // bring it back into the search, tied to its representative by two clauses.
+            let r = self.representative(var.pos());
+            self.repr[var.0] = var.pos();
+            self.order.insert(var.0, &self.activity);
+            self.add_clause(&[var.neg(), r]);
+            self.add_clause(&[var.pos(), r.negate()]);
+        }
+        if let Some(value) = self.assigns[var.0] {
+            if let Some(propagator) = self.propagator.as_mut() {
+                propagator.notify_assignment(Lit::new(var, !value), true);
+            }
+        }
+    }
+
+    pub fn remove_observed_var(&mut self, var: Var) {
+        self.observed[var.0] = false;
+    }
+
+    pub fn is_observed(&self, var: Var) -> bool {
+        self.observed[var.0]
+    }
+
+    /* Run inprocessing between restarts, on by default. */
+    pub fn set_inprocessing(&mut self, enabled: bool) {
+        self.inprocessing = enabled;
+    }
+
+    /* True if inprocessing replaced `var` by an equivalent literal. */
+    pub fn is_substituted(&self, var: Var) -> bool {
+        self.repr[var.0] != var.pos()
+    }
+
+    /* The literal standing for `lit` in the clauses, `lit` itself unless its variable
+     * was substituted. */
+    pub fn representative(&self, lit: Lit) -> Lit {
+        let mut lit = lit;
+        while self.is_substituted(lit.var()) {
+            lit = Lit(self.repr[lit.var().0].0 ^ (lit.0 & 1));
+        }
+        lit
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.names.len()
+    }
+
+    /* Number of live clauses, learnt ones included. */
+    pub fn num_clauses(&self) -> usize {
+        self.clauses.iter().filter(|c| !c.removed).count()
+    }
+
+    /* False once the clauses added so far are unsatisfiable without any assumption. */
+    pub fn is_ok(&self) -> bool {
+        self.ok
+    }
+
+    /* Get the variable called `name`, creating it on first use. */
+    pub fn var(&mut self, name: &str) -> Var {
+        if let Some(&var) = self.ids.get(name) {
+            return var;
+        }
+        let var = Var(self.names.len());
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), var);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.assigns.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.activity.push(0.0);
+        self.phases.push(false);
+        self.seen.push(false);
+        self.observed.push(false);
+        self.repr.push(var.pos());
+        self.order.grow(self.names.len());
+        self.order.insert(var.0, &self.activity);
+        var
+    }
+
+    /* A variable with a generated name that does not clash with existing ones. */
+    pub fn new_var(&mut self) -> Var {
+        let mut i = self.names.len();
+        loop {
+            let name = format!("_{}", i);
+            if !self.ids.contains_key(&name) {
+                return self.var(&name);
+            }
+            i += 1;
+        }
+    }
+
+    /* Polarity tried first when branching on `var`, until phase saving overrides it. */
+    pub fn set_phase(&mut self, var: Var, phase: bool) {
+        self.phases[var.0] = phase;
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Var> {
+        self.ids.get(name).cloned()
+    }
+
+    pub fn name(&self, var: Var) -> &str {
+        &self.names[var.0]
+    }
+
+    pub fn lit(&mut self, literal: &Literal) -> Lit {
+        let var = self.var(literal.var_name());
+        Lit::new(var, literal.is_negated())
+    }
+
+    pub fn literal(&self, lit: Lit) -> Literal {
+        let name = self.names[lit.var().0].clone();
+        if lit.is_negated() { Literal::neg(name) } else { Literal::pos(name) }
+    }
+
+    pub fn add_clauses(&mut self, clauses: &Clauses) -> bool {
+        for clause in clauses.iter() {
+            self.add(clause);
+        }
+        self.ok
+    }
+
+    pub fn add(&mut self, clause: &Clause) -> bool {
+        let lits = clause.iter().map(|l| self.lit(l)).collect::<Vec<_>>();
+        self.add_clause(&lits)
+    }
+
+    /* Add a clause permanently. Returns false if the solver became trivially unsatisfiable. */
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        if !self.ok {
+            return false;
+        }
+        self.cancel_until(0);
+        let mut lits = lits.iter().map(|&lit| self.representative(lit)).collect::<Vec<_>>();
+        lits.sort();
+        lits.dedup();
+        let mut simplified = Vec::with_capacity(lits.len());
+        for (i, &lit) in lits.iter().enumerate() {
+            if i > 0 && lits[i - 1] == lit.negate() {
+                return true; // tautology
+            }
+            match self.lit_value(lit) {
+                Some(true) => return true,
+                Some(false) => {},
+                None => simplified.push(lit),
+            }
+        }
+        match simplified.len() {
+            0 => {
+                self.ok = false;
+            },
+            1 => {
+                self.enqueue(simplified[0], None);
+                if self.propagate().is_some() {
+                    self.ok = false;
+                }
+            },
+            _ => {
+                self.attach(simplified, false);
+            }
+        }
+        self.ok
+    }
+
+    pub fn solve(&mut self) -> bool {
+        self.solve_with(&[])
+    }
+
+    /* Solve with every literal in `assumptions` forced true for this call only. */
+    pub fn solve_with(&mut self, assumptions: &[Lit]) -> bool {
+        self.solve_limited(assumptions, None).expect("unlimited search must terminate")
+    }
+
+    /* Like `solve_with`, but gives up with `None` after `max_conflicts` conflicts or when
+     * the terminate callback asks for it. */
+    pub fn solve_limited(&mut self, assumptions: &[Lit], max_conflicts: Option<u64>) -> Option<bool> {
+        self.model.clear();
+        self.core.clear();
+        self.interrupted = false;
+        if !self.ok {
+            return Some(false);
+        }
+        self.max_learnts = (self.num_clauses() as f64 / 3.0).max(100.0);
+        self.progress = Some(Progress { assumptions: assumptions.to_vec(), max_conflicts, start: self.stats.conflicts, restarts: 0 });
+        self.run()
+    }
+
+    /* Continue the call that was in progress when the solver was checkpointed, with its
+     * assumptions and conflict limit. `None` if it gives up again, or if no call was in
+     * progress. */
+    pub fn resume(&mut self) -> Option<bool> {
+        self.progress.as_ref()?;
+        self.model.clear();
+        self.core.clear();
+        self.interrupted = false;
+        self.run()
+    }
+
+    /* Call `checkpoint` at the first restart after every `every` conflicts, at level 0 and
+     * with the search state complete, so `save` from there can be resumed. */
+    pub fn set_checkpoint(&mut self, every: u64, checkpoint: Option<Checkpoint>) {
+        self.checkpoint = checkpoint.map(|checkpoint| (every, self.stats.conflicts + every, checkpoint));
+    }
+
+    /* Write the complete state to `writer`: the clauses with the learnt ones, watches, the
+     * trail at level 0, activities, saved phases, statistics but for the timings, and the
+     * call in progress if saved from a checkpoint. Callbacks, the event sink and the
+     * external propagator are not saved.
+     * */
+    pub fn save<W: Write>(&self, writer: W) -> io::Result<()> {
+        let lit = |e: &mut Encoder<W>, l: &Lit| e.usize(l.0);
+        let lits = |e: &mut Encoder<W>, c: &Vec<Lit>| e.seq(c, lit);
+        let value = |e: &mut Encoder<W>, b: &Option<bool>| e.option(*b, |e, b| e.bool(b));
+        let mut e = Encoder(writer);
+        e.header()?;
+        e.seq(&self.names, |e, n| e.str(n))?;
+        e.seq(&self.clauses, |e, c| {
+            lits(e, &c.lits)?;
+            e.bool(c.learnt)?;
+            e.f64(c.activity)?;
+            e.bool(c.removed)
+        })?;
+        e.seq(&self.watches, |e, ws| e.seq(ws, |e, w| {
+            e.usize(w.cref)?;
+            lit(e, &w.blocker)
+        }))?;
+        e.seq(&self.assigns, value)?;
+        e.seq(&self.levels, |e, &l| e.usize(l))?;
+        e.seq(&self.reasons, |e, r| e.option(*r, |e, c| e.usize(c)))?;
+        e.seq(&self.trail, lit)?;
+        e.seq(&self.trail_lim, |e, &l| e.usize(l))?;
+        e.usize(self.qhead)?;
+        e.seq(&self.activity, |e, &a| e.f64(a))?;
+        e.f64(self.var_inc)?;
+        e.f64(self.clause_inc)?;
+        e.seq(&self.order.heap, |e, &v| e.usize(v))?;
+        e.seq(&self.order.indices, |e, i| e.option(*i, |e, i| e.usize(i)))?;
+        e.seq(&self.phases, |e, &p| e.bool(p))?;
+        e.usize(self.learnts)?;
+        e.f64(self.max_learnts)?;
+        e.seq(&self.model, value)?;
+        e.seq(&self.core, lit)?;
+        e.bool(self.ok)?;
+        let stats = &self.stats;
+        for counter in [
+            stats.decisions, stats.propagations, stats.conflicts, stats.restarts, stats.pure_literals,
+            stats.eliminated_vars, stats.resolvents, stats.inprocessings, stats.substituted_vars,
+            stats.vivified_clauses, stats.reduced_binaries,
+        ] {
+            e.u64(counter)?;
+        }
+        e.seq(&stats.learned_sizes.iter().collect::<Vec<_>>(), |e, &(&size, &count)| {
+            e.usize(size)?;
+            e.u64(count)
+        })?;
+        e.seq(&self.observed, |e, &o| e.bool(o))?;
+        e.seq(&self.pending, lits)?;
+        e.seq(&self.repr, lit)?;
+        e.bool(self.inprocessing)?;
+        e.u64(self.next_inprocess)?;
+        e.option(self.progress.as_ref(), |e, p| {
+            lits(e, &p.assumptions)?;
+            e.option(p.max_conflicts, |e, m| e.u64(m))?;
+            e.u64(p.start)?;
+            e.usize(p.restarts)
+        })?;
+        e.0.flush()
+    }
+
+    /* A solver in the state written by `save`. Search from it goes exactly the way it would
+     * have gone in the saved solver. */
+    pub fn load<R: Read>(reader: R) -> io::Result<Solver> {
+        let lit = |d: &mut Decoder<R>| d.usize().map(Lit);
+        let lits = |d: &mut Decoder<R>| d.seq(lit);
+        let value = |d: &mut Decoder<R>| d.option(|d| d.bool());
+        let mut d = Decoder(reader);
+        d.header()?;
+        let mut solver = Solver::new();
+        solver.names = d.seq(|d| d.str())?;
+        solver.clauses = d.seq(|d| {
+            Ok(ClauseData { lits: lits(d)?, learnt: d.bool()?, activity: d.f64()?, removed: d.bool()? })
+        })?;
+        solver.watches = d.seq(|d| d.seq(|d| Ok(Watch { cref: d.usize()?, blocker: lit(d)? })))?;
+        solver.assigns = d.seq(value)?;
+        solver.levels = d.seq(|d| d.usize())?;
+        solver.reasons = d.seq(|d| d.option(|d| d.usize()))?;
+        solver.trail = d.seq(lit)?;
+        solver.trail_lim = d.seq(|d| d.usize())?;
+        solver.qhead = d.usize()?;
+        solver.activity = d.seq(|d| d.f64())?;
+        solver.var_inc = d.f64()?;
+        solver.clause_inc = d.f64()?;
+        solver.order.heap = d.seq(|d| d.usize())?;
+        solver.order.indices = d.seq(|d| d.option(|d| d.usize()))?;
+        solver.phases = d.seq(|d| d.bool())?;
+        solver.learnts = d.usize()?;
+        solver.max_learnts = d.f64()?;
+        solver.model = d.seq(value)?;
+        solver.core = d.seq(lit)?;
+        solver.ok = d.bool()?;
+        let stats = &mut solver.stats;
+        for counter in [
+            &mut stats.decisions, &mut stats.propagations, &mut stats.conflicts, &mut stats.restarts,
+            &mut stats.pure_literals, &mut stats.eliminated_vars, &mut stats.resolvents, &mut stats.inprocessings,
+            &mut stats.substituted_vars, &mut stats.vivified_clauses, &mut stats.reduced_binaries,
+        ] {
+            *counter = d.u64()?;
+        }
+        stats.learned_sizes = d.seq(|d| Ok((d.usize()?, d.u64()?)))?.into_iter().collect();
+        solver.observed = d.seq(|d| d.bool())?;
+        solver.pending = d.seq(lits)?;
+        solver.repr = d.seq(lit)?;
+        solver.inprocessing = d.bool()?;
+        solver.next_inprocess = d.u64()?;
+        solver.progress = d.option(|d| {
+            Ok(Progress { assumptions: lits(d)?, max_conflicts: d.option(|d| d.u64())?, start: d.u64()?, restarts: d.usize()? })
+        })?;
+
+        let n = solver.names.len();
+        solver.ids = solver.names.iter().enumerate().map(|(v, name)| (name.clone(), Var(v))).collect();
+        solver.seen = vec![false; n];
+        // reasons at level 0 are never looked at, and the propagator is gone anyway.
+        for reason in solver.reasons.iter_mut().filter(|r| **r == Some(EXTERNAL)) {
+            *reason = None;
+        }
+        solver.validate().map_err(invalid)?;
+        Ok(solver)
+    }
+
+    // sizes, indices and invariants a loaded state must agree on, so a corrupt file cannot
+    // cause panics or hangs: watched clauses have two literals and are watched by one of
+    // them, the trail holds exactly the assigned literals, each the first literal of its
+    // reason, substituted variables map to representatives, and a call in progress started
+    // at no more conflicts than there are and restarts fewer times than `luby` can count.
+    fn validate(&self) -> Result<(), String> {
+        let n = self.names.len();
+        let per_var = [
+            self.assigns.len(), self.levels.len(), self.reasons.len(), self.activity.len(), self.phases.len(),
+            self.order.indices.len(), self.observed.len(), self.repr.len(),
+        ];
+        if self.ids.len() != n || per_var.iter().any(|&len| len != n) || self.watches.len() != 2 * n {
+            return Err("inconsistent number of variables".to_string());
+        }
+        let lit_ok = |l: &Lit| l.0 < 2 * n;
+        let cref_ok = |c: usize| c < self.clauses.len();
+        let clauses_ok = self.clauses.iter().all(|c| c.lits.iter().all(lit_ok) && (c.removed || !c.lits.is_empty()));
+        let watches_ok = self.watches.iter().flatten().all(|w| cref_ok(w.cref) && lit_ok(&w.blocker));
+        let lits_ok = self.trail.iter().chain(self.core.iter()).chain(self.repr.iter()).chain(self.pending.iter().flatten()).all(lit_ok)
+            && self.progress.iter().flat_map(|p| p.assumptions.iter()).all(lit_ok);
+        let reasons_ok = self.reasons.iter().flatten().all(|&c| cref_ok(c));
+        let order_ok = self.order.heap.iter().enumerate().all(|(i, &v)| v < n && self.order.indices[v] == Some(i))
+            && self.order.indices.iter().flatten().all(|&i| i < self.order.heap.len());
+        let trail_ok = self.qhead <= self.trail.len() && self.trail_lim.iter().all(|&l| l <= self.trail.len());
+        if !(clauses_ok && watches_ok && lits_ok && reasons_ok && order_ok && trail_ok && self.model.len() <= n) {
+            return Err("corrupt solver state".to_string());
+        }
+        if self.progress.as_ref().is_some_and(|p| p.start > self.stats.conflicts || p.restarts > MAX_RESTARTS) {
+            return Err("corrupt call in progress".to_string());
+        }
+        let repr_ok = self.repr.iter().enumerate().all(|(v, &r)| {
+            r == Var(v).pos() || (r.var().0 != v && !self.is_substituted(r.var()))
+        });
+        if !repr_ok {
+            return Err("corrupt substitution".to_string());
+        }
+
+        let watched = self.watches.iter().enumerate().all(|(l, ws)| ws.iter().all(|w| {
+            let c = &self.clauses[w.cref];
+            c.removed || (c.lits.len() >= 2 && (c.lits[0].0 == l || c.lits[1].0 == l))
+        }));
+        if !watched {
+            return Err("corrupt watches".to_string());
+        }
+        let mut on_trail = vec![false; n];
+        for &lit in self.trail.iter() {
+            let v = lit.var().0;
+            if on_trail[v] || self.lit_value(lit) != Some(true) {
+                return Err("corrupt trail".to_string());
+            }
+            on_trail[v] = true;
+            if self.reasons[v].is_some_and(|c| self.clauses[c].removed || self.clauses[c].lits.first() != Some(&lit)) {
+                return Err("corrupt reasons".to_string());
+            }
+        }
+        let unassigned = (0..n).all(|v| on_trail[v] || (self.assigns[v].is_none() && self.reasons[v].is_none()));
+        if !unassigned || self.trail_lim.windows(2).any(|w| w[0] > w[1]) {
+            return Err("corrupt trail".to_string());
+        }
+        Ok(())
+    }
+
+    // the restart loop of the call in `progress`.
+    fn run(&mut self) -> Option<bool> {
+        let result = loop {
+            let Progress { ref assumptions, max_conflicts, start, restarts } = *self.progress.as_ref().unwrap();
+            let budget = luby(2.0, restarts) * 100.0;
+            let budget = match max_conflicts {
+                Some(max) => {
+                    let used = self.stats.conflicts - start;
+                    if used >= max {
+                        break None;
+                    }
+                    (budget as u64).min(max - used)
+                },
+                None => budget as u64,
+            };
+            // inprocessing may have substituted some of them since the last restart.
+            let mapped = assumptions.iter().map(|&lit| self.representative(lit)).collect::<Vec<_>>();
+            match self.search(&mapped, budget) {
+                Some(result) => break Some(result),
+                None if self.interrupted => break None,
+                None => {
+                    self.progress.as_mut().unwrap().restarts += 1;
+                    self.stats.restarts += 1;
+                    self.emit(|_| Event::Restart);
+                    self.max_learnts *= 1.1;
+                    if self.inprocessing && self.stats.conflicts >= self.next_inprocess {
+                        self.inprocess();
+                        self.next_inprocess = self.stats.conflicts + INPROCESS_INTERVAL * (self.stats.inprocessings + 1);
+                    }
+                    if let Some((every, next, mut checkpoint)) = self.checkpoint.take() {
+                        let next = if self.stats.conflicts >= next {
+                            checkpoint(self);
+                            self.stats.conflicts + every
+                        } else {
+                            next
+                        };
+                        self.checkpoint = Some((every, next, checkpoint));
+                    }
+                }
+            }
+        };
+        let assumptions = self.progress.take().unwrap().assumptions;
+        match result {
+            Some(true) => {
+                self.model = self.assigns.clone();
+                for v in 0..self.model.len() {
+                    if self.is_substituted(Var(v)) {
+                        let r = self.representative(Var(v).pos());
+                        self.model[v] = self.assigns[r.var().0].map(|b| b != r.is_negated());
+                    }
+                }
+            },
+            Some(false) => {
+                // the core is over representatives, report the assumptions they stand for.
+                let core = std::mem::take(&mut self.core);
+                for lit in core {
+                    for &a in assumptions.iter() {
+                        if self.representative(a) == lit && !self.core.contains(&a) {
+                            self.core.push(a);
+                        }
+                    }
+                }
+            },
+            None => {},
+        }
+        self.cancel_until(0);
+        result
+    }
+
+    /* Value of `lit` in the model found by the last successful call. */
+    pub fn value(&self, lit: Lit) -> Option<bool> {
+        self.model.get(lit.var().0).cloned().flatten().map(|b| b != lit.is_negated())
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+            .iter()
+            .enumerate()
+            .filter_map(|(v, b)| b.map(|b| (self.names[v].clone(), b)))
+            .collect()
+    }
+
+    /* Assumptions of the last failed call that took part in the refutation. Empty if the
+     * clauses are unsatisfiable on their own. */
+    pub fn core(&self) -> &[Lit] {
+        &self.core
+    }
+
+    pub fn failed(&self, lit: Lit) -> bool {
+        self.core.contains(&lit)
+    }
+
+    /* Literals fixed at decision level 0, these hold in every model. */
+    pub fn fixed(&self) -> Vec<Lit> {
+        let end = self.trail_lim.first().cloned().unwrap_or(self.trail.len());
+        let mut fixed = self.trail[..end].to_vec();
+        for v in (0..self.num_vars()).map(Var) {
+            let r = self.representative(v.pos());
+            if r != v.pos() && self.levels[r.var().0] == 0 {
+                if let Some(value) = self.lit_value(r) {
+                    fixed.push(Lit::new(v, !value));
+                }
+            }
+        }
+        fixed
+    }
+
+    fn emit(&mut self, event: impl FnOnce(&Solver) -> Event) {
+        if self.sink.is_some() {
+            let event = event(self);
+            if let Some(sink) = self.sink.as_mut() {
+                sink.event(&event);
+            }
+        }
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn lit_value(&self, lit: Lit) -> Option<bool> {
+        self.assigns[lit.var().0].map(|b| b != lit.is_negated())
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>, learnt: bool) -> usize {
+        let cref = self.clauses.len();
+        self.watches[lits[0].0].push(Watch { cref, blocker: lits[1] });
+        self.watches[lits[1].0].push(Watch { cref, blocker: lits[0] });
+        self.clauses.push(ClauseData { lits, learnt, activity: 0.0, removed: false });
+        if learnt {
+            self.learnts += 1;
+        }
+        cref
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let v = lit.var().0;
+        self.assigns[v] = Some(!lit.is_negated());
+        self.levels[v] = self.decision_level();
+        self.reasons[v] = reason;
+        self.trail.push(lit);
+        if self.observed[v] {
+            let fixed = self.levels[v] == 0;
+            if let Some(propagator) = self.propagator.as_mut() {
+                propagator.notify_assignment(lit, fixed);
+            }
+        }
+    }
+
+    fn new_decision_level(&mut self) {
+        self.trail_lim.push(self.trail.len());
+        if let Some(propagator) = self.propagator.as_mut() {
+            propagator.notify_new_decision_level();
+        }
+    }
+
+    fn cancel_until(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let lim = self.trail_lim[level];
+        for i in (lim..self.trail.len()).rev() {
+            let v = self.trail[i].var().0;
+            self.phases[v] = self.assigns[v].unwrap_or(false);
+            self.assigns[v] = None;
+            self.reasons[v] = None;
+            self.order.insert(v, &self.activity);
+        }
+        self.trail.truncate(lim);
+        self.trail_lim.truncate(level);
+        self.qhead = self.trail.len();
+        if let Some(propagator) = self.propagator.as_mut() {
+            propagator.notify_backtrack(level);
+        }
+    }
+
+    /* Reason clause of variable `v`, asking the external propagator for it if needed. The
+     * clause is kept as a learnt one with the propagated literal first and the literal
+     * assigned last second, as if it had propagated itself. */
+    fn reason(&mut self, v: usize) -> Option<usize> {
+        if self.reasons[v] != Some(EXTERNAL) {
+            return self.reasons[v];
+        }
+        let lit = Lit::new(Var(v), !self.assigns[v].expect("reason of an unassigned variable"));
+        let mut lits = self.propagator.as_mut().expect("external reason without a propagator").add_reason_clause(lit);
+        lits.sort();
+        lits.dedup();
+        assert!(
+            lits.contains(&lit) && lits.iter().all(|&q| q == lit || self.lit_value(q) == Some(false)),
+            "the reason of a propagation must contain it and be false otherwise",
+        );
+        lits.sort_by_key(|&q| (q != lit, std::cmp::Reverse(self.levels[q.var().0])));
+        let cref = if lits.len() == 1 {
+            // a unit reason is never watched, it only serves conflict analysis.
+            self.clauses.push(ClauseData { lits, learnt: false, activity: 0.0, removed: false });
+            self.clauses.len() - 1
+        } else {
+            self.attach(lits, true)
+        };
+        self.reasons[v] = Some(cref);
+        Some(cref)
+    }
+
+    /* Add a clause in the middle of the search. The trail is cut back to the level where
+     * the clause would have propagated, so the watches stay valid. Returns the clause if it
+     * is falsified, then at its own highest decision level ready for conflict analysis.
+     * */
+    fn add_during_search(&mut self, lits: Vec<Lit>, learnt: bool) -> Option<usize> {
+        let mut lits = lits.into_iter().map(|lit| self.representative(lit)).collect::<Vec<_>>();
+        lits.sort();
+        lits.dedup();
+        if lits.windows(2).any(|w| w[0] == w[1].negate()) {
+            return None;
+        }
+        let fixed = |s: &Solver, q: Lit| if s.levels[q.var().0] == 0 { s.lit_value(q) } else { None };
+        if lits.iter().any(|&q| fixed(self, q) == Some(true)) {
+            return None;
+        }
+        lits.retain(|&q| fixed(self, q) != Some(false));
+        // literals not false first, then the false ones from the last assigned.
+        lits.sort_by_key(|&q| {
+            let falsified = self.lit_value(q) == Some(false);
+            (falsified, std::cmp::Reverse(if falsified { self.levels[q.var().0] } else { 0 }))
+        });
+        let level = |s: &Solver, q: Lit| s.levels[q.var().0];
+        match lits.len() {
+            0 => {
+                self.cancel_until(0);
+                self.ok = false;
+                None
+            },
+            1 => {
+                self.cancel_until(0);
+                self.enqueue(lits[0], None);
+                None
+            },
+            _ if self.lit_value(lits[0]) == Some(false) => {
+                self.cancel_until(level(self, lits[0]));
+                Some(self.attach(lits, learnt))
+            },
+            _ if self.lit_value(lits[1]) == Some(false) => {
+                let (first, second) = (lits[0], level(self, lits[1]));
+                if self.lit_value(first).is_none() || level(self, first) > second {
+                    self.cancel_until(second);
+                    let cref = self.attach(lits, learnt);
+                    self.enqueue(first, Some(cref));
+                } else {
+                    self.attach(lits, learnt);
+                }
+                None
+            },
+            _ => {
+                self.attach(lits, learnt);
+                None
+            },
+        }
+    }
+
+    /* Unit propagation interleaved with the external propagator until neither has anything
+     * to add, returning the conflicting clause if any. */
+    fn propagate_external(&mut self) -> Option<usize> {
+        loop {
+            if let Some(conflict) = self.propagate() {
+                return Some(conflict);
+            }
+            if self.propagator.is_none() && self.pending.is_empty() {
+                return None;
+            }
+            while let Some(lit) = self.propagator.as_mut().and_then(|p| p.propagate()) {
+                match self.lit_value(lit) {
+                    Some(true) => {},
+                    Some(false) => {
+                        let reason = self.propagator.as_mut().unwrap().add_reason_clause(lit);
+                        if let Some(conflict) = self.add_during_search(reason, true) {
+                            return Some(conflict);
+                        }
+                        break;
+                    },
+                    None => {
+                        self.enqueue(lit, Some(EXTERNAL));
+                        let level = self.decision_level();
+                        self.emit(|s| Event::Propagation { literal: s.literal(lit), level });
+                    },
+                }
+            }
+            while let Some(clause) = self.pending.pop().or_else(|| self.propagator.as_mut().and_then(|p| p.add_external_clause())) {
+                if let Some(conflict) = self.add_during_search(clause, false) {
+                    return Some(conflict);
+                }
+                if !self.ok {
+                    return None;
+                }
+            }
+            if self.qhead == self.trail.len() {
+                return None;
+            }
+        }
+    }
+
+    /* Let the propagator check a complete assignment. A rejected one comes with a clause
+     * it violates, left for `propagate_external` to add. */
+    fn accept_model(&mut self) -> bool {
+        let trail = self.trail.clone();
+        let Some(propagator) = self.propagator.as_mut() else {
+            return true;
+        };
+        if propagator.check_found_model(&trail) {
+            return true;
+        }
+        let clause = propagator.add_external_clause().expect("a rejected model needs a clause excluding it");
+        assert!(
+            clause.iter().all(|&q| self.lit_value(q) == Some(false)),
+            "the clause excluding a rejected model must be false in it",
+        );
+        self.pending.push(clause);
+        false
+    }
+
+    /* Propagate everything on the trail, returning the conflicting clause if any. */
+    fn propagate(&mut self) -> Option<usize> {
+        let start = Instant::now();
+        let mut conflict = None;
+        while self.qhead < self.trail.len() {
+            let p = self.trail[self.qhead];
+            self.qhead += 1;
+            self.stats.propagations += 1;
+            let false_lit = p.negate();
+            let mut ws = std::mem::take(&mut self.watches[false_lit.0]);
+            let mut i = 0;
+            let mut j = 0;
+            while i < ws.len() {
+                let w = ws[i];
+                i += 1;
+                if self.lit_value(w.blocker) == Some(true) {
+                    ws[j] = w;
+                    j += 1;
+                    continue;
+                }
+                let cref = w.cref;
+                if self.clauses[cref].removed {
+                    continue;
+                }
+                if self.clauses[cref].lits[0] == false_lit {
+                    self.clauses[cref].lits.swap(0, 1);
+                }
+                let first = self.clauses[cref].lits[0];
+                let watch = Watch { cref, blocker: first };
+                if first != w.blocker && self.lit_value(first) == Some(true) {
+                    ws[j] = watch;
+                    j += 1;
+                    continue;
+                }
+
+                let mut moved = false;
+                for k in 2..self.clauses[cref].lits.len() {
+                    let lit = self.clauses[cref].lits[k];
+                    if self.lit_value(lit) != Some(false) {
+                        self.clauses[cref].lits.swap(1, k);
+                        self.watches[lit.0].push(watch);
+                        moved = true;
+                        break;
+                    }
+                }
+                if moved {
+                    continue;
+                }
+
+                ws[j] = watch;
+                j += 1;
+                if self.lit_value(first) == Some(false) {
+                    conflict = Some(cref);
+                    self.qhead = self.trail.len();
+                    while i < ws.len() {
+                        ws[j] = ws[i];
+                        j += 1;
+                        i += 1;
+                    }
+                } else {
+                    self.enqueue(first, Some(cref));
+                    let level = self.decision_level();
+                    self.emit(|s| Event::Propagation { literal: s.literal(first), level });
+                }
+            }
+            ws.truncate(j);
+            self.watches[false_lit.0] = ws;
+            if conflict.is_some() {
+                break;
+            }
+        }
+        self.stats.record(Phase::Propagation, start.elapsed());
+        conflict
+    }
+
+    /* First UIP conflict analysis. Returns the learnt clause with the asserting literal
+     * first and a literal of the backjump level second, and the backjump level. */
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let start = Instant::now();
+        let mut learnt = vec![Lit(0)];
+        let mut path = 0;
+        let mut p: Option<Lit> = None;
+        let mut index = self.trail.len();
+        let mut cref = conflict;
+        loop {
+            self.bump_clause(cref);
+            let skip = if p.is_some() { 1 } else { 0 };
+            for k in skip..self.clauses[cref].lits.len() {
+                let q = self.clauses[cref].lits[k];
+                let v = q.var().0;
+                if !self.seen[v] && self.levels[v] > 0 {
+                    self.bump_var(v);
+                    self.seen[v] = true;
+                    if self.levels[v] >= self.decision_level() {
+                        path += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index].var().0] {
+                    break;
+                }
+            }
+            let next = self.trail[index];
+            self.seen[next.var().0] = false;
+            path -= 1;
+            p = Some(next);
+            if path == 0 {
+                break;
+            }
+            cref = self.reason(next.var().0).expect("implied literal without reason");
+        }
+        learnt[0] = p.unwrap().negate();
+
+        // drop literals implied by the rest of the clause.
+        let to_clear = learnt.clone();
+        let mut minimized = vec![learnt[0]];
+        for &lit in learnt[1..].iter() {
+            let redundant = match self.reasons[lit.var().0] {
+                // external reasons are not worth asking for here.
+                None | Some(EXTERNAL) => false,
+                Some(reason) => self.clauses[reason].lits[1..].iter().all(|q| {
+                    self.seen[q.var().0] || self.levels[q.var().0] == 0
+                }),
+            };
+            if !redundant {
+                minimized.push(lit);
+            }
+        }
+        for lit in to_clear {
+            self.seen[lit.var().0] = false;
+        }
+
+        let mut level = 0;
+        if minimized.len() > 1 {
+            let mut max = 1;
+            for k in 2..minimized.len() {
+                if self.levels[minimized[k].var().0] > self.levels[minimized[max].var().0] {
+                    max = k;
+                }
+            }
+            minimized.swap(1, max);
+            level = self.levels[minimized[1].var().0];
+        }
+        self.stats.record(Phase::Analysis, start.elapsed());
+        (minimized, level)
+    }
+
+    /* Collect the assumptions that made `p` true, `p` being the negation of a failed
+     * assumption. */
+    fn analyze_final(&mut self, p: Lit) {
+        self.core.clear();
+        self.core.push(p.negate());
+        if self.decision_level() == 0 {
+            return;
+        }
+        self.seen[p.var().0] = true;
+        for i in (self.trail_lim[0]..self.trail.len()).rev() {
+            let lit = self.trail[i];
+            let v = lit.var().0;
+            if !self.seen[v] {
+                continue;
+            }
+            match self.reason(v) {
+                None => self.core.push(lit),
+                Some(reason) => {
+                    for k in 1..self.clauses[reason].lits.len() {
+                        let q = self.clauses[reason].lits[k].var().0;
+                        if self.levels[q] > 0 {
+                            self.seen[q] = true;
+                        }
+                    }
+                },
+            }
+            self.seen[v] = false;
+        }
+        self.seen[p.var().0] = false;
+    }
+
+    fn bump_var(&mut self, v: usize) {
+        self.activity[v] += self.var_inc;
+        if self.activity[v] > 1e100 {
+            for a in self.activity.iter_mut() {
+                *a *= 1e-100;
+            }
+            self.var_inc *= 1e-100;
+        }
+        self.order.increase(v, &self.activity);
+    }
+
+    fn bump_clause(&mut self, cref: usize) {
+        if !self.clauses[cref].learnt {
+            return;
+        }
+        self.clauses[cref].activity += self.clause_inc;
+        if self.clauses[cref].activity > 1e20 {
+            for c in self.clauses.iter_mut().filter(|c| c.learnt) {
+                c.activity *= 1e-20;
+            }
+            self.clause_inc *= 1e-20;
+        }
+    }
+
+    fn pick_branch(&mut self) -> Option<Lit> {
+        if let Some(lit) = self.propagator.as_mut().and_then(|p| p.decide()) {
+            if self.lit_value(lit).is_none() {
+                return Some(lit);
+            }
+        }
+        while let Some(v) = self.order.pop(&self.activity) {
+            if self.assigns[v].is_none() && !self.is_substituted(Var(v)) {
+                return Some(Lit::new(Var(v), !self.phases[v]));
+            }
+        }
+        None
+    }
+
+    fn locked(&self, cref: usize) -> bool {
+        let first = self.clauses[cref].lits[0];
+        self.reasons[first.var().0] == Some(cref) && self.lit_value(first) == Some(true)
+    }
+
+    /* Throw away the less active half of the learnt clauses, binary clauses are kept. */
+    fn reduce_db(&mut self) {
+        let start = Instant::now();
+        let mut candidates = (0..self.clauses.len())
+            .filter(|&c| {
+                let clause = &self.clauses[c];
+                clause.learnt && !clause.removed && clause.lits.len() > 2 && !self.locked(c)
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|&a, &b| self.clauses[a].activity.partial_cmp(&self.clauses[b].activity).unwrap());
+        for &c in candidates[..candidates.len() / 2].iter() {
+            self.clauses[c].removed = true;
+            self.clauses[c].lits = Vec::new();
+            self.learnts -= 1;
+        }
+        let clauses = &self.clauses;
+        for ws in self.watches.iter_mut() {
+            ws.retain(|w| !clauses[w.cref].removed);
+        }
+        self.stats.record(Phase::ReduceDb, start.elapsed());
+    }
+
+    /* One round of inprocessing at decision level 0: equivalent literal substitution,
+     * transitive reduction of the binary clauses and vivification, each followed by a
+     * rebuild of the clause database. Skipped while an external propagator is connected,
+     * as its clauses and reasons refer to the variables it observes. Returns false if the
+     * clauses turned out unsatisfiable.
+     * */
+    pub fn inprocess(&mut self) -> bool {
+        self.cancel_until(0);
+        if !self.ok || self.propagator.is_some() {
+            return self.ok;
+        }
+        let start = Instant::now();
+        self.stats.inprocessings += 1;
+        let clauses = self.live_clauses();
+        let clauses = self.substitute(clauses);
+        let clauses = self.reduce_binaries(clauses);
+        if self.rebuild(clauses) {
+            self.vivify();
+            let clauses = self.live_clauses();
+            self.rebuild(clauses);
+        }
+        self.stats.record(Phase::Inprocessing, start.elapsed());
+        self.ok
+    }
+
+    // the clauses not satisfied at level 0, without their false literals.
+    fn live_clauses(&self) -> Vec<ClauseData> {
+        self.clauses
+            .iter()
+            .filter(|c| !c.removed && !c.lits.iter().any(|&q| self.lit_value(q) == Some(true)))
+            .map(|c| {
+                let lits = c.lits.iter().cloned().filter(|&q| self.lit_value(q).is_none()).collect();
+                ClauseData { lits, ..c.clone() }
+            })
+            .collect()
+    }
+
+    /* Replace the clause database by `clauses`, at level 0. Units are enqueued and
+     * propagated, so this returns false if the clauses are unsatisfiable by propagation. */
+    fn rebuild(&mut self, clauses: Vec<ClauseData>) -> bool {
+        for ws in self.watches.iter_mut() {
+            ws.clear();
+        }
+        for &lit in self.trail.iter() {
+            self.reasons[lit.var().0] = None;
+        }
+        self.clauses.clear();
+        self.learnts = 0;
+        for clause in clauses {
+            if clause.lits.iter().any(|&q| self.lit_value(q) == Some(true)) {
+                continue;
+            }
+            let lits = clause.lits.into_iter().filter(|&q| self.lit_value(q).is_none()).collect::<Vec<_>>();
+            match lits.len() {
+                0 => {
+                    self.ok = false;
+                    return false;
+                },
+                1 => self.enqueue(lits[0], None),
+                _ => {
+                    let cref = self.attach(lits, clause.learnt);
+                    self.clauses[cref].activity = clause.activity;
+                },
+            }
+        }
+        if self.propagate().is_some() {
+            self.ok = false;
+        }
+        self.ok
+    }
+
+    /* Equivalent literal substitution. Literals in one strongly connected component of the
+     * binary implication graph are equivalent, so all of them are replaced by one
+     * representative, the one of the smallest variable. Observed variables are only ever
+     * representatives, so the propagator keeps seeing them.
+     * */
+    fn substitute(&mut self, clauses: Vec<ClauseData>) -> Vec<ClauseData> {
+        let mut graph = vec![Vec::new(); 2 * self.num_vars()];
+        for clause in clauses.iter().filter(|c| c.lits.len() == 2) {
+            let (a, b) = (clause.lits[0], clause.lits[1]);
+            graph[a.negate().0].push(b.0);
+            graph[b.negate().0].push(a.0);
+        }
+        let component = fragment::tarjan(&graph);
+        let mut best: Vec<Option<Lit>> = vec![None; 2 * self.num_vars()];
+        for v in (0..self.num_vars()).map(Var) {
+            if component[v.pos().0] == component[v.neg().0] {
+                self.ok = false;
+                return clauses;
+            }
+            for lit in [v.pos(), v.neg()] {
+                let key = |q: Lit| (!self.observed[q.var().0], q.var());
+                let best = &mut best[component[lit.0]];
+                if best.is_none_or(|b| key(lit) < key(b)) {
+                    *best = Some(lit);
+                }
+            }
+        }
+        let mut substituted = 0;
+        for v in (0..self.num_vars()).map(Var) {
+            let r = best[component[v.pos().0]].unwrap();
+            if r.var() != v && !self.observed[v.0] && self.assigns[v.0].is_none() && !self.is_substituted(v) {
+                self.repr[v.0] = r;
+                substituted += 1;
+            }
+        }
+        if substituted == 0 {
+            return clauses;
+        }
+        self.stats.substituted_vars += substituted;
+        clauses
+            .into_iter()
+            .filter_map(|c| {
+                let mut lits = c.lits.iter().map(|&q| self.representative(q)).collect::<Vec<_>>();
+                lits.sort();
+                lits.dedup();
+                if lits.windows(2).any(|w| w[0] == w[1].negate()) {
+                    return None;
+                }
+                Some(ClauseData { lits, ..c })
+            })
+            .collect()
+    }
+
+    /* Drop binary clauses whose implication `¬a → b` also follows from a path through
+     * other binary clauses. The search for such a path is depth first with a step budget. */
+    fn reduce_binaries(&mut self, mut clauses: Vec<ClauseData>) -> Vec<ClauseData> {
+        let mut graph: Vec<Vec<(usize, usize)>> = vec![Vec::new(); 2 * self.num_vars()];
+        for (i, clause) in clauses.iter().enumerate().filter(|(_, c)| c.lits.len() == 2) {
+            let (a, b) = (clause.lits[0], clause.lits[1]);
+            graph[a.negate().0].push((b.0, i));
+            graph[b.negate().0].push((a.0, i));
+        }
+        let mut visited = vec![usize::MAX; graph.len()];
+        let mut steps = 0;
+        for i in 0..clauses.len() {
+            if clauses[i].lits.len() != 2 || steps > REDUCE_STEPS {
+                continue;
+            }
+            let (from, to) = (clauses[i].lits[0].negate().0, clauses[i].lits[1].0);
+            let mut stack = vec![from];
+            visited[from] = i;
+            let mut found = false;
+            while let Some(node) = stack.pop() {
+                for &(next, j) in graph[node].iter() {
+                    steps += 1;
+                    if j == i || clauses[j].removed || visited[next] == i {
+                        continue;
+                    }
+                    if next == to {
+                        found = true;
+                        break;
+                    }
+                    visited[next] = i;
+                    stack.push(next);
+                }
+                if found {
+                    break;
+                }
+            }
+            if found {
+                clauses[i].removed = true;
+                self.stats.reduced_binaries += 1;
+            }
+        }
+        clauses.retain(|c| !c.removed);
+        clauses
+    }
+
+    /* Vivification: propagate the negation of a clause's literals one by one, without the
+     * clause itself. A conflict, or a literal of the clause becoming true, shows a prefix
+     * already implies the clause, and literals becoming false can go. The clause is hidden
+     * by marking it removed, which also drops watches on the way, so the caller rebuilds
+     * the database afterwards.
+     * */
+    fn vivify(&mut self) {
+        let (low, high) = VIVIFY_PROPAGATIONS;
+        let limit = self.stats.propagations + (self.stats.propagations / VIVIFY_SHARE).clamp(low, high);
+        let mut candidates = (0..self.clauses.len()).filter(|&c| self.clauses[c].lits.len() > 2).collect::<Vec<_>>();
+        // original clauses first, then the most active learnt ones.
+        candidates.sort_by(|&a, &b| {
+            let (a, b) = (&self.clauses[a], &self.clauses[b]);
+            a.learnt.cmp(&b.learnt).then(b.activity.partial_cmp(&a.activity).unwrap())
+        });
+        let mut shortened = Vec::new();
+        for cref in candidates {
+            if self.stats.propagations > limit {
+                break;
+            }
+            let lits = self.clauses[cref].lits.clone();
+            if lits.iter().any(|&q| self.lit_value(q) == Some(true)) {
+                continue;
+            }
+            self.clauses[cref].removed = true;
+            let mut kept = Vec::with_capacity(lits.len());
+            for &lit in lits.iter() {
+                match self.lit_value(lit) {
+                    Some(true) => {
+                        kept.push(lit);
+                        break;
+                    },
+                    Some(false) => {},
+                    None => {
+                        kept.push(lit);
+                        self.new_decision_level();
+                        self.enqueue(lit.negate(), None);
+                        if self.propagate().is_some() {
+                            break;
+                        }
+                    },
+                }
+            }
+            self.cancel_until(0);
+            self.clauses[cref].removed = false;
+            if kept.len() < lits.len() {
+                shortened.push((cref, kept));
+            }
+        }
+        // still watched by their old literals until the rebuild.
+        self.stats.vivified_clauses += shortened.len() as u64;
+        for (cref, lits) in shortened {
+            self.clauses[cref].lits = lits;
+        }
+    }
+
+    /* Search until a model, a refutation or `budget` conflicts, in which case `None` is
+     * returned and the solver should restart. */
+    fn search(&mut self, assumptions: &[Lit], budget: u64) -> Option<bool> {
+        let mut conflicts = 0;
+        loop {
+            let conflict = self.propagate_external();
+            if !self.ok {
+                return Some(false);
+            }
+            if let Some(conflict) = conflict {
+                self.stats.conflicts += 1;
+                conflicts += 1;
+                let level = self.decision_level();
+                self.emit(|_| Event::Conflict { level });
+                if level == 0 {
+                    self.ok = false;
+                    return Some(false);
+                }
+                if self.terminate.as_mut().is_some_and(|terminate| terminate()) {
+                    self.interrupted = true;
+                    self.cancel_until(0);
+                    return None;
+                }
+                let (learnt, backjump) = self.analyze(conflict);
+                self.cancel_until(backjump);
+                self.stats.learn(learnt.len());
+                let size = learnt.len();
+                self.emit(|_| Event::Learned { size });
+                if let Some((max_length, learn)) = self.learn.as_mut() {
+                    if size <= *max_length {
+                        let names = &self.names;
+                        let clause = learnt.iter().map(|&q| {
+                            let name = names[q.var().0].clone();
+                            if q.is_negated() { Literal::neg(name) } else { Literal::pos(name) }
+                        }).collect::<Vec<_>>();
+                        learn(&clause);
+                    }
+                }
+                let asserting = learnt[0];
+                if learnt.len() == 1 {
+                    self.enqueue(asserting, None);
+                } else {
+                    let cref = self.attach(learnt, true);
+                    self.bump_clause(cref);
+                    self.enqueue(asserting, Some(cref));
+                }
+                self.var_inc /= 0.95;
+                self.clause_inc /= 0.999;
+            } else {
+                if conflicts >= budget {
+                    self.cancel_until(0);
+                    return None;
+                }
+                if self.learnts as f64 >= self.max_learnts + self.trail.len() as f64 {
+                    self.reduce_db();
+                }
+
+                let mut next = None;
+                while self.decision_level() < assumptions.len() {
+                    let p = assumptions[self.decision_level()];
+                    match self.lit_value(p) {
+                        Some(true) => self.new_decision_level(),
+                        Some(false) => {
+                            self.analyze_final(p.negate());
+                            return Some(false);
+                        },
+                        None => {
+                            next = Some(p);
+                            break;
+                        }
+                    }
+                }
+                let next = match next {
+                    Some(lit) => lit,
+                    None => match self.pick_branch() {
+                        Some(lit) => {
+                            self.stats.decisions += 1;
+                            lit
+                        },
+                        None if self.accept_model() => return Some(true),
+                        None => continue,
+                    }
+                };
+                self.new_decision_level();
+                self.enqueue(next, None);
+                let level = self.decision_level();
+                self.emit(|s| Event::Decision { literal: s.literal(next), level });
+            }
+        }
+    }
+}
+
+
+/* Finite subsequences of the luby sequence 1, 1, 2, 1, 1, 2, 4, 1, ... scaled by `y`. */
+// the restarts of one call `luby` is defined for, the size of its sequence doubles past
+// the count.
+const MAX_RESTARTS: usize = usize::MAX / 4;
+
+
+fn luby(y: f64, mut x: usize) -> f64 {
+    let mut size = 1;
+    let mut seq = 0;
+    while size < x + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != x {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        x %= size;
+    }
+    y.powi(seq)
+}
+
+
+pub fn satisfiable_cdcl(clauses: Clauses) -> bool {
+    Solver::from_clauses(&clauses).solve()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `solver` saved, loaded and saved again after `tamper` changed the loaded state, then
+    // loaded once more.
+    fn reload(solver: &Solver, tamper: &dyn Fn(&mut Solver)) -> io::Result<Solver> {
+        let mut bytes = Vec::new();
+        solver.save(&mut bytes)?;
+        let mut copy = Solver::load(&bytes[..])?;
+        tamper(&mut copy);
+        let mut bytes = Vec::new();
+        copy.save(&mut bytes)?;
+        Solver::load(&bytes[..])
+    }
+
+    #[test]
+    fn test_load_refuses_broken_invariants() {
+        let mut solver = Solver::new();
+        let (a, b, c) = (solver.var("a"), solver.var("b"), solver.var("c"));
+        solver.add_clause(&[a.pos(), b.pos()]);
+        solver.add_clause(&[a.neg(), b.neg()]);
+        solver.add_clause(&[c.pos()]);
+        assert!(reload(&solver, &|_| {}).is_ok());
+        let refused = |tamper: &dyn Fn(&mut Solver)| {
+            reload(&solver, tamper).err().map(|e| e.kind()) == Some(io::ErrorKind::InvalidData)
+        };
+        // a ∨ b cut to a, still watched by b.
+        assert!(refused(&|s| s.clauses[0].lits.truncate(1)));
+        // the watch of a on ¬a ∨ ¬b.
+        assert!(refused(&|s| s.watches[a.pos().0].iter_mut().filter(|w| w.cref == 0).for_each(|w| w.cref = 1)));
+        // c on the trail but false.
+        assert!(refused(&|s| s.assigns[c.0] = Some(false)));
+        // c implied by a ∨ b.
+        assert!(refused(&|s| s.reasons[c.0] = Some(0)));
+        // c implied by a clause that does not hold it first.
+        assert!(refused(&|s| {
+            s.clauses.push(ClauseData { lits: vec![a.pos(), c.pos()], learnt: false, activity: 0.0, removed: false });
+            s.reasons[c.0] = Some(s.clauses.len() - 1);
+        }));
+
+        // calls in progress whose counters would overflow on resuming.
+        let call = |start: u64, restarts: usize| Progress { assumptions: Vec::new(), max_conflicts: Some(10), start, restarts };
+        assert!(reload(&solver, &|s| s.progress = Some(call(0, 3))).is_ok());
+        assert!(refused(&|s| s.progress = Some(call(s.stats.conflicts + 1, 0))));
+        assert!(refused(&|s| s.progress = Some(call(0, usize::MAX))));
+
+        // substitutions that never reach a representative: a cycle, a variable standing for
+        // its own negation, and a chain through a substituted variable.
+        assert!(reload(&solver, &|s| s.repr[a.0] = b.neg()).is_ok());
+        assert!(refused(&|s| {
+            s.repr[a.0] = b.pos();
+            s.repr[b.0] = a.pos();
+        }));
+        assert!(refused(&|s| s.repr[a.0] = a.neg()));
+        assert!(refused(&|s| {
+            s.repr[a.0] = b.pos();
+            s.repr[b.0] = c.neg();
+        }));
+    }
+}
@@ -0,0 +1,132 @@
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+
+/* The binary format of solver checkpoints, see `Solver::save` and `Solver::load`.
+ *
+ * A checkpoint starts with the magic bytes `TPCK` and a format version, and then lists the
+ * solver's fields in a fixed order. Integers are little endian u64, floats their IEEE bits,
+ * and sequences are prefixed with their length. A reader refuses versions it does not know
+ * rather than guessing.
+ * */
+pub const MAGIC: &[u8; 4] = b"TPCK";
+pub const VERSION: u32 = 1;
+
+
+pub(crate) struct Encoder<W: Write>(pub W);
+
+
+impl<W: Write> Encoder<W> {
+    pub fn header(&mut self) -> io::Result<()> {
+        self.0.write_all(MAGIC)?;
+        self.0.write_all(&VERSION.to_le_bytes())
+    }
+
+    pub fn u64(&mut self, value: u64) -> io::Result<()> {
+        self.0.write_all(&value.to_le_bytes())
+    }
+
+    pub fn usize(&mut self, value: usize) -> io::Result<()> {
+        self.u64(value as u64)
+    }
+
+    pub fn f64(&mut self, value: f64) -> io::Result<()> {
+        self.u64(value.to_bits())
+    }
+
+    pub fn bool(&mut self, value: bool) -> io::Result<()> {
+        self.0.write_all(&[value as u8])
+    }
+
+    pub fn str(&mut self, value: &str) -> io::Result<()> {
+        self.usize(value.len())?;
+        self.0.write_all(value.as_bytes())
+    }
+
+    pub fn option<T>(&mut self, value: Option<T>, mut some: impl FnMut(&mut Self, T) -> io::Result<()>) -> io::Result<()> {
+        self.bool(value.is_some())?;
+        match value {
+            Some(value) => some(self, value),
+            None => Ok(()),
+        }
+    }
+
+    pub fn seq<T>(&mut self, items: &[T], mut item: impl FnMut(&mut Self, &T) -> io::Result<()>) -> io::Result<()> {
+        self.usize(items.len())?;
+        items.iter().try_for_each(|i| item(self, i))
+    }
+}
+
+
+pub(crate) struct Decoder<R: Read>(pub R);
+
+
+impl<R: Read> Decoder<R> {
+    pub fn header(&mut self) -> io::Result<()> {
+        let mut magic = [0; 4];
+        self.0.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a solver checkpoint".to_string()));
+        }
+        let mut version = [0; 4];
+        self.0.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            return Err(invalid(format!("checkpoint version {} is not supported, expected {}", version, VERSION)));
+        }
+        Ok(())
+    }
+
+    pub fn u64(&mut self) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        self.0.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    pub fn usize(&mut self) -> io::Result<usize> {
+        usize::try_from(self.u64()?).map_err(|_| invalid("integer out of range".to_string()))
+    }
+
+    pub fn f64(&mut self) -> io::Result<f64> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+
+    pub fn bool(&mut self) -> io::Result<bool> {
+        let mut byte = [0];
+        self.0.read_exact(&mut byte)?;
+        match byte[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(invalid(format!("invalid boolean {}", b))),
+        }
+    }
+
+    pub fn str(&mut self) -> io::Result<String> {
+        let len = self.usize()?;
+        let mut bytes = Vec::new();
+        (&mut self.0).take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of checkpoint"));
+        }
+        String::from_utf8(bytes).map_err(|_| invalid("invalid utf-8 in name".to_string()))
+    }
+
+    pub fn option<T>(&mut self, some: impl FnOnce(&mut Self) -> io::Result<T>) -> io::Result<Option<T>> {
+        if self.bool()? { some(self).map(Some) } else { Ok(None) }
+    }
+
+    pub fn seq<T>(&mut self, mut item: impl FnMut(&mut Self) -> io::Result<T>) -> io::Result<Vec<T>> {
+        let len = self.usize()?;
+        // the length is not trusted for the allocation, a corrupt one fails on reading.
+        let mut items = Vec::with_capacity(len.min(1 << 16));
+        for _ in 0..len {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+}
+
+
+pub(crate) fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -1,6 +1,7 @@
 use crate::fol::ast::*;
 use crate::sat::dimacs;
-use std::{collections::HashSet, iter::FromIterator};
+use crate::sat::fragment;
+use std::{collections::{HashMap, HashSet}, iter::FromIterator};
 use std:: ops::{Deref, DerefMut};
 use std::fmt::Display;
 use std::io;
@@ -47,6 +48,10 @@ pub struct Clauses(pub Vec<Clause>);
 pub struct Clause(pub HashSet<Literal>);
 
 
+/* A total or partial assignment from variable names to truth values */
+pub type Model = HashMap<String, bool>;
+
+
 #[derive(Debug, Clone)]
 pub struct SATSolver(pub fn(Clauses) -> bool);
 
@@ -81,14 +86,21 @@ impl DerefMut for Clause {
 }
 
 
+impl Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Literal::Pos(pos) => write!(f, "{}", pos),
+            Literal::Neg(neg) => write!(f, "-{}", neg),
+        }
+    }
+}
+
+
 impl Display for Clause {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{{ ")?;
         for lit in self.0.iter() {
-            match lit {
-                Literal::Pos(pos) => write!(f, "{} ", pos)?,
-                Literal::Neg(neg) => write!(f, "-{} ", neg)?,
-            }
+            write!(f, "{} ", lit)?;
         }
         write!(f, "}}")
     }
@@ -120,11 +132,33 @@ impl FromIterator<Clause> for Clauses {
 }
 
 
+impl Default for Clause {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl Literal {
+    pub fn is_satisfied_by(&self, model: &Model) -> bool {
+        model.get(self.var_name()).is_some_and(|&b| b != self.is_negated())
+    }
+}
+
+
 impl Clause {
     pub fn new() -> Self {
         Clause(HashSet::new())
     }
 
+    pub fn is_satisfied_by(&self, model: &Model) -> bool {
+        self.iter().any(|lit| lit.is_satisfied_by(model))
+    }
+
+    pub fn is_tautology(&self) -> bool {
+        self.iter().any(|lit| self.contains(&lit.negate()))
+    }
+
     pub fn remove_trivals(&mut self) {
         let symbols = self.iter().map(|lit| lit.var_name().to_string()).collect::<Vec<_>>();
         for symbol in symbols {
@@ -139,6 +173,13 @@ impl Clause {
 }
 
 
+impl Default for Clauses {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
 impl Clauses {
     pub fn from_formula(formla: Formula<Cnf>) -> Self {
         let mut result = Clauses::new();
@@ -153,8 +194,8 @@ impl Clauses {
 
         fn on_lit(lit: &Literal) -> Formula<Cnf> {
             match lit {
-                Literal::Pos(s) => Formula::pred(&s, vec![]),
-                Literal::Neg(s) => Formula::not(Formula::pred(&s, vec![])),
+                Literal::Pos(s) => Formula::pred(s, vec![]),
+                Literal::Neg(s) => Formula::not(Formula::pred(s, vec![])),
             }
         }
         self.iter()
@@ -174,13 +215,22 @@ impl Clauses {
         Clauses(Vec::new())
     }
 
+    pub fn is_satisfied_by(&self, model: &Model) -> bool {
+        self.iter().all(|clause| clause.is_satisfied_by(model))
+    }
+
+    /* 2-CNF and (renamable) Horn clauses are decided in linear time, `sat` is only called
+     * on the rest. */
     pub fn is_satisfiable(self,  sat: SATSolver) -> bool {
-        sat.0(self)
+        match fragment::solve(&self) {
+            Some(model) => model.is_some(),
+            None => sat.0(self),
+        }
     }
 
     pub fn is_valid(self, sat: SATSolver) -> bool {
         let neg = Formula::not(self.to_formula()).cast::<Raw>().to_nnf().to_pnf().skolemize().ground().to_cnf();
-        !sat.0(Clauses::from_formula(neg))
+        !Clauses::from_formula(neg).is_satisfiable(sat)
     }
 }
 
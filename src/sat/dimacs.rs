@@ -27,13 +27,27 @@ pub fn parse<R: BufRead>(reader: R) -> io::Result<Clauses> {
             if let "0" = *field {
                 break;
             }
-            if let Some('-') = field.chars().nth(0) {
-                set.insert(Literal::neg(field[1..].to_string()));
-            } else {
-                set.insert(Literal::pos(field.to_string()));
-            }
+            set.insert(literal(field));
         }
         clauses.push(set);
     }
     Ok(clauses)
 }
+
+
+/* The variable and clause counts of a `p cnf <vars> <clauses>` header line. */
+pub(crate) fn header(line: &str) -> Option<(usize, usize)> {
+    match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["p", "cnf", vars, clauses] => Some((vars.parse().ok()?, clauses.parse().ok()?)),
+        _ => None,
+    }
+}
+
+
+/* The literal of a clause field, negative when it starts with `-`. */
+pub(crate) fn literal(field: &str) -> Literal {
+    match field.strip_prefix('-') {
+        Some(var) => Literal::neg(var.to_string()),
+        None => Literal::pos(field.to_string()),
+    }
+}
@@ -0,0 +1,445 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use crate::sat::clauses::*;
+
+
+/* Deterministic decomposable negation normal form.
+ *
+ * The circuit is built from literals with and/or nodes, where the children of an and share
+ * no variable (decomposable) and the children of an or have no model in common
+ * (deterministic). Both properties together make model counting, conditioning, optimisation
+ * and enumeration linear in the size of the circuit, which pays off when many queries are
+ * asked of one compiled CNF.
+ *
+ * Nodes are stored children first, so the circuit can be evaluated in one pass from the
+ * front. Every node is a variable index into `Dnnf::names`.
+ * */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Node {
+    True,
+    False,
+    /* Variable index and polarity, `true` for the positive literal. */
+    Lit(usize, bool),
+    And(Vec<usize>),
+    /* An or deciding on a variable: each child fixes it differently. */
+    Or(usize, Vec<usize>),
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Dnnf {
+    nodes: Vec<Node>,
+    root: usize,
+    names: Vec<String>,
+    // the variables models range over, conditioning takes variables out.
+    scope: Vec<usize>,
+}
+
+
+/* Hash consed node store, simplifying constants away as nodes are made. */
+struct Builder {
+    nodes: Vec<Node>,
+    unique: HashMap<Node, usize>,
+}
+
+
+const FALSE: usize = 0;
+const TRUE: usize = 1;
+
+
+impl Builder {
+    fn new() -> Self {
+        let mut builder = Builder { nodes: Vec::new(), unique: HashMap::new() };
+        builder.push(Node::False);
+        builder.push(Node::True);
+        builder
+    }
+
+    fn push(&mut self, node: Node) -> usize {
+        if let Some(&id) = self.unique.get(&node) {
+            return id;
+        }
+        self.nodes.push(node.clone());
+        self.unique.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn lit(&mut self, var: usize, positive: bool) -> usize {
+        self.push(Node::Lit(var, positive))
+    }
+
+    fn and(&mut self, children: Vec<usize>) -> usize {
+        if children.contains(&FALSE) {
+            return FALSE;
+        }
+        let mut children = children.into_iter().filter(|&c| c != TRUE).collect::<Vec<_>>();
+        children.sort();
+        children.dedup();
+        match children.len() {
+            0 => TRUE,
+            1 => children[0],
+            _ => self.push(Node::And(children)),
+        }
+    }
+
+    fn or(&mut self, var: usize, children: Vec<usize>) -> usize {
+        let mut children = children.into_iter().filter(|&c| c != FALSE).collect::<Vec<_>>();
+        children.sort();
+        match children.len() {
+            0 => FALSE,
+            1 => children[0],
+            _ => self.push(Node::Or(var, children)),
+        }
+    }
+}
+
+
+// a clause as signed 1-based variable numbers, like DIMACS.
+type Lits = Vec<i64>;
+
+
+/* Top down compiler in the style of c2d and Dsharp: unit propagation, then every connected
+ * component of the remaining clauses is compiled on its own and cached, and a component is
+ * split by deciding its most frequent variable. */
+struct Compiler {
+    builder: Builder,
+    cache: HashMap<Vec<Lits>, usize>,
+}
+
+
+impl Compiler {
+    fn compile(&mut self, clauses: Vec<Lits>) -> usize {
+        let Some((clauses, units)) = propagate(clauses) else {
+            return FALSE;
+        };
+        let mut children = units.into_iter().map(|l| self.builder.lit(l.unsigned_abs() as usize - 1, l > 0)).collect::<Vec<_>>();
+        for component in components(clauses) {
+            let node = match self.cache.get(&component) {
+                Some(&node) => node,
+                None => {
+                    let var = branch_var(&component);
+                    let branches = [var, -var]
+                        .iter()
+                        .map(|&l| {
+                            let sub = self.compile(assign(&component, l));
+                            let lit = self.builder.lit(var as usize - 1, l > 0);
+                            self.builder.and(vec![lit, sub])
+                        })
+                        .collect();
+                    let node = self.builder.or(var as usize - 1, branches);
+                    self.cache.insert(component, node);
+                    node
+                },
+            };
+            if node == FALSE {
+                return FALSE;
+            }
+            children.push(node);
+        }
+        self.builder.and(children)
+    }
+}
+
+
+/* Clauses with `lit` made true. */
+fn assign(clauses: &[Lits], lit: i64) -> Vec<Lits> {
+    clauses
+        .iter()
+        .filter(|c| !c.contains(&lit))
+        .map(|c| c.iter().cloned().filter(|&l| l != -lit).collect())
+        .collect()
+}
+
+
+/* Unit propagation to a fixpoint, `None` on conflict. */
+fn propagate(mut clauses: Vec<Lits>) -> Option<(Vec<Lits>, Vec<i64>)> {
+    let mut units = Vec::new();
+    loop {
+        if clauses.iter().any(|c| c.is_empty()) {
+            return None;
+        }
+        match clauses.iter().find(|c| c.len() == 1) {
+            Some(unit) => {
+                let lit = unit[0];
+                units.push(lit);
+                clauses = assign(&clauses, lit);
+            },
+            None => return Some((clauses, units)),
+        }
+    }
+}
+
+
+/* Split into groups of clauses connected by shared variables, each in canonical order so
+ * equal components hit the same cache entry. */
+fn components(clauses: Vec<Lits>) -> Vec<Vec<Lits>> {
+    fn find(parent: &mut HashMap<u64, u64>, v: u64) -> u64 {
+        let p = *parent.entry(v).or_insert(v);
+        if p == v {
+            return v;
+        }
+        let root = find(parent, p);
+        parent.insert(v, root);
+        root
+    }
+    let mut parent = HashMap::new();
+    for clause in clauses.iter() {
+        let first = find(&mut parent, clause[0].unsigned_abs());
+        for lit in clause[1..].iter() {
+            let other = find(&mut parent, lit.unsigned_abs());
+            parent.insert(other, first);
+        }
+    }
+    let mut groups: HashMap<u64, Vec<Lits>> = HashMap::new();
+    for mut clause in clauses {
+        clause.sort_by_key(|l| (l.unsigned_abs(), *l));
+        clause.dedup();
+        let root = find(&mut parent, clause[0].unsigned_abs());
+        groups.entry(root).or_default().push(clause);
+    }
+    let mut groups = groups.into_values().collect::<Vec<_>>();
+    for group in groups.iter_mut() {
+        group.sort();
+        group.dedup();
+    }
+    groups.sort();
+    groups
+}
+
+
+fn branch_var(clauses: &[Lits]) -> i64 {
+    let mut occurrences: HashMap<i64, usize> = HashMap::new();
+    for lit in clauses.iter().flat_map(|c| c.iter()) {
+        *occurrences.entry(lit.abs()).or_insert(0) += 1;
+    }
+    occurrences.into_iter().max_by_key(|&(v, n)| (n, -v)).map(|(v, _)| v).unwrap()
+}
+
+
+impl Dnnf {
+    /* Compile clauses, the models being over the variables occurring in them. */
+    pub fn compile(clauses: &Clauses) -> Self {
+        let mut names = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name().to_string())).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        let index = names.iter().enumerate().map(|(i, n)| (n.as_str(), i as i64 + 1)).collect::<HashMap<_, _>>();
+        let lits = clauses
+            .iter()
+            .filter(|c| !c.is_tautology())
+            .map(|c| c.iter().map(|l| if l.is_negated() { -index[l.var_name()] } else { index[l.var_name()] }).collect())
+            .collect();
+        let mut compiler = Compiler { builder: Builder::new(), cache: HashMap::new() };
+        let root = compiler.compile(lits);
+        let scope = (0..names.len()).collect();
+        Dnnf { nodes: compiler.builder.nodes, root, names, scope }
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.nodes.iter().map(|n| match n { Node::And(c) | Node::Or(_, c) => c.len(), _ => 0 }).sum()
+    }
+
+    /* Names of the variables the models range over. */
+    pub fn vars(&self) -> Vec<&str> {
+        self.scope.iter().map(|&v| self.names[v].as_str()).collect()
+    }
+
+    pub fn is_satisfiable(&self) -> bool {
+        self.root != FALSE
+    }
+
+    // number of variables below every node, for counting over the ones an or branch skips.
+    fn var_sets(&self) -> Vec<Vec<usize>> {
+        let mut sets: Vec<Vec<usize>> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let set = match node {
+                Node::True | Node::False => Vec::new(),
+                Node::Lit(v, _) => vec![*v],
+                Node::And(children) | Node::Or(_, children) => {
+                    let mut set = children.iter().flat_map(|&c| sets[c].iter().cloned()).collect::<Vec<_>>();
+                    set.sort();
+                    set.dedup();
+                    set
+                },
+            };
+            sets.push(set);
+        }
+        sets
+    }
+
+    /* Number of models over `vars()`. Counts of 2^128 or more saturate at `u128::MAX`. */
+    pub fn count(&self) -> u128 {
+        // c * 2^n, saturating.
+        fn shift(c: u128, n: usize) -> u128 {
+            match c {
+                0 => 0,
+                _ if n > c.leading_zeros() as usize => u128::MAX,
+                _ => c << n,
+            }
+        }
+        let sets = self.var_sets();
+        let mut counts: Vec<u128> = Vec::with_capacity(self.nodes.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            let count = match node {
+                Node::False => 0,
+                Node::True | Node::Lit(..) => 1,
+                Node::And(children) => children.iter().fold(1u128, |p, &c| p.saturating_mul(counts[c])),
+                // a branch not mentioning some variable of the or leaves it free.
+                Node::Or(_, children) => children.iter().fold(0u128, |s, &c| s.saturating_add(shift(counts[c], sets[i].len() - sets[c].len()))),
+            };
+            counts.push(count);
+        }
+        shift(counts[self.root], self.scope.len() - sets[self.root].len())
+    }
+
+    /* The circuit of the models with all `literals` true, over the remaining variables.
+     * Literals over unknown variables are ignored. */
+    pub fn condition(&self, literals: &[Literal]) -> Dnnf {
+        let index = self.names.iter().enumerate().map(|(i, n)| (n.as_str(), i)).collect::<HashMap<_, _>>();
+        let fixed = literals
+            .iter()
+            .filter_map(|l| index.get(l.var_name()).map(|&v| (v, !l.is_negated())))
+            .collect::<HashMap<_, _>>();
+        let mut builder = Builder::new();
+        let mut map = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let id = match node {
+                Node::False => FALSE,
+                Node::True => TRUE,
+                Node::Lit(v, positive) => match fixed.get(v) {
+                    Some(value) => if value == positive { TRUE } else { FALSE },
+                    None => builder.lit(*v, *positive),
+                },
+                Node::And(children) => builder.and(children.iter().map(|&c| map[c]).collect()),
+                Node::Or(v, children) => builder.or(*v, children.iter().map(|&c| map[c]).collect()),
+            };
+            map.push(id);
+        }
+        let scope = self.scope.iter().cloned().filter(|v| !fixed.contains_key(v)).collect();
+        Dnnf { root: map[self.root], nodes: builder.nodes, names: self.names.clone(), scope }
+    }
+
+    /* A model with the fewest true variables, and that number. */
+    pub fn min_cardinality(&self) -> Option<(usize, Model)> {
+        if self.root == FALSE {
+            return None;
+        }
+        // cost of every node, with the child an or should take.
+        let mut costs: Vec<Option<usize>> = Vec::with_capacity(self.nodes.len());
+        let mut choice = vec![0; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            let cost = match node {
+                Node::False => None,
+                Node::True => Some(0),
+                Node::Lit(_, positive) => Some(*positive as usize),
+                Node::And(children) => children.iter().map(|&c| costs[c]).sum(),
+                Node::Or(_, children) => {
+                    let best = children.iter().filter(|&&c| costs[c].is_some()).min_by_key(|&&c| costs[c]);
+                    best.map(|&c| {
+                        choice[i] = c;
+                        costs[c].unwrap()
+                    })
+                },
+            };
+            costs.push(cost);
+        }
+
+        // variables the chosen branches do not mention stay false.
+        let mut model = self.scope.iter().map(|&v| (self.names[v].clone(), false)).collect::<Model>();
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            match &self.nodes[id] {
+                Node::Lit(v, positive) => {
+                    model.insert(self.names[*v].clone(), *positive);
+                },
+                Node::And(children) => stack.extend(children.iter().cloned()),
+                Node::Or(..) => stack.push(choice[id]),
+                Node::True | Node::False => {},
+            }
+        }
+        Some((costs[self.root].unwrap(), model))
+    }
+
+    /* All models over `vars()`. There are `count()` of them, so only for small circuits. */
+    pub fn models(&self) -> Vec<Model> {
+        // partial models of every node, as (variable, value) lists.
+        let mut partial: Vec<Vec<Vec<(usize, bool)>>> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let models = match node {
+                Node::False => Vec::new(),
+                Node::True => vec![Vec::new()],
+                Node::Lit(v, positive) => vec![vec![(*v, *positive)]],
+                Node::And(children) => children.iter().fold(vec![Vec::new()], |acc, &c| {
+                    acc.iter()
+                        .flat_map(|left| partial[c].iter().map(move |right| [left.clone(), right.clone()].concat()))
+                        .collect()
+                }),
+                Node::Or(_, children) => children.iter().flat_map(|&c| partial[c].iter().cloned()).collect(),
+            };
+            partial.push(models);
+        }
+
+        // determinism keeps the partial models apart, so completing them never repeats one.
+        let mut models = Vec::new();
+        for assignment in partial[self.root].iter() {
+            let set = assignment.iter().map(|&(v, _)| v).collect::<HashSet<_>>();
+            let free = self.scope.iter().cloned().filter(|v| !set.contains(v)).collect::<Vec<_>>();
+            for bits in 0..1u64 << free.len() {
+                let mut model = assignment.iter().map(|&(v, b)| (self.names[v].clone(), b)).collect::<Model>();
+                for (i, &v) in free.iter().enumerate() {
+                    model.insert(self.names[v].clone(), bits >> i & 1 == 1);
+                }
+                models.push(model);
+            }
+        }
+        models
+    }
+
+    /* The c2d `.nnf` format: a header `nnf <nodes> <edges> <vars>`, then one line per node,
+     * children first. `L <lit>` is a literal over variables numbered from 1 in `names`
+     * order, `A <k> <children>` an and, `O <var> <k> <children>` an or deciding on `var`.
+     * True and false are `A 0` and `O 0 0`.
+     * */
+    pub fn write_nnf<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        // only the nodes reachable from the root, renumbered in order.
+        let mut reachable = vec![false; self.nodes.len()];
+        reachable[self.root] = true;
+        for id in (0..self.nodes.len()).rev() {
+            if let (true, Node::And(children) | Node::Or(_, children)) = (reachable[id], &self.nodes[id]) {
+                for &c in children.iter() {
+                    reachable[c] = true;
+                }
+            }
+        }
+        let ids = (0..self.nodes.len()).filter(|&id| reachable[id]).collect::<Vec<_>>();
+        let renumber = ids.iter().enumerate().map(|(new, &old)| (old, new)).collect::<HashMap<_, _>>();
+        let children = |c: &[usize]| c.iter().map(|c| renumber[c].to_string()).collect::<Vec<_>>().join(" ");
+        let edges = ids.iter().map(|&id| match &self.nodes[id] { Node::And(c) | Node::Or(_, c) => c.len(), _ => 0 }).sum::<usize>();
+
+        writeln!(writer, "nnf {} {} {}", ids.len(), edges, self.names.len())?;
+        for &id in ids.iter() {
+            match &self.nodes[id] {
+                Node::True => writeln!(writer, "A 0")?,
+                Node::False => writeln!(writer, "O 0 0")?,
+                Node::Lit(v, positive) => writeln!(writer, "L {}", if *positive { *v as i64 + 1 } else { -(*v as i64) - 1 })?,
+                Node::And(c) => writeln!(writer, "A {} {}", c.len(), children(c))?,
+                Node::Or(v, c) => writeln!(writer, "O {} {} {}", v + 1, c.len(), children(c))?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_nnf(&self) -> String {
+        let mut buffer = Vec::new();
+        self.write_nnf(&mut buffer).expect("writing to memory");
+        String::from_utf8(buffer).unwrap()
+    }
+}
@@ -1,106 +1,384 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::time::Instant;
 
 use crate::sat::clauses::*;
+use crate::sat::stats::*;
 
 
-/* The original davis putnam procedure. This only terminates on unsat cnf.
+/* The original davis putnam procedure.
  * */
 pub fn satisfiable_dp(clauses: Clauses) -> bool {
-    let mut clauses = clauses;
+    satisfiable_dp_with(clauses, &mut Stats::new(), &mut ())
+}
+
+
+/* `satisfiable_dp` that counts its work into `stats` and reports every rule it applies
+ * to `sink`. Pass `&mut ()` as the sink if only the counters are needed.
+ * */
+pub fn satisfiable_dp_with(clauses: Clauses, stats: &mut Stats, sink: &mut dyn EventSink) -> bool {
+    matches!(certify_dp_with(clauses, stats, sink), Certificate::Sat { .. })
+}
+
+
+/* Davis putnam that justifies its answer: a model of the input clauses, rebuilt from the
+ * recorded steps, or a resolution refutation.
+ * */
+pub fn certify_dp(clauses: Clauses) -> Certificate {
+    certify_dp_with(clauses, &mut Stats::new(), &mut ())
+}
+
+
+pub fn certify_dp_with(clauses: Clauses, stats: &mut Stats, sink: &mut dyn EventSink) -> Certificate {
+    let vars = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name().to_string())).collect::<HashSet<_>>();
+    let mut dp = Dp::new(clauses);
     loop {
-        if clauses.0.is_empty() {
-            return true;
+        if dp.clauses.is_empty() {
+            let model = reconstruct(&dp.steps, vars);
+            return Certificate::Sat { model, steps: dp.steps };
         }
-        if clauses.0.iter().any(|clause| clause.is_empty()) {
-            return false;
+        if let Some(&(_, id)) = dp.clauses.iter().find(|(clause, _)| clause.is_empty()) {
+            stats.conflicts += 1;
+            sink.event(&Event::Conflict { level: 0 });
+            return Certificate::Unsat(Refutation::trim(&dp.proof, id));
         }
 
-        match unit_propagation_rule(clauses) {
-            Ok(s) => {
-                clauses = s; continue;
-            },
-            Err(s) => {
-                clauses = s;
-            }
-        };
+        let start = Instant::now();
+        let unit = dp.propagate_unit();
+        stats.record(Phase::UnitPropagation, start.elapsed());
+        if let Some(literal) = unit {
+            stats.propagations += 1;
+            sink.event(&Event::Propagation { literal, level: 0 });
+            continue;
+        }
 
-        match affirmative_negative_rule(clauses) {
-            Ok(s) => {
-                clauses = s; continue;
-            },
-            Err(s) => {
-                clauses = s;
+        let start = Instant::now();
+        let pure = dp.remove_pure_literals();
+        stats.record(Phase::PureLiteral, start.elapsed());
+        if !pure.is_empty() {
+            stats.pure_literals += pure.len() as u64;
+            for literal in pure {
+                sink.event(&Event::PureLiteral { literal });
             }
+            continue;
         }
 
-        match resolution_rule(clauses) {
-            Ok(s) => {
-                clauses = s; continue;
+        let start = Instant::now();
+        let resolved = dp.resolve_symbol();
+        stats.record(Phase::Resolution, start.elapsed());
+        if let Some((var, resolvents)) = resolved {
+            stats.eliminated_vars += 1;
+            stats.resolvents += resolvents as u64;
+            sink.event(&Event::Eliminated { var, resolvents });
+        }
+    }
+}
+
+
+/* One simplification applied by the procedure, in the order they happened. */
+#[derive(Debug, Clone)]
+pub enum Step {
+    /* A unit clause made the literal true. */
+    Unit(Literal),
+    /* The literal was pure, the clauses containing it were dropped. */
+    Pure(Literal),
+    /* The variable was resolved away, these clauses containing it were replaced by their
+     * resolvents. */
+    Eliminated(String, Vec<Clause>),
+}
+
+
+#[derive(Debug, Clone)]
+pub enum Certificate {
+    Sat { model: Model, steps: Vec<Step> },
+    Unsat(Refutation),
+}
+
+
+/* Extend the empty model of the final, empty CNF to a model of the input by undoing the
+ * steps backwards. A unit or pure literal is made true. An eliminated variable is set to
+ * false unless that leaves one of its removed clauses false: the resolvents hold, so the
+ * clauses on the other side are then satisfied anyway. Variables that dropped out of every
+ * clause on the way may take any value, they are set to false up front.
+ * */
+pub fn reconstruct(steps: &[Step], vars: impl IntoIterator<Item = String>) -> Model {
+    let mut model = vars.into_iter().map(|v| (v, false)).collect::<Model>();
+    for step in steps.iter().rev() {
+        match step {
+            Step::Unit(literal) | Step::Pure(literal) => {
+                model.insert(literal.var_name().to_string(), !literal.is_negated());
             },
-            Err(s) => {
-                clauses = s;
+            Step::Eliminated(var, removed) => {
+                model.insert(var.clone(), false);
+                if !removed.iter().all(|c| c.is_satisfied_by(&model)) {
+                    model.insert(var.clone(), true);
+                }
+            },
+        }
+    }
+    model
+}
+
+
+/* A clause of a resolution proof, either given or resolved from two earlier ones on
+ * `pivot`. Parents are indices into the proof. */
+#[derive(Debug, Clone)]
+pub enum Derivation {
+    Input(Clause),
+    Resolvent { clause: Clause, pivot: String, parents: (usize, usize) },
+}
+
+
+impl Derivation {
+    pub fn clause(&self) -> &Clause {
+        match self {
+            Derivation::Input(clause) | Derivation::Resolvent { clause, .. } => clause,
+        }
+    }
+}
+
+
+/* A resolution proof ending in the empty clause. */
+#[derive(Debug, Clone)]
+pub struct Refutation(pub Vec<Derivation>);
+
+
+impl Refutation {
+    /* The derivations `root` depends on, renumbered in order. */
+    fn trim(proof: &[Derivation], root: usize) -> Self {
+        let mut used = HashSet::new();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            if used.insert(id) {
+                if let Derivation::Resolvent { parents: (a, b), .. } = proof[id] {
+                    stack.push(a);
+                    stack.push(b);
+                }
             }
         }
+        let mut used = used.into_iter().collect::<Vec<_>>();
+        used.sort();
+        let renumber = used.iter().enumerate().map(|(new, &old)| (old, new)).collect::<HashMap<_, _>>();
+        Refutation(used
+            .into_iter()
+            .map(|id| match &proof[id] {
+                Derivation::Input(clause) => Derivation::Input(clause.clone()),
+                Derivation::Resolvent { clause, pivot, parents: (a, b) } => Derivation::Resolvent {
+                    clause: clause.clone(),
+                    pivot: pivot.clone(),
+                    parents: (renumber[a], renumber[b]),
+                },
+            })
+            .collect())
+    }
+
+    /* Whether this is a refutation of `clauses`: every input is one of them, every
+     * resolvent is the resolvent of earlier clauses on its pivot, and the last clause is
+     * empty. */
+    pub fn check(&self, clauses: &Clauses) -> bool {
+        let resolves = |i: usize, clause: &Clause, pivot: &String, (a, b): (usize, usize)| {
+            if a >= i || b >= i {
+                return false;
+            }
+            let (a, b) = (self.0[a].clause(), self.0[b].clause());
+            let p = Literal::pos(pivot.clone());
+            let n = p.negate();
+            // p is taken from the parent that contains it, a tautological parent holding
+            // both literals may be on either side.
+            [(a, b), (b, a)].iter().any(|&(a, b)| {
+                a.contains(&p) && b.contains(&n) && resolve(a, b, &p).0 == clause.0
+            })
+        };
+        let valid = self.0.iter().enumerate().all(|(i, derivation)| match derivation {
+            Derivation::Input(clause) => clauses.iter().any(|c| c.0 == clause.0),
+            Derivation::Resolvent { clause, pivot, parents } => resolves(i, clause, pivot, *parents),
+        });
+        valid && self.0.last().is_some_and(|d| d.clause().is_empty())
     }
 }
 
 
-/* Remove unit clause. If we have a clause with a single literal P,
- * - Remove ¬P from other clauses.
- * - Remove clauses contains P including itself.
- * */
-pub fn unit_propagation_rule(mut clauses: Clauses) -> Result<Clauses, Clauses> {
-    let mut value = None;
-    for clause in clauses.0.iter() {
-        if clause.len() == 1 {
-            value = clause.iter().next().cloned();
-            break
+impl Display for Refutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, derivation) in self.0.iter().enumerate() {
+            match derivation {
+                Derivation::Input(clause) => writeln!(f, "{}: {} input", i, clause)?,
+                Derivation::Resolvent { clause, pivot, parents: (a, b) } => {
+                    writeln!(f, "{}: {} resolve {} on {}, {}", i, clause, pivot, a, b)?
+                },
+            }
         }
+        Ok(())
+    }
+}
+
+
+/* The resolvent `(a ∖ {p}) ∪ (b ∖ {¬p})` of `a` containing `p` and `b` containing `¬p`. */
+fn resolve(a: &Clause, b: &Clause, p: &Literal) -> Clause {
+    let n = p.negate();
+    a.iter().filter(|&l| l != p).chain(b.iter().filter(|&l| *l != n)).cloned().collect()
+}
+
+
+/* The running CNF, every clause with the index of its derivation in `proof`. */
+pub(crate) struct Dp {
+    clauses: Vec<(Clause, usize)>,
+    proof: Vec<Derivation>,
+    steps: Vec<Step>,
+}
+
+
+impl Dp {
+    // tautologies are dropped up front, so no clause holds a variable in both polarities
+    // and the rules below can split the clauses on the sign of a literal.
+    pub(crate) fn new(clauses: Clauses) -> Self {
+        let proof = clauses.iter().cloned().map(Derivation::Input).collect::<Vec<_>>();
+        let clauses = clauses.0.into_iter().enumerate().filter(|(_, c)| !c.is_tautology()).map(|(i, c)| (c, i));
+        Dp { clauses: clauses.collect(), proof, steps: Vec::new() }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.clauses.len()
+    }
+
+    pub(crate) fn into_clauses(self) -> Clauses {
+        self.clauses.into_iter().map(|(c, _)| c).collect()
+    }
+
+    fn derive(&mut self, clause: Clause, pivot: &str, parents: (usize, usize)) -> usize {
+        self.proof.push(Derivation::Resolvent { clause, pivot: pivot.to_string(), parents });
+        self.proof.len() - 1
     }
 
-    if let Some(unit) = value {
+    // unit propagation, returns the propagated unit.
+    fn propagate_unit(&mut self) -> Option<Literal> {
+        let &(ref clause, unit_id) = self.clauses.iter().find(|(c, _)| c.len() == 1)?;
+        let unit = clause.iter().next().cloned().unwrap();
         let neg = unit.negate();
-        let remove = |c: &mut Clause| if c.contains(&neg) { c.remove(&neg); } else {};
-        clauses.0.iter_mut().for_each(remove);
-        clauses.0.retain(|c| !c.contains(&unit));
-    } else {
-        return Err(clauses)
+        let clauses = std::mem::take(&mut self.clauses);
+        for (mut clause, id) in clauses {
+            if clause.contains(&unit) {
+                continue;
+            }
+            if clause.remove(&neg) {
+                // the shortened clause is the resolvent with the unit.
+                let id = self.derive(clause.clone(), unit.var_name(), (unit_id, id));
+                self.clauses.push((clause, id));
+            } else {
+                self.clauses.push((clause, id));
+            }
+        }
+        self.steps.push(Step::Unit(unit.clone()));
+        Some(unit)
     }
-    Ok(clauses)
-}
 
+    // affirmative negative rule, returns the pure literals it removed.
+    fn remove_pure_literals(&mut self) -> Vec<Literal> {
+        const POS: u8 = 0b01; const NEG: u8 = 0b10;
+        let mut occurrences: HashMap<String, u8> = HashMap::new();
+        for (clause, _) in self.clauses.iter() {
+            for literal in clause.iter() {
+                let k = literal.var_name().to_string();
+                let mask = if literal.is_negated() { NEG } else { POS };
+                match occurrences.get_mut(&k) {
+                    Some (occur) => { *occur |= mask },
+                    None => { occurrences.insert(k.to_string(), mask); }
+                };
+            }
+        }
+        let to_remove = occurrences
+            .into_iter()
+            .filter(|(_, o)| { *o == POS || *o == NEG })
+            .map(|(k, o)| if o == POS { Literal::pos(k) } else { Literal::neg(k) })
+            .collect::<Vec<_>>();
+        self.clauses.retain(|(c, _)| !to_remove.iter().any(|pure| c.contains(pure)));
+        self.steps.extend(to_remove.iter().cloned().map(Step::Pure));
+        to_remove
+    }
 
-/* If a literal occurs only positively or negatively, we can remove all clauses contain them
- * while preserving satisfiability. */
-pub fn affirmative_negative_rule(mut clauses: Clauses) -> Result<Clauses, Clauses> {
-    const POS: u8 = 0b01; const NEG: u8 = 0b10;
-    let mut occurrences: HashMap<String, u8> = HashMap::new();
-    for clause in clauses.iter() {
-        for literal in clause.iter() {
-            let k = literal.var_name().to_string();
-            let mask = if literal.is_negated() { NEG } else { POS };
-            match occurrences.get_mut(&k) {
-                Some (occur) => { *occur |= mask },
-                None => { occurrences.insert(k.to_string(), mask); }
-            };
+    // positive and negative occurrences of every variable.
+    pub(crate) fn occurrences(&self) -> HashMap<&str, (usize, usize)> {
+        let mut occurrences: HashMap<&str, (usize, usize)> = HashMap::new();
+        for (clause, _) in self.clauses.iter() {
+            for literal in clause.iter() {
+                let entry = occurrences.entry(literal.var_name()).or_insert((0, 0));
+                if literal.is_negated() { entry.1 += 1 } else { entry.0 += 1 }
+            }
+        }
+        occurrences
+    }
+
+    // resolution rule on one symbol, returns it with the number of resolvents it produced.
+    fn resolve_symbol(&mut self) -> Option<(String, usize)> {
+        // ties are broken by name so the elimination order is deterministic.
+        let (symbol, _) = self.occurrences().into_iter().min_by_key(|&(k, (p, n))| (p * n, k))?;
+        let symbol = symbol.to_string();
+        let count = self.eliminate(&symbol);
+        Some((symbol, count))
+    }
+
+    // replace the clauses containing `symbol` by their resolvents on it, returns the number
+    // of resolvents kept, tautologies are dropped.
+    pub(crate) fn eliminate(&mut self, symbol: &str) -> usize {
+        let symbol = symbol.to_string();
+        let p = Literal::pos(symbol.clone());
+        let n = Literal::neg(symbol.clone());
+        let clauses = std::mem::take(&mut self.clauses);
+        let (pos, rest): (Vec<_>, Vec<_>) = clauses.into_iter().partition(|(c, _)| c.contains(&p));
+        let (neg, rest): (Vec<_>, Vec<_>) = rest.into_iter().partition(|(c, _)| c.contains(&n));
+
+        let mut resolvents = Vec::new();
+        for (pclause, pid) in pos.iter() { // cross over
+            for (nclause, nid) in neg.iter() {
+                let resolvent = resolve(pclause, nclause, &p);
+                if !resolvent.is_tautology() {
+                    let id = self.derive(resolvent.clone(), &symbol, (*pid, *nid));
+                    resolvents.push((resolvent, id));
+                }
+            }
         }
+
+        let count = resolvents.len();
+        let removed = pos.into_iter().chain(neg).map(|(c, _)| c).collect();
+        self.steps.push(Step::Eliminated(symbol, removed));
+        self.clauses = rest.into_iter().filter(|(c, _)| !c.is_tautology()).collect();
+        self.clauses.extend(resolvents);
+        self.remove_subsumed_clauses();
+        count
     }
-    let to_remove = occurrences
-        .into_iter()
-        .filter(|(_, o)| { *o == POS || *o == NEG })
-        .map(|(k, _)| k)
-        .collect::<Vec<_>>();
-    if to_remove.len() == 0 { return Err(clauses) }
-    clauses.retain(|c| {
-        let mut keep = true;
-        for pure in to_remove.iter() {
-            if c.contains(&Literal::pos(pure.to_string())) || c.contains(&Literal::neg(pure.to_string())) {
-                keep = false;
+
+    // Remove clauses that contain another clause, including duplicates. They are implied by
+    // the smaller clause, and without this the resolvents of a few eliminations swamp the
+    // CNF.
+    pub(crate) fn remove_subsumed_clauses(&mut self) {
+        let mut clauses = std::mem::take(&mut self.clauses);
+        clauses.sort_by_key(|(c, _)| c.len());
+        for (clause, id) in clauses {
+            if !self.clauses.iter().any(|(k, _)| k.is_subset(&clause)) {
+                self.clauses.push((clause, id));
             }
         }
-        keep
-    });
-    Ok(clauses)
+    }
+}
+
+
+/* Remove unit clause. If we have a clause with a single literal P,
+ * - Remove ¬P from other clauses.
+ * - Remove clauses contains P including itself.
+ * */
+pub fn unit_propagation_rule(clauses: Clauses) -> Result<Clauses, Clauses> {
+    let mut dp = Dp::new(clauses);
+    let applied = dp.propagate_unit().is_some();
+    if applied { Ok(dp.into_clauses()) } else { Err(dp.into_clauses()) }
+}
+
+
+/* If a literal occurs only positively or negatively, we can remove all clauses contain them
+ * while preserving satisfiability. */
+pub fn affirmative_negative_rule(clauses: Clauses) -> Result<Clauses, Clauses> {
+    let mut dp = Dp::new(clauses);
+    let applied = !dp.remove_pure_literals().is_empty();
+    if applied { Ok(dp.into_clauses()) } else { Err(dp.into_clauses()) }
 }
 
 
@@ -121,73 +399,12 @@ pub fn affirmative_negative_rule(mut clauses: Clauses) -> Result<Clauses, Clause
  *
  * These resolvents are then being added back into the CNF for the next iteration.
  *
- * DP will simply try to iterate through all symbols in the cnf and try to resolve them in
- * turn. There are better way to pick literal in more optimized algorithms.
+ * Each application eliminates one symbol, the one producing the fewest resolvents. Symbols
+ * have to be eliminated one at a time: the resolvents on P may contain the next symbol, so
+ * resolving every symbol against the original clauses at once loses models.
  * */
-pub fn resolution_rule(mut clauses: Clauses) -> Result<Clauses, Clauses> {
-    let mut occurrences = HashMap::new();
-    for clause in clauses.iter() {
-        for literal in clause.iter() {
-            if let Some(v) = occurrences.get_mut(literal.var_name()) {
-                *v += 1;
-            } else {
-                occurrences.insert(literal.var_name().to_string(), 1);
-            }
-        }
-    }
-    let mut symbols: Vec<_> = occurrences.iter().collect();
-    symbols.sort_by_key(|&(_, v)| -v);
-
-    let mut resolvents = Clauses::new();
-    let mut need_to_remove = HashSet::new();
-
-
-    for symbol in symbols.into_iter().map(|(k,_)| k) {
-        let p = Literal::pos(symbol.clone());
-        let n = Literal::neg(symbol.clone());
-
-        let mut pos = Vec::new();
-        let mut neg = Vec::new();
-
-        clauses.iter_mut().enumerate().for_each(|(idx, clause)| {
-            if clause.contains(&p) {
-                pos.push(idx);
-            } else if clause.contains(&n) {
-                neg.push(idx)
-            }
-        });
-        for pidx in pos.iter_mut() { // cross over
-            for nidx in neg.iter() {
-                let pclause = &clauses[*pidx];
-                let nclause = &clauses[*nidx];
-                let mut resolvent = pclause.union(nclause).cloned().collect::<Clause>();
-                resolvent.remove_trivals();
-                resolvents.push(resolvent);
-            }
-        }
-
-        need_to_remove.extend(pos.into_iter().chain(neg).collect::<HashSet<_>>());
-    }
-
-    clauses = clauses.0
-        .into_iter()
-        .enumerate()
-        .filter(|(idx, _)| { !need_to_remove.contains(&idx)} )
-        .map(|(_, c)| c)
-        .collect();
-    clauses = remove_trivial_clauses(clauses);
-    clauses.append(&mut resolvents);
-    Ok(clauses)
-}
-
-
-// Remove trivial tautology from clauses. If removing the tautology yeilds an empty clause, we
-// remove the empty clause completely.
-// This function preserves empty clauses that are already there.
-fn remove_trivial_clauses(mut clauses: Clauses) -> Clauses {
-    let n_empty_clauses = clauses.iter().filter(|c| c.len() == 0).count();
-    clauses.iter_mut().for_each(|c| c.remove_trivals());
-    clauses.retain(|clause| !clause.is_empty());
-    clauses.append(&mut std::iter::repeat_n(Clause::new(), n_empty_clauses).collect::<Clauses>());
-    clauses
+pub fn resolution_rule(clauses: Clauses) -> Result<Clauses, Clauses> {
+    let mut dp = Dp::new(clauses);
+    let applied = dp.resolve_symbol().is_some();
+    if applied { Ok(dp.into_clauses()) } else { Err(dp.into_clauses()) }
 }
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use crate::sat::cdcl::Solver;
+use crate::sat::clauses::*;
+use crate::sat::fragment::{self, Fragment};
+use crate::sat::gen::Rng;
+
+
+/* Instance features for engine selection, after SATzilla (Xu, Hutter, Hoos and
+ * Leyton-Brown).
+ *
+ * Syntactic features are cheap statistics of the clauses: size, clause lengths, variable
+ * degrees, literal polarities and the share of Horn and short clauses. Probing features
+ * come from running two engines briefly, a CDCL search limited to `PROBE_CONFLICTS`
+ * conflicts and a few WalkSAT tries, and tell how the instance behaves under search.
+ *
+ * Extraction is deterministic: both probes number variables in name order and sort the
+ * literals and clauses they are given, and the local search uses a fixed seed, so the same
+ * clauses always give the same features.
+ * */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Features {
+    pub vars: usize,
+    pub clauses: usize,
+    /* Clauses per variable, 0 without variables. */
+    pub ratio: f64,
+    pub clause_length: Summary,
+    /* Occurrences of each variable. */
+    pub var_degree: Summary,
+    /* Share of positive literals in each clause. */
+    pub clause_balance: Summary,
+    /* Share of positive occurrences of each variable. */
+    pub var_balance: Summary,
+    /* Shares of unit, binary, ternary and Horn clauses. */
+    pub unit: f64,
+    pub binary: f64,
+    pub ternary: f64,
+    pub horn: f64,
+    pub fragment: Fragment,
+    pub search: SearchProbe,
+    pub local: LocalProbe,
+}
+
+
+/* Mean, standard deviation and range of a statistic, all 0 when there are no values. */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Summary {
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+
+/* How a short CDCL run went. */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SearchProbe {
+    /* The answer if the probe already decided the instance. */
+    pub result: Option<bool>,
+    pub conflicts: u64,
+    pub propagations_per_decision: f64,
+    pub mean_learned_size: f64,
+    /* Share of the variables fixed at decision level 0 afterwards. */
+    pub fixed: f64,
+}
+
+
+/* How WalkSAT did over `LOCAL_TRIES` tries of `LOCAL_FLIPS` flips. */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LocalProbe {
+    pub solved: bool,
+    /* Least share of falsified clauses reached, averaged over the tries. */
+    pub best: f64,
+    /* Flips until the best was first reached, as a share of the flips of a try. */
+    pub best_step: f64,
+}
+
+
+pub const PROBE_CONFLICTS: u64 = 100;
+pub const LOCAL_TRIES: usize = 4;
+pub const LOCAL_FLIPS: usize = 2_000;
+// probability of a random walk step in WalkSAT.
+const NOISE: f64 = 0.5;
+const SEED: u64 = 0x5a7_2111a;
+
+
+/* Names of the entries of `Features::vector`, in order. */
+pub const NAMES: &[&str] = &[
+    "vars", "clauses", "ratio",
+    "clause_length_mean", "clause_length_std", "clause_length_min", "clause_length_max",
+    "var_degree_mean", "var_degree_std", "var_degree_min", "var_degree_max",
+    "clause_balance_mean", "clause_balance_std", "clause_balance_min", "clause_balance_max",
+    "var_balance_mean", "var_balance_std", "var_balance_min", "var_balance_max",
+    "unit", "binary", "ternary", "horn",
+    "two_cnf", "is_horn", "renamable_horn",
+    "search_sat", "search_unsat", "search_conflicts", "search_propagations_per_decision",
+    "search_learned_size", "search_fixed",
+    "local_solved", "local_best", "local_best_step",
+];
+
+
+impl Summary {
+    pub fn of(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return Summary::default();
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+        Summary {
+            mean,
+            std: variance.sqrt(),
+            min: values.iter().cloned().fold(f64::INFINITY, f64::min),
+            max: values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+
+    /* Standard deviation relative to the mean, 0 if the mean is. */
+    pub fn variation(&self) -> f64 {
+        if self.mean == 0.0 { 0.0 } else { self.std / self.mean }
+    }
+}
+
+
+impl Features {
+    pub fn extract(clauses: &Clauses) -> Self {
+        let mut names = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name().to_string())).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        let index = names.iter().enumerate().map(|(i, n)| (n.as_str(), i)).collect::<HashMap<_, _>>();
+
+        let (mut positive, mut negative) = (vec![0usize; names.len()], vec![0usize; names.len()]);
+        for literal in clauses.iter().flat_map(|c| c.iter()) {
+            let v = index[literal.var_name()];
+            if literal.is_negated() { negative[v] += 1 } else { positive[v] += 1 }
+        }
+        let share = |count: usize| if clauses.is_empty() { 0.0 } else { count as f64 / clauses.len() as f64 };
+        let with_length = |len: usize| share(clauses.iter().filter(|c| c.len() == len).count());
+        let lengths = clauses.iter().map(|c| c.len() as f64).collect::<Vec<_>>();
+        let clause_balance = clauses
+            .iter()
+            .filter(|c| !c.is_empty())
+            .map(|c| c.iter().filter(|l| !l.is_negated()).count() as f64 / c.len() as f64)
+            .collect::<Vec<_>>();
+        let degrees = (0..names.len()).map(|v| (positive[v] + negative[v]) as f64).collect::<Vec<_>>();
+        let var_balance = (0..names.len()).map(|v| positive[v] as f64 / degrees[v]).collect::<Vec<_>>();
+
+        Features {
+            vars: names.len(),
+            clauses: clauses.len(),
+            ratio: if names.is_empty() { 0.0 } else { clauses.len() as f64 / names.len() as f64 },
+            clause_length: Summary::of(&lengths),
+            var_degree: Summary::of(&degrees),
+            clause_balance: Summary::of(&clause_balance),
+            var_balance: Summary::of(&var_balance),
+            unit: with_length(1),
+            binary: with_length(2),
+            ternary: with_length(3),
+            horn: share(clauses.iter().filter(|c| c.iter().filter(|l| !l.is_negated()).count() <= 1).count()),
+            fragment: fragment::classify(clauses),
+            search: search_probe(clauses, &names),
+            local: Walk::new(clauses, &index).probe(),
+        }
+    }
+
+    /* The features as numbers, named by `NAMES`, for learned models. */
+    pub fn vector(&self) -> Vec<f64> {
+        let flag = |b: bool| b as u8 as f64;
+        let mut vector = vec![self.vars as f64, self.clauses as f64, self.ratio];
+        for summary in [&self.clause_length, &self.var_degree, &self.clause_balance, &self.var_balance] {
+            vector.extend([summary.mean, summary.std, summary.min, summary.max]);
+        }
+        vector.extend([
+            self.unit,
+            self.binary,
+            self.ternary,
+            self.horn,
+            flag(self.fragment == Fragment::TwoCnf),
+            flag(self.fragment == Fragment::Horn),
+            flag(matches!(self.fragment, Fragment::RenamableHorn(_))),
+            flag(self.search.result == Some(true)),
+            flag(self.search.result == Some(false)),
+            self.search.conflicts as f64,
+            self.search.propagations_per_decision,
+            self.search.mean_learned_size,
+            self.search.fixed,
+            flag(self.local.solved),
+            self.local.best,
+            self.local.best_step,
+        ]);
+        vector
+    }
+}
+
+
+fn search_probe(clauses: &Clauses, names: &[String]) -> SearchProbe {
+    // a solver numbers variables as it meets them, in hashing order within a clause.
+    let mut solver = Solver::new();
+    for name in names {
+        solver.var(name);
+    }
+    let mut lits = clauses
+        .iter()
+        .map(|c| {
+            let mut lits = c.iter().map(|l| solver.lit(l)).collect::<Vec<_>>();
+            lits.sort_unstable();
+            lits
+        })
+        .collect::<Vec<_>>();
+    lits.sort_unstable();
+    for clause in lits.iter() {
+        solver.add_clause(clause);
+    }
+    let result = solver.solve_limited(&[], Some(PROBE_CONFLICTS));
+    let stats = &solver.stats;
+    let vars = solver.num_vars();
+    SearchProbe {
+        result,
+        conflicts: stats.conflicts,
+        propagations_per_decision: stats.propagations as f64 / stats.decisions.max(1) as f64,
+        mean_learned_size: stats.mean_learned_size(),
+        fixed: if vars == 0 { 0.0 } else { solver.fixed().len() as f64 / vars as f64 },
+    }
+}
+
+
+// WalkSAT state: the number of true literals of every clause and the falsified clauses.
+struct Walk {
+    // literals as (variable, positive), sorted so the walk does not depend on hashing.
+    clauses: Vec<Vec<(usize, bool)>>,
+    // clauses of the positive and the negative literal of every variable, at 2v and 2v + 1.
+    occurrences: Vec<Vec<usize>>,
+    values: Vec<bool>,
+    true_count: Vec<usize>,
+    falsified: Vec<usize>,
+    // position of every clause in `falsified`, if it is there.
+    position: Vec<Option<usize>>,
+}
+
+
+impl Walk {
+    fn new(clauses: &Clauses, index: &HashMap<&str, usize>) -> Self {
+        let clauses = clauses
+            .iter()
+            .map(|c| {
+                let mut lits = c.iter().map(|l| (index[l.var_name()], !l.is_negated())).collect::<Vec<_>>();
+                lits.sort_unstable();
+                lits
+            })
+            .collect::<Vec<_>>();
+        let mut occurrences = vec![Vec::new(); 2 * index.len()];
+        for (i, clause) in clauses.iter().enumerate() {
+            for &(v, positive) in clause.iter() {
+                occurrences[2 * v + !positive as usize].push(i);
+            }
+        }
+        let n = clauses.len();
+        Walk {
+            clauses,
+            occurrences,
+            values: vec![false; index.len()],
+            true_count: vec![0; n],
+            falsified: Vec::new(),
+            position: vec![None; n],
+        }
+    }
+
+    fn probe(mut self) -> LocalProbe {
+        let m = self.clauses.len();
+        if m == 0 {
+            return LocalProbe { solved: true, best: 0.0, best_step: 0.0 };
+        }
+        // an empty clause can never be satisfied, and has no variable to flip.
+        if self.clauses.iter().any(|c| c.is_empty()) {
+            return LocalProbe { solved: false, best: 1.0, best_step: 0.0 };
+        }
+        let mut rng = Rng::new(SEED);
+        let (mut best_total, mut step_total) = (0.0, 0.0);
+        for _ in 0..LOCAL_TRIES {
+            let (best, step) = self.try_once(&mut rng);
+            if best == 0 {
+                return LocalProbe { solved: true, best: 0.0, best_step: step as f64 / LOCAL_FLIPS as f64 };
+            }
+            best_total += best as f64 / m as f64;
+            step_total += step as f64 / LOCAL_FLIPS as f64;
+        }
+        let tries = LOCAL_TRIES as f64;
+        LocalProbe { solved: false, best: best_total / tries, best_step: step_total / tries }
+    }
+
+    // least number of falsified clauses reached from a random assignment, and when.
+    fn try_once(&mut self, rng: &mut Rng) -> (usize, usize) {
+        for value in self.values.iter_mut() {
+            *value = rng.bool();
+        }
+        self.falsified.clear();
+        for i in 0..self.clauses.len() {
+            let values = &self.values;
+            self.true_count[i] = self.clauses[i].iter().filter(|&&(v, positive)| values[v] == positive).count();
+            self.position[i] = None;
+            if self.true_count[i] == 0 {
+                self.position[i] = Some(self.falsified.len());
+                self.falsified.push(i);
+            }
+        }
+        let (mut best, mut best_step) = (self.falsified.len(), 0);
+        for step in 1..=LOCAL_FLIPS {
+            if self.falsified.is_empty() {
+                break;
+            }
+            let clause = self.falsified[rng.below(self.falsified.len())];
+            let breaks = self.clauses[clause].iter().map(|&(v, _)| (self.breaks(v), v)).collect::<Vec<_>>();
+            let &(least, _) = breaks.iter().min().unwrap();
+            let v = if least > 0 && rng.float() < NOISE {
+                breaks[rng.below(breaks.len())].1
+            } else {
+                let ties = breaks.iter().filter(|&&(b, _)| b == least).collect::<Vec<_>>();
+                ties[rng.below(ties.len())].1
+            };
+            self.flip(v);
+            if self.falsified.len() < best {
+                best = self.falsified.len();
+                best_step = step;
+            }
+        }
+        (best, best_step)
+    }
+
+    // clauses only `v` satisfies, which flipping it falsifies.
+    fn breaks(&self, v: usize) -> usize {
+        let true_lit = 2 * v + !self.values[v] as usize;
+        self.occurrences[true_lit].iter().filter(|&&c| self.true_count[c] == 1).count()
+    }
+
+    fn flip(&mut self, v: usize) {
+        let was_true = 2 * v + !self.values[v] as usize;
+        self.values[v] = !self.values[v];
+        for k in 0..self.occurrences[was_true].len() {
+            let c = self.occurrences[was_true][k];
+            self.true_count[c] -= 1;
+            if self.true_count[c] == 0 {
+                self.position[c] = Some(self.falsified.len());
+                self.falsified.push(c);
+            }
+        }
+        for k in 0..self.occurrences[was_true ^ 1].len() {
+            let c = self.occurrences[was_true ^ 1][k];
+            self.true_count[c] += 1;
+            if self.true_count[c] == 1 {
+                let at = self.position[c].take().unwrap();
+                self.falsified.swap_remove(at);
+                if let Some(&moved) = self.falsified.get(at) {
+                    self.position[moved] = Some(at);
+                }
+            }
+        }
+    }
+}
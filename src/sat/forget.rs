@@ -0,0 +1,161 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::sat::cdcl::*;
+use crate::sat::clauses::*;
+use crate::sat::dp::Dp;
+
+
+/* Forgetting, or existential quantification: `∃x. F` is the CNF over the variables of F
+ * but x that holds exactly when some value of x satisfies F. It is equivalent to the
+ * resolvents of F on x together with the clauses without x, which is what the resolution
+ * rule of DP computes.
+ *
+ * Resolving variables away one at a time may blow up the clauses, so it is only used while
+ * the clauses stay within `MAX_GROWTH` times the input. The variables left then are
+ * forgotten by enumerating the prime implicates over the kept variables with a SAT solver,
+ * whose number only depends on the result.
+ * */
+pub const MAX_GROWTH: usize = 4;
+
+
+/* `∃vars. clauses`, a CNF over the other variables of `clauses`. */
+pub fn forget(clauses: &Clauses, vars: &[String]) -> Clauses {
+    forget_bounded(clauses, vars, MAX_GROWTH * clauses.len().max(1))
+}
+
+
+/* `clauses` projected onto `keep`: every other variable is forgotten. */
+pub fn project(clauses: &Clauses, keep: &[String]) -> Clauses {
+    let keep = keep.iter().map(|v| v.as_str()).collect::<HashSet<_>>();
+    let mut vars = clauses
+        .iter()
+        .flat_map(|c| c.iter().map(|l| l.var_name()))
+        .filter(|v| !keep.contains(v))
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>();
+    vars.sort();
+    vars.dedup();
+    forget(clauses, &vars)
+}
+
+
+/* `forget` resolving while there are at most `max_clauses` clauses, 0 to go straight to
+ * prime implicates. */
+pub fn forget_bounded(clauses: &Clauses, vars: &[String], max_clauses: usize) -> Clauses {
+    let mut dp = Dp::new(clauses.iter().filter(|c| !c.is_tautology()).cloned().collect());
+    dp.remove_subsumed_clauses();
+    let mut left = vars.iter().map(|v| v.as_str()).collect::<HashSet<_>>();
+    loop {
+        // the variable adding the fewest clauses, by name on ties.
+        let next = dp
+            .occurrences()
+            .into_iter()
+            .filter(|(v, _)| left.contains(v))
+            .map(|(v, (p, n))| ((p * n) as isize - (p + n) as isize, v.to_string()))
+            .min();
+        let Some((growth, var)) = next else {
+            return dp.into_clauses();
+        };
+        if dp.len() as isize + growth > max_clauses as isize {
+            let working = dp.into_clauses();
+            let keep = working
+                .iter()
+                .flat_map(|c| c.iter().map(|l| l.var_name()))
+                .filter(|v| !left.contains(v))
+                .map(|v| v.to_string())
+                .collect::<HashSet<_>>();
+            return prime_implicates(&working, &keep);
+        }
+        dp.eliminate(&var);
+        left.remove(var.as_str());
+    }
+}
+
+
+/* The prime implicates of `clauses` over `keep`, their projection onto `keep` as a CNF.
+ *
+ * Implicates are collected in a second solver over the kept variables, which proposes
+ * assignments that satisfy them so far. One that cannot be extended to a model of
+ * `clauses` fails some of its literals, and the negation of a minimal failing subset is a
+ * new prime implicate. One that can be extended is widened to a cube of kept literals all
+ * whose extensions can, and that cube is blocked. When no assignment is left the implicates
+ * hold exactly on the covered cubes, the projection.
+ * */
+pub fn prime_implicates(clauses: &Clauses, keep: &HashSet<String>) -> Clauses {
+    let mut full = Solver::from_clauses(clauses);
+    let mut names = keep.iter().filter(|v| full.lookup(v).is_some()).cloned().collect::<Vec<_>>();
+    names.sort();
+    let vars = names.iter().map(|n| full.lookup(n).unwrap()).collect::<Vec<_>>();
+    let mut proposer = Solver::new();
+    let proposed = names.iter().map(|n| proposer.var(n)).collect::<Vec<_>>();
+
+    let mut implicates = Vec::new();
+    while proposer.solve() {
+        let assignment = (0..vars.len())
+            .map(|i| Lit::new(vars[i], proposer.value(proposed[i].pos()) != Some(true)))
+            .collect::<Vec<_>>();
+        // the same literal in the proposer.
+        let proposer_lit = |lit: Lit| {
+            let i = vars.iter().position(|&v| v == lit.var()).unwrap();
+            Lit::new(proposed[i], lit.is_negated())
+        };
+        if full.solve_with(&assignment) {
+            let cube = widen(clauses, &full, &assignment);
+            let block = cube.iter().map(|&l| proposer_lit(l).negate()).collect::<Vec<_>>();
+            proposer.add_clause(&block);
+        } else {
+            let core = full.core().to_vec();
+            let core = shrink(&mut full, core);
+            let implicate = core.iter().map(|&l| l.negate()).collect::<Vec<_>>();
+            proposer.add_clause(&implicate.iter().map(|&l| proposer_lit(l)).collect::<Vec<_>>());
+            implicates.push(implicate.iter().map(|&l| full.literal(l)).collect::<Clause>());
+        }
+    }
+    let mut dp = Dp::new(Clauses(implicates));
+    dp.remove_subsumed_clauses();
+    dp.into_clauses()
+}
+
+
+// a minimal subset of the failed assumptions `core` that still fails, by deletion.
+pub(crate) fn shrink(solver: &mut Solver, mut core: Vec<Lit>) -> Vec<Lit> {
+    let mut i = 0;
+    while i < core.len() {
+        let mut without = core.clone();
+        without.remove(i);
+        if solver.solve_with(&without) {
+            i += 1;
+        } else {
+            let smaller = solver.core().to_vec();
+            core.retain(|l| smaller.contains(l));
+        }
+    }
+    core
+}
+
+
+// literals of `assignment` enough to satisfy `clauses` together with the values the model
+// of `solver` gives the other variables.
+fn widen(clauses: &Clauses, solver: &Solver, assignment: &[Lit]) -> Vec<Lit> {
+    let kept = assignment.iter().map(|&l| (l.var(), l)).collect::<HashMap<_, _>>();
+    let mut cube = HashSet::new();
+    let mut uncovered = Vec::new();
+    for clause in clauses.iter() {
+        let mut lits = clause.iter().map(|l| solver.lookup(l.var_name()).map(|v| Lit::new(v, l.is_negated())).unwrap()).collect::<Vec<_>>();
+        lits.sort();
+        let by_others = lits.iter().any(|l| !kept.contains_key(&l.var()) && solver.value(*l) == Some(true));
+        if !by_others {
+            uncovered.push(lits.into_iter().filter(|l| kept.get(&l.var()) == Some(l)).collect::<Vec<_>>());
+        }
+    }
+    // clauses with a single choice first, then the rest reuse what was already taken.
+    uncovered.sort_by_key(|lits| lits.len());
+    for lits in uncovered {
+        if !lits.iter().any(|l| cube.contains(l)) {
+            cube.insert(lits[0]);
+        }
+    }
+    let mut cube = cube.into_iter().collect::<Vec<_>>();
+    cube.sort();
+    cube
+}
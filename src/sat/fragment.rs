@@ -0,0 +1,282 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::sat::cdcl;
+use crate::sat::clauses::*;
+
+
+/* Polynomial fragments of CNF.
+ *
+ * 2-CNF, where every clause has at most two literals, and Horn, where every clause has at
+ * most one positive literal, are decidable in linear time. A CNF is renamable Horn if
+ * flipping the polarity of some variables everywhere makes it Horn; finding such a renaming
+ * is itself a 2-SAT problem.
+ * */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fragment {
+    TwoCnf,
+    Horn,
+    /* Horn after flipping these variables. */
+    RenamableHorn(Vec<String>),
+    General,
+}
+
+
+pub fn is_two_cnf(clauses: &Clauses) -> bool {
+    clauses.iter().all(|c| c.len() <= 2)
+}
+
+
+pub fn is_horn(clauses: &Clauses) -> bool {
+    clauses.iter().all(|c| c.iter().filter(|l| !l.is_negated()).count() <= 1)
+}
+
+
+/* The cheapest fragment `clauses` belong to. */
+pub fn classify(clauses: &Clauses) -> Fragment {
+    if is_two_cnf(clauses) {
+        Fragment::TwoCnf
+    } else if is_horn(clauses) {
+        Fragment::Horn
+    } else if let Some(flipped) = horn_renaming(clauses) {
+        Fragment::RenamableHorn(flipped)
+    } else {
+        Fragment::General
+    }
+}
+
+
+/* Decide `clauses` if they fall into a polynomial fragment, `None` otherwise. */
+pub fn solve(clauses: &Clauses) -> Option<Option<Model>> {
+    match classify(clauses) {
+        Fragment::TwoCnf => Some(two_sat(clauses)),
+        Fragment::Horn => Some(horn_sat(clauses)),
+        Fragment::RenamableHorn(flipped) => Some(renamed_horn_sat(clauses, &flipped)),
+        Fragment::General => None,
+    }
+}
+
+
+/* Decided in polynomial time if the clauses are 2-CNF or (renamable) Horn, by CDCL
+ * otherwise. */
+pub fn satisfiable(clauses: Clauses) -> bool {
+    match solve(&clauses) {
+        Some(model) => model.is_some(),
+        None => cdcl::satisfiable_cdcl(clauses),
+    }
+}
+
+
+// variables of `clauses` numbered in order of appearance.
+fn index(clauses: &Clauses) -> (Vec<String>, HashMap<String, usize>) {
+    let mut names = Vec::new();
+    let mut indices = HashMap::new();
+    for literal in clauses.iter().flat_map(|c| c.iter()) {
+        if !indices.contains_key(literal.var_name()) {
+            indices.insert(literal.var_name().to_string(), names.len());
+            names.push(literal.var_name().to_string());
+        }
+    }
+    (names, indices)
+}
+
+
+/* 2-SAT by strongly connected components of the implication graph, Aspvall, Plass and
+ * Tarjan. A clause `a ∨ b` gives the edges `¬a → b` and `¬b → a`, and the clauses are
+ * unsatisfiable iff some x and ¬x share a component. Tarjan's algorithm finishes the
+ * components in reverse topological order, so setting x iff its component finishes before
+ * the one of ¬x never makes an implication false.
+ * */
+pub fn two_sat(clauses: &Clauses) -> Option<Model> {
+    assert!(is_two_cnf(clauses), "two_sat needs clauses of at most two literals");
+    if clauses.iter().any(|c| c.is_empty()) {
+        return None;
+    }
+    let (names, indices) = index(clauses);
+    // node 2v is v, node 2v + 1 is ¬v.
+    let node = |l: &Literal| 2 * indices[l.var_name()] + l.is_negated() as usize;
+    let mut graph = vec![Vec::new(); 2 * names.len()];
+    for clause in clauses.iter() {
+        let lits = clause.iter().map(node).collect::<Vec<_>>();
+        let (a, b) = match lits[..] {
+            [a] => (a, a),
+            [a, b] => (a, b),
+            _ => unreachable!(),
+        };
+        graph[a ^ 1].push(b);
+        graph[b ^ 1].push(a);
+    }
+
+    let components = tarjan(&graph);
+    let mut model = Model::new();
+    for (v, name) in names.into_iter().enumerate() {
+        let (pos, neg) = (components[2 * v], components[2 * v + 1]);
+        if pos == neg {
+            return None;
+        }
+        model.insert(name, pos < neg);
+    }
+    Some(model)
+}
+
+
+/* Component of every node, numbered in the order Tarjan's algorithm finishes them. Iterative,
+ * so long implication chains do not overflow the stack. */
+pub(crate) fn tarjan(graph: &[Vec<usize>]) -> Vec<usize> {
+    const UNVISITED: usize = usize::MAX;
+    let n = graph.len();
+    let mut order = vec![UNVISITED; n];
+    let mut low = vec![0; n];
+    let mut component = vec![UNVISITED; n];
+    let mut stack = Vec::new();
+    let mut counter = 0;
+    let mut components = 0;
+    for root in 0..n {
+        if order[root] != UNVISITED {
+            continue;
+        }
+        // (node, next edge to look at)
+        let mut calls = vec![(root, 0)];
+        order[root] = counter;
+        low[root] = counter;
+        counter += 1;
+        stack.push(root);
+        while let Some(&mut (v, ref mut edge)) = calls.last_mut() {
+            if let Some(&w) = graph[v].get(*edge) {
+                *edge += 1;
+                if order[w] == UNVISITED {
+                    order[w] = counter;
+                    low[w] = counter;
+                    counter += 1;
+                    stack.push(w);
+                    calls.push((w, 0));
+                } else if component[w] == UNVISITED {
+                    low[v] = low[v].min(order[w]);
+                }
+                continue;
+            }
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+            if low[v] == order[v] {
+                while let Some(w) = stack.pop() {
+                    component[w] = components;
+                    if w == v {
+                        break;
+                    }
+                }
+                components += 1;
+            }
+        }
+    }
+    component
+}
+
+
+/* Horn-SAT by unit resolution marking, Dowling and Gallier. Every clause counts the body
+ * variables, its negative literals, not yet known true; a clause whose count drops to zero
+ * forces its head, or is violated if it has none. What gets marked is the least model.
+ * */
+pub fn horn_sat(clauses: &Clauses) -> Option<Model> {
+    assert!(is_horn(clauses), "horn_sat needs clauses with at most one positive literal");
+    let (names, indices) = index(clauses);
+    let mut remaining = Vec::with_capacity(clauses.len());
+    let mut heads = Vec::with_capacity(clauses.len());
+    let mut bodies: Vec<Vec<usize>> = vec![Vec::new(); names.len()];
+    let mut queue = VecDeque::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        let head = clause.iter().find(|l| !l.is_negated()).map(|l| indices[l.var_name()]);
+        let body = clause.iter().filter(|l| l.is_negated()).map(|l| indices[l.var_name()]).collect::<Vec<_>>();
+        for &v in body.iter() {
+            bodies[v].push(i);
+        }
+        if body.is_empty() {
+            queue.push_back(i);
+        }
+        remaining.push(body.len());
+        heads.push(head);
+    }
+
+    let mut value = vec![false; names.len()];
+    while let Some(i) = queue.pop_front() {
+        let head = heads[i]?;
+        if value[head] {
+            continue;
+        }
+        value[head] = true;
+        for &j in bodies[head].iter() {
+            remaining[j] -= 1;
+            if remaining[j] == 0 {
+                queue.push_back(j);
+            }
+        }
+    }
+    Some(names.into_iter().zip(value).collect())
+}
+
+
+fn rename(clauses: &Clauses, flipped: &HashSet<&str>) -> Clauses {
+    clauses
+        .iter()
+        .map(|c| c.iter().map(|l| if flipped.contains(l.var_name()) { l.negate() } else { l.clone() }).collect())
+        .collect()
+}
+
+
+/* A set of variables whose flipping makes `clauses` Horn, `None` if there is none.
+ *
+ * With a variable `r_x` meaning "x is flipped", literal x ends up positive iff ¬r_x and ¬x
+ * iff r_x. Each clause may end up with at most one positive literal, which the sequential
+ * at-most-one encoding states in binary clauses with one auxiliary per literal, so the
+ * whole check is a linear size 2-SAT problem.
+ * */
+pub fn horn_renaming(clauses: &Clauses) -> Option<Vec<String>> {
+    let flip = |name: &str| format!("r{}", name);
+    let positive = |l: &Literal| if l.is_negated() { Literal::pos(flip(l.var_name())) } else { Literal::neg(flip(l.var_name())) };
+    let binary = |a: Literal, b: Literal| vec![a, b].into_iter().collect::<Clause>();
+    let mut constraints = Clauses::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        // s{i}_{j}: one of the first j + 1 literals of clause i is positive.
+        let seen = |j: usize| Literal::pos(format!("s{}_{}", i, j));
+        for (j, literal) in clause.iter().enumerate() {
+            constraints.push(binary(positive(literal).negate(), seen(j)));
+            if j > 0 {
+                constraints.push(binary(seen(j - 1).negate(), seen(j)));
+                constraints.push(binary(seen(j - 1).negate(), positive(literal).negate()));
+            }
+        }
+    }
+    let model = two_sat(&constraints)?;
+    let mut flipped = clauses
+        .iter()
+        .flat_map(|c| c.iter().map(|l| l.var_name()))
+        .filter(|v| model.get(&flip(v)) == Some(&true))
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>();
+    flipped.sort();
+    flipped.dedup();
+    Some(flipped)
+}
+
+
+/* Horn-SAT on the renamed clauses, with the model flipped back. */
+pub fn renamed_horn_sat(clauses: &Clauses, flipped: &[String]) -> Option<Model> {
+    let set = flipped.iter().map(|v| v.as_str()).collect::<HashSet<_>>();
+    let mut model = horn_sat(&rename(clauses, &set))?;
+    for (var, value) in model.iter_mut() {
+        if set.contains(var.as_str()) {
+            *value = !*value;
+        }
+    }
+    Some(model)
+}
+
+
+pub fn satisfiable_two_sat(clauses: Clauses) -> bool {
+    two_sat(&clauses).is_some()
+}
+
+
+pub fn satisfiable_horn(clauses: Clauses) -> bool {
+    horn_sat(&clauses).is_some()
+}
@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+
+use crate::sat::clauses::*;
+
+
+/* Instance generators, for tests and benchmarks.
+ *
+ * Everything random takes an explicit `Rng`, so an instance is reproducible from its seed.
+ * Random k-SAT names its variables `1..=n` like DIMACS does, the structured families use
+ * descriptive names such as `p2h1` for "pigeon 2 sits in hole 1".
+ * */
+
+
+/* SplitMix64, small and good enough for generating instances. Not for cryptography. */
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /* Uniform in `0..n`. */
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "empty range");
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /* Uniform in `[0, 1)`. */
+    pub fn float(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+
+/* Undirected simple graph on vertices `0..vertices`. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    pub vertices: usize,
+    pub edges: Vec<(usize, usize)>,
+}
+
+
+impl Graph {
+    /* The cycle 0, 1, ..., n - 1, back to 0, for n >= 3. */
+    pub fn cycle(n: usize) -> Self {
+        Graph { vertices: n, edges: (0..n).map(|v| (v, (v + 1) % n)).collect() }
+    }
+
+    pub fn complete(n: usize) -> Self {
+        Graph { vertices: n, edges: (0..n).flat_map(|u| (u + 1..n).map(move |v| (u, v))).collect() }
+    }
+
+    /* The `width` × `height` grid, vertex `(x, y)` being `y * width + x`. */
+    pub fn grid(width: usize, height: usize) -> Self {
+        let mut edges = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let v = y * width + x;
+                if x + 1 < width {
+                    edges.push((v, v + 1));
+                }
+                if y + 1 < height {
+                    edges.push((v, v + width));
+                }
+            }
+        }
+        Graph { vertices: width * height, edges }
+    }
+
+    /* A uniformly random graph with `n` vertices and `m` distinct edges. */
+    pub fn random(rng: &mut Rng, n: usize, m: usize) -> Self {
+        assert!(m <= n * n.saturating_sub(1) / 2, "too many edges for {} vertices", n);
+        let mut edges = HashSet::new();
+        while edges.len() < m {
+            let (u, v) = (rng.below(n), rng.below(n));
+            if u != v {
+                edges.insert((u.min(v), u.max(v)));
+            }
+        }
+        let mut edges = edges.into_iter().collect::<Vec<_>>();
+        edges.sort();
+        Graph { vertices: n, edges }
+    }
+}
+
+
+fn literal(name: String, positive: bool) -> Literal {
+    if positive { Literal::pos(name) } else { Literal::neg(name) }
+}
+
+
+// a clause over k distinct variables of 1..=n with random signs.
+fn random_clause(rng: &mut Rng, k: usize, n: usize) -> Clause {
+    let mut vars = Vec::with_capacity(k);
+    while vars.len() < k {
+        let v = 1 + rng.below(n);
+        if !vars.contains(&v) {
+            vars.push(v);
+        }
+    }
+    vars.into_iter().map(|v| literal(v.to_string(), rng.bool())).collect()
+}
+
+
+/* Uniform random k-SAT with `n` variables and `round(ratio * n)` clauses. Each clause has k
+ * distinct variables, so `k <= n`. For 3-SAT the instances are hardest around the
+ * satisfiability threshold, a ratio of about 4.26.
+ * */
+pub fn random_ksat(rng: &mut Rng, k: usize, n: usize, ratio: f64) -> Clauses {
+    assert!(k <= n, "clauses of {} distinct variables out of {}", k, n);
+    let m = (ratio * n as f64).round() as usize;
+    (0..m).map(|_| random_clause(rng, k, n)).collect()
+}
+
+
+/* Random k-SAT that is satisfiable by construction: a hidden assignment is drawn first, and
+ * clauses it falsifies are rejected. Returns the clauses with the hidden assignment.
+ * */
+pub fn planted_ksat(rng: &mut Rng, k: usize, n: usize, ratio: f64) -> (Clauses, Model) {
+    assert!(k <= n, "clauses of {} distinct variables out of {}", k, n);
+    let model = (1..=n).map(|v| (v.to_string(), rng.bool())).collect::<Model>();
+    let m = (ratio * n as f64).round() as usize;
+    let mut clauses = Clauses::new();
+    while clauses.len() < m {
+        let clause = random_clause(rng, k, n);
+        if clause.is_satisfied_by(&model) {
+            clauses.push(clause);
+        }
+    }
+    (clauses, model)
+}
+
+
+/* `holes + 1` pigeons in `holes` holes, one per hole. Unsatisfiable, and resolution proofs
+ * of that are exponential in the number of holes.
+ * */
+pub fn pigeonhole(holes: usize) -> Clauses {
+    let var = |p: usize, h: usize| format!("p{}h{}", p, h);
+    let mut clauses = Clauses::new();
+    for p in 0..=holes {
+        clauses.push((0..holes).map(|h| Literal::pos(var(p, h))).collect());
+    }
+    for h in 0..holes {
+        for p in 0..=holes {
+            for q in p + 1..=holes {
+                clauses.push(vec![Literal::neg(var(p, h)), Literal::neg(var(q, h))].into_iter().collect());
+            }
+        }
+    }
+    clauses
+}
+
+
+/* Tseitin parity formula of a graph: one variable `e{u}_{v}` per edge, and at every vertex
+ * the edges around it must xor to its charge. Summing all constraints counts each edge
+ * twice, so the formula is unsatisfiable iff some connected component has odd total charge.
+ * */
+pub fn tseitin_parity(graph: &Graph, charges: &[bool]) -> Clauses {
+    assert_eq!(charges.len(), graph.vertices, "one charge per vertex");
+    let mut incident = vec![Vec::new(); graph.vertices];
+    for &(u, v) in graph.edges.iter() {
+        let name = format!("e{}_{}", u.min(v), u.max(v));
+        incident[u].push(name.clone());
+        incident[v].push(name);
+    }
+    let mut clauses = Clauses::new();
+    for (edges, &charge) in incident.iter().zip(charges.iter()) {
+        // forbid every assignment of the incident edges with the wrong parity.
+        for bits in 0..1usize << edges.len() {
+            if (bits.count_ones() % 2 == 1) != charge {
+                clauses.push(edges.iter().enumerate().map(|(i, e)| literal(e.clone(), bits >> i & 1 == 0)).collect());
+            }
+        }
+    }
+    clauses
+}
+
+
+/* Proper `k`-colouring of a graph, `v{v}c{c}` meaning vertex v has colour c. Every vertex
+ * gets exactly one colour and adjacent vertices get different ones, so models and
+ * colourings correspond one to one.
+ * */
+pub fn colouring(graph: &Graph, k: usize) -> Clauses {
+    let var = |v: usize, c: usize| format!("v{}c{}", v, c);
+    let mut clauses = Clauses::new();
+    for v in 0..graph.vertices {
+        clauses.push((0..k).map(|c| Literal::pos(var(v, c))).collect());
+        for c in 0..k {
+            for d in c + 1..k {
+                clauses.push(vec![Literal::neg(var(v, c)), Literal::neg(var(v, d))].into_iter().collect());
+            }
+        }
+    }
+    for &(u, v) in graph.edges.iter() {
+        for c in 0..k {
+            clauses.push(vec![Literal::neg(var(u, c)), Literal::neg(var(v, c))].into_iter().collect());
+        }
+    }
+    clauses
+}
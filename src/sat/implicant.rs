@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::fol::ast::*;
+use crate::sat::cdcl::*;
+use crate::sat::clauses::*;
+
+
+/* Prime implicants: a partial assignment is an implicant of F if every extension of it
+ * satisfies F, and prime if no literal can be dropped from it. A prime implicant shrunk
+ * from a model keeps only the atoms that matter to it.
+ *
+ * For clauses an assignment is an implicant exactly when it makes a literal of every
+ * non-tautological clause true, so shrinking is a greedy pass over the literals, dropping
+ * each one that is not the last true literal of some clause. For a formula an assignment
+ * is an implicant when it refutes the negated formula, and a SAT solver on the Tseitin
+ * encoding of ¬F shrinks it by deletion, starting from the failed assumptions.
+ *
+ * Literals are returned sorted by variable name.
+ * */
+pub struct Implicants {
+    // the clauses or the encoding of F, to enumerate models of.
+    models: Solver,
+    check: Check,
+    atoms: HashSet<String>,
+}
+
+
+enum Check {
+    Cover(Clauses),
+    // the Tseitin encoding of ¬F.
+    Refute(Box<Solver>),
+}
+
+
+impl Implicants {
+    pub fn new(clauses: &Clauses) -> Self {
+        let clauses = clauses.iter().filter(|c| !c.is_tautology()).cloned().collect::<Clauses>();
+        let atoms = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name().to_string())).collect();
+        Implicants { models: Solver::from_clauses(&clauses), check: Check::Cover(clauses), atoms }
+    }
+
+    /* Implicants of a propositional formula, every atom `P(t, ...)` being a variable named
+     * like `Clauses::from_formula` names it. Quantifiers are not propositional and panic.
+     * */
+    pub fn from_formula<S>(formula: &Formula<S>) -> Self {
+        let mut atoms = HashSet::new();
+        collect_atoms(formula, &mut atoms);
+        let encoded = |holds: bool| {
+            let mut solver = Solver::new();
+            // atoms first, so the generated names of the gates never shadow them.
+            let mut names = atoms.iter().collect::<Vec<_>>();
+            names.sort();
+            for name in names {
+                solver.var(name);
+            }
+            let root = encode(&mut solver, formula);
+            solver.add_clause(&[if holds { root } else { root.negate() }]);
+            solver
+        };
+        Implicants { models: encoded(true), check: Check::Refute(Box::new(encoded(false))), atoms }
+    }
+
+    /* True if every extension of `assignment` satisfies the clauses or formula. */
+    pub fn is_implicant(&mut self, assignment: &[Literal]) -> bool {
+        let atoms = &self.atoms;
+        match self.check {
+            Check::Cover(ref clauses) => {
+                let assigned = assignment.iter().collect::<HashSet<_>>();
+                clauses.iter().all(|c| c.iter().any(|l| assigned.contains(l)))
+            },
+            Check::Refute(ref mut solver) => {
+                let lits = assignment.iter().filter(|l| atoms.contains(l.var_name())).map(|l| solver.lit(l)).collect::<Vec<_>>();
+                !solver.solve_with(&lits)
+            },
+        }
+    }
+
+    /* A prime implicant contained in `model`, which may be partial but has to be an
+     * implicant itself, `None` if it is not. Variables that do not occur are dropped.
+     * */
+    pub fn shrink(&mut self, model: &Model) -> Option<Vec<Literal>> {
+        let mut names = model.keys().filter(|v| self.atoms.contains(v.as_str())).collect::<Vec<_>>();
+        names.sort();
+        let assignment = names
+            .into_iter()
+            .map(|v| if model[v] { Literal::pos(v.clone()) } else { Literal::neg(v.clone()) })
+            .collect::<Vec<_>>();
+        let mut implicant = match self.check {
+            Check::Cover(ref clauses) => cover(clauses, assignment)?,
+            Check::Refute(ref mut solver) => {
+                let lits = assignment.iter().map(|l| solver.lit(l)).collect::<Vec<_>>();
+                if solver.solve_with(&lits) {
+                    return None;
+                }
+                let core = solver.core().to_vec();
+                let core = crate::sat::forget::shrink(solver, core);
+                core.into_iter().map(|l| solver.literal(l)).collect()
+            },
+        };
+        implicant.sort_by(|a, b| a.var_name().cmp(b.var_name()));
+        Some(implicant)
+    }
+
+    /* Up to `limit` distinct prime implicants. Each comes from a model that none of the
+     * earlier ones is contained in, so when fewer than `limit` are returned their
+     * disjunction is equivalent to the clauses or formula.
+     * */
+    pub fn enumerate(&mut self, limit: usize) -> Vec<Vec<Literal>> {
+        let mut implicants = Vec::new();
+        while implicants.len() < limit && self.models.solve() {
+            let mut model = self.models.model();
+            model.retain(|v, _| self.atoms.contains(v));
+            let implicant = self.shrink(&model).expect("a model is an implicant");
+            let block = implicant.iter().map(|l| self.models.lit(&l.negate())).collect::<Vec<_>>();
+            self.models.add_clause(&block);
+            implicants.push(implicant);
+        }
+        implicants
+    }
+}
+
+
+// drop every literal of `assignment` that is not the only true one of some clause.
+fn cover(clauses: &Clauses, assignment: Vec<Literal>) -> Option<Vec<Literal>> {
+    let assigned = assignment.iter().collect::<HashSet<_>>();
+    let mut count = HashMap::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        let n = clause.iter().filter(|l| assigned.contains(l)).count();
+        if n == 0 {
+            return None;
+        }
+        count.insert(i, n);
+    }
+    let mut occurrences: HashMap<&Literal, Vec<usize>> = HashMap::new();
+    for (i, clause) in clauses.iter().enumerate() {
+        for literal in clause.iter().filter(|l| assigned.contains(l)) {
+            occurrences.entry(literal).or_default().push(i);
+        }
+    }
+    let mut implicant = Vec::new();
+    for literal in assignment.iter() {
+        let Some(clauses) = occurrences.get(literal) else {
+            continue;
+        };
+        if clauses.iter().all(|c| count[c] > 1) {
+            for c in clauses {
+                *count.get_mut(c).unwrap() -= 1;
+            }
+        } else {
+            implicant.push(literal.clone());
+        }
+    }
+    Some(implicant)
+}
+
+
+fn collect_atoms<S>(formula: &Formula<S>, atoms: &mut HashSet<String>) {
+    match formula {
+        Formula::Pred(pred) => {
+            atoms.insert(pred.unique());
+        },
+        Formula::Not(not) => collect_atoms(&not.formula, atoms),
+        Formula::And(And { formula1, formula2, .. })
+        | Formula::Or(Or { formula1, formula2, .. })
+        | Formula::Implies(Implies { formula1, formula2, .. })
+        | Formula::Iff(Iff { formula1, formula2, .. }) => {
+            collect_atoms(formula1, atoms);
+            collect_atoms(formula2, atoms);
+        },
+        _ => panic!("Expect propositional formula, got {:?}", formula)
+    }
+}
+
+
+// Tseitin encoding of `formula` into `solver`, the returned literal is true exactly when
+// the formula is.
+fn encode<S>(solver: &mut Solver, formula: &Formula<S>) -> Lit {
+    let gate = |solver: &mut Solver, a: &Formula<S>, b: &Formula<S>| {
+        let a = encode(solver, a);
+        let b = encode(solver, b);
+        (solver.new_var().pos(), a, b)
+    };
+    match formula {
+        Formula::Pred(pred) => solver.var(&pred.unique()).pos(),
+        Formula::Not(not) => encode(solver, &not.formula).negate(),
+        Formula::And(and) => {
+            let (x, a, b) = gate(solver, &and.formula1, &and.formula2);
+            solver.add_clause(&[x.negate(), a]);
+            solver.add_clause(&[x.negate(), b]);
+            solver.add_clause(&[x, a.negate(), b.negate()]);
+            x
+        },
+        Formula::Or(or) => {
+            let (x, a, b) = gate(solver, &or.formula1, &or.formula2);
+            solver.add_clause(&[x.negate(), a, b]);
+            solver.add_clause(&[x, a.negate()]);
+            solver.add_clause(&[x, b.negate()]);
+            x
+        },
+        Formula::Implies(imp) => {
+            let (x, a, b) = gate(solver, &imp.formula1, &imp.formula2);
+            solver.add_clause(&[x.negate(), a.negate(), b]);
+            solver.add_clause(&[x, a]);
+            solver.add_clause(&[x, b.negate()]);
+            x
+        },
+        Formula::Iff(iff) => {
+            let (x, a, b) = gate(solver, &iff.formula1, &iff.formula2);
+            solver.add_clause(&[x.negate(), a.negate(), b]);
+            solver.add_clause(&[x.negate(), a, b.negate()]);
+            solver.add_clause(&[x, a, b]);
+            solver.add_clause(&[x, a.negate(), b.negate()]);
+            x
+        },
+        _ => panic!("Expect propositional formula, got {:?}", formula)
+    }
+}
+
+
+/* A prime implicant of `clauses` contained in `model`, `None` if `model` does not satisfy
+ * them. */
+pub fn prime_implicant(clauses: &Clauses, model: &Model) -> Option<Vec<Literal>> {
+    Implicants::new(clauses).shrink(model)
+}
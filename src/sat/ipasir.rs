@@ -0,0 +1,165 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::os::raw::{c_char, c_int, c_void};
+
+use crate::sat::cdcl::*;
+use crate::sat::clauses::Literal;
+
+
+/* The IPASIR C interface to the incremental CDCL solver, see `include/ipasir.h`.
+ *
+ * A solver handle is a boxed `Ipasir`. Variable n is the solver variable named `n` like in
+ * DIMACS, so `Clauses::from_dimacs` and IPASIR agree on names. Clauses are added literal by
+ * literal and closed with a zero, assumptions hold for the next `ipasir_solve` only.
+ *
+ * The safety contract is IPASIR's: every handle comes from `ipasir_init` and is passed to
+ * `ipasir_release` exactly once, and callbacks stay valid while they are set. Literals are
+ * non-zero and not `INT32_MIN`, except for the zero closing a clause. A panic cannot unwind
+ * into C and aborts the host, so breaking these is only caught by debug assertions: release
+ * builds ignore a null handle or an invalid literal.
+ * */
+struct Ipasir {
+    solver: Solver,
+    clause: Vec<Lit>,
+    assumptions: Vec<Lit>,
+}
+
+
+impl Ipasir {
+    // the literal, `None` if it is not one.
+    fn lit(&mut self, lit: i32) -> Option<Lit> {
+        debug_assert!(lit != 0 && lit != i32::MIN, "invalid literal {}", lit);
+        if lit == 0 || lit == i32::MIN {
+            return None;
+        }
+        let var = self.solver.var(&lit.unsigned_abs().to_string());
+        Some(Lit::new(var, lit < 0))
+    }
+
+    // the literal if the variable exists, without creating it.
+    fn lookup(&self, lit: i32) -> Option<Lit> {
+        let var = self.solver.lookup(&lit.unsigned_abs().to_string())?;
+        Some(Lit::new(var, lit < 0))
+    }
+}
+
+
+unsafe fn handle<'a>(solver: *mut c_void) -> Option<&'a mut Ipasir> {
+    debug_assert!(!solver.is_null(), "null IPASIR solver");
+    (solver as *mut Ipasir).as_mut()
+}
+
+
+fn dimacs(literal: &Literal) -> i32 {
+    let var = literal.var_name().parse::<i32>().expect("IPASIR variables are numbers");
+    if literal.is_negated() { -var } else { var }
+}
+
+
+#[no_mangle]
+pub extern "C" fn ipasir_signature() -> *const c_char {
+    concat!("theorem_prover-", env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+
+#[no_mangle]
+pub extern "C" fn ipasir_init() -> *mut c_void {
+    let state = Ipasir { solver: Solver::new(), clause: Vec::new(), assumptions: Vec::new() };
+    Box::into_raw(Box::new(state)) as *mut c_void
+}
+
+
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_release(solver: *mut c_void) {
+    if !solver.is_null() {
+        drop(Box::from_raw(solver as *mut Ipasir));
+    }
+}
+
+
+/* Add a literal to the clause being built, or close it with 0. */
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_add(solver: *mut c_void, lit_or_zero: i32) {
+    let Some(state) = handle(solver) else { return };
+    if lit_or_zero == 0 {
+        let clause = std::mem::take(&mut state.clause);
+        state.solver.add_clause(&clause);
+    } else if let Some(lit) = state.lit(lit_or_zero) {
+        state.clause.push(lit);
+    }
+}
+
+
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_assume(solver: *mut c_void, lit: i32) {
+    let Some(state) = handle(solver) else { return };
+    if let Some(lit) = state.lit(lit) {
+        state.assumptions.push(lit);
+    }
+}
+
+
+/* 10 if satisfiable, 20 if unsatisfiable and 0 if the terminate callback stopped it. */
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_solve(solver: *mut c_void) -> c_int {
+    let Some(state) = handle(solver) else { return 0 };
+    let assumptions = std::mem::take(&mut state.assumptions);
+    match state.solver.solve_limited(&assumptions, None) {
+        Some(true) => 10,
+        Some(false) => 20,
+        None => 0,
+    }
+}
+
+
+/* `lit` if it is true in the last model, `-lit` if it is false and 0 if it does not
+ * matter. */
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_val(solver: *mut c_void, lit: i32) -> i32 {
+    let Some(state) = handle(solver) else { return 0 };
+    match state.lookup(lit).and_then(|l| state.solver.value(l)) {
+        Some(true) => lit,
+        Some(false) => -lit,
+        None => 0,
+    }
+}
+
+
+/* 1 if assumption `lit` was used to refute the last call, 0 otherwise. */
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_failed(solver: *mut c_void, lit: i32) -> c_int {
+    let Some(state) = handle(solver) else { return 0 };
+    state.lookup(lit).is_some_and(|l| state.solver.failed(l)) as c_int
+}
+
+
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_terminate(
+    solver: *mut c_void,
+    data: *mut c_void,
+    terminate: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+) {
+    let Some(state) = handle(solver) else { return };
+    state.solver.set_terminate(terminate.map(|terminate| -> Terminate {
+        Box::new(move || unsafe { terminate(data) != 0 })
+    }));
+}
+
+
+/* `learn` gets every learnt clause of at most `max_length` literals, zero terminated. */
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_learn(
+    solver: *mut c_void,
+    data: *mut c_void,
+    max_length: c_int,
+    learn: Option<unsafe extern "C" fn(*mut c_void, *mut i32)>,
+) {
+    let Some(state) = handle(solver) else { return };
+    state.solver.set_learn(max_length.max(0) as usize, learn.map(|learn| -> Learn {
+        Box::new(move |clause: &[Literal]| {
+            let mut lits = clause.iter().map(dimacs).collect::<Vec<_>>();
+            lits.push(0);
+            unsafe { learn(data, lits.as_mut_ptr()) }
+        })
+    }));
+}
@@ -1,3 +1,26 @@
 pub mod dp;
 pub mod clauses;
 pub mod dimacs;
+pub mod stats;
+pub mod cdcl;
+pub mod checkpoint;
+pub mod propagator;
+pub mod ipasir;
+pub mod backbone;
+pub mod mus;
+pub mod aig;
+pub mod aiger;
+pub mod bmc;
+pub mod bdd;
+pub mod qbf;
+pub mod qdimacs;
+pub mod gen;
+pub mod brute;
+pub mod fragment;
+pub mod dnnf;
+pub mod forget;
+pub mod implicant;
+pub mod symmetry;
+pub mod approxmc;
+pub mod features;
+pub mod selector;
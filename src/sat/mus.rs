@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+
+use crate::sat::cdcl::*;
+use crate::sat::clauses::*;
+
+
+/* Minimal unsatisfiable subsets (MUS) and minimal correction sets (MCS).
+ *
+ * A MUS is an unsatisfiable subset of the clauses that becomes satisfiable when any one of
+ * its clauses is dropped; a MCS is a subset whose removal makes the rest satisfiable, and
+ * which is minimal with that property. Every MUS hits every MCS and vice versa, so
+ * together they explain why a set of requirements conflicts and how to repair it.
+ *
+ * All subsets are reported as sorted indices into the input `Clauses`.
+ * */
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shrink {
+    /* Try dropping the clauses one by one, keeping only those that are needed. */
+    Deletion,
+    /* Junker's divide and conquer algorithm, fewer calls when the MUS is small. */
+    QuickXplain,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subset {
+    Mus(Vec<usize>),
+    Mcs(Vec<usize>),
+}
+
+
+/* An incremental solver where clause i only takes part in a call if its selector is assumed.
+ * Clause i is added as `Ci ∨ ¬si`.
+ * */
+struct Oracle {
+    solver: Solver,
+    selectors: Vec<Lit>,
+}
+
+
+impl Oracle {
+    fn new(clauses: &Clauses) -> Self {
+        let mut solver = Solver::new();
+        // intern the problem variables first so selector names never shadow them.
+        let lits = clauses
+            .iter()
+            .map(|c| c.iter().map(|l| solver.lit(l)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let mut selectors = Vec::with_capacity(lits.len());
+        for mut clause in lits {
+            let selector = solver.new_var().pos();
+            clause.push(selector.negate());
+            solver.add_clause(&clause);
+            selectors.push(selector);
+        }
+        Oracle { solver, selectors }
+    }
+
+    fn is_sat(&mut self, subset: &[usize]) -> bool {
+        let assumptions = subset.iter().map(|&i| self.selectors[i]).collect::<Vec<_>>();
+        self.solver.solve_with(&assumptions)
+    }
+
+    /* Indices of the clauses used to refute the last call. */
+    fn core(&self) -> Vec<usize> {
+        let mut core = self.selectors
+            .iter()
+            .enumerate()
+            .filter(|(_, &s)| self.solver.failed(s))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        core.sort();
+        core
+    }
+
+    /* Indices of the clauses satisfied by the model of the last call. */
+    fn satisfied(&self, clauses: &Clauses) -> Vec<usize> {
+        let model = self.solver.model();
+        (0..clauses.len()).filter(|&i| clauses[i].is_satisfied_by(&model)).collect()
+    }
+
+    /* Extend a satisfiable subset until no other clause can be added. */
+    fn grow(&mut self, clauses: &Clauses, subset: &[usize]) -> Vec<usize> {
+        let mut current = subset.iter().cloned().collect::<HashSet<_>>();
+        let sat = self.is_sat(subset);
+        assert!(sat, "grow needs a satisfiable seed");
+        current.extend(self.satisfied(clauses));
+        for i in 0..clauses.len() {
+            if current.contains(&i) {
+                continue;
+            }
+            let mut candidate = current.iter().cloned().collect::<Vec<_>>();
+            candidate.push(i);
+            if self.is_sat(&candidate) {
+                current.extend(self.satisfied(clauses));
+            }
+        }
+        let mut grown = current.into_iter().collect::<Vec<_>>();
+        grown.sort();
+        grown
+    }
+
+    fn shrink(&mut self, core: &[usize], method: Shrink) -> Vec<usize> {
+        let mut mus = match method {
+            Shrink::Deletion => self.deletion(core),
+            Shrink::QuickXplain => self.quickxplain(&[], &[], core),
+        };
+        mus.sort();
+        mus
+    }
+
+    fn deletion(&mut self, core: &[usize]) -> Vec<usize> {
+        let mut current = core.to_vec();
+        let mut critical = HashSet::new();
+        let mut i = 0;
+        while i < current.len() {
+            let candidate = current[i];
+            if critical.contains(&candidate) {
+                i += 1;
+                continue;
+            }
+            let rest = current.iter().cloned().filter(|&c| c != candidate).collect::<Vec<_>>();
+            if self.is_sat(&rest) {
+                critical.insert(candidate);
+                i += 1;
+            } else {
+                // the refutation may not need all of `rest`, keep only what it used.
+                let used = self.core().into_iter().collect::<HashSet<_>>();
+                current = rest.into_iter().filter(|c| used.contains(c)).collect();
+                i = 0;
+            }
+        }
+        current
+    }
+
+    /* QuickXplain: `background` is known to be part of the answer, `delta` was the last
+     * chunk added to it. Returns a minimal subset of `candidates` that is unsatisfiable
+     * together with the background.
+     * */
+    fn quickxplain(&mut self, background: &[usize], delta: &[usize], candidates: &[usize]) -> Vec<usize> {
+        if !delta.is_empty() && !self.is_sat(background) {
+            return Vec::new();
+        }
+        if candidates.len() == 1 {
+            return candidates.to_vec();
+        }
+        let (left, right) = candidates.split_at(candidates.len() / 2);
+        let with_left = [background, left].concat();
+        let right_part = self.quickxplain(&with_left, left, right);
+        let with_right = [background, &right_part].concat();
+        let left_part = self.quickxplain(&with_right, &right_part, left);
+        [left_part, right_part].concat()
+    }
+}
+
+
+/* Indices of the clauses used in one refutation, `None` if the clauses are satisfiable.
+ * The core is not minimal in general, see `mus` and `shrink`.
+ * */
+pub fn unsat_core(clauses: &Clauses) -> Option<Vec<usize>> {
+    let mut oracle = Oracle::new(clauses);
+    let all = (0..clauses.len()).collect::<Vec<_>>();
+    if oracle.is_sat(&all) {
+        None
+    } else {
+        Some(oracle.core())
+    }
+}
+
+
+/* Shrink the unsatisfiable subset `core` of `clauses` to a MUS. */
+pub fn shrink(clauses: &Clauses, core: &[usize], method: Shrink) -> Vec<usize> {
+    let mut oracle = Oracle::new(clauses);
+    let sat = oracle.is_sat(core);
+    assert!(!sat, "shrink needs an unsatisfiable core");
+    oracle.shrink(core, method)
+}
+
+
+/* One MUS of `clauses`, `None` if they are satisfiable. */
+pub fn mus(clauses: &Clauses) -> Option<Vec<usize>> {
+    let mut oracle = Oracle::new(clauses);
+    let all = (0..clauses.len()).collect::<Vec<_>>();
+    if oracle.is_sat(&all) {
+        return None;
+    }
+    let core = oracle.core();
+    Some(oracle.shrink(&core, Shrink::Deletion))
+}
+
+
+/* MARCO enumeration of all MUSes and MCSes.
+ *
+ * A second solver, the map, has one variable per clause and its models are the subsets
+ * not explored yet. Each model is a seed: a satisfiable seed is grown to a maximal
+ * satisfiable subset whose complement is a MCS, and every subset of it is blocked; an
+ * unsatisfiable seed is shrunk to a MUS, and every superset of it is blocked. The map
+ * prefers including clauses, so seeds tend to be large.
+ * */
+pub struct Marco<'a> {
+    clauses: &'a Clauses,
+    oracle: Oracle,
+    map: Solver,
+    method: Shrink,
+}
+
+
+impl<'a> Marco<'a> {
+    pub fn new(clauses: &'a Clauses) -> Self {
+        Marco::with_shrink(clauses, Shrink::Deletion)
+    }
+
+    pub fn with_shrink(clauses: &'a Clauses, method: Shrink) -> Self {
+        let mut map = Solver::new();
+        for i in 0..clauses.len() {
+            let var = map.var(&i.to_string());
+            map.set_phase(var, true);
+        }
+        Marco { clauses, oracle: Oracle::new(clauses), map, method }
+    }
+
+    fn seed(&mut self) -> Option<Vec<usize>> {
+        if !self.map.solve() {
+            return None;
+        }
+        Some((0..self.clauses.len()).filter(|&i| self.map.value(Var(i).pos()) == Some(true)).collect())
+    }
+}
+
+
+impl<'a> Iterator for Marco<'a> {
+    type Item = Subset;
+
+    fn next(&mut self) -> Option<Subset> {
+        let seed = self.seed()?;
+        if self.oracle.is_sat(&seed) {
+            let mss = self.oracle.grow(self.clauses, &seed);
+            let mcs = (0..self.clauses.len()).filter(|i| mss.binary_search(i).is_err()).collect::<Vec<_>>();
+            let block = mcs.iter().map(|&i| Var(i).pos()).collect::<Vec<_>>();
+            self.map.add_clause(&block);
+            Some(Subset::Mcs(mcs))
+        } else {
+            let core = self.oracle.core();
+            let mus = self.oracle.shrink(&core, self.method);
+            let block = mus.iter().map(|&i| Var(i).neg()).collect::<Vec<_>>();
+            self.map.add_clause(&block);
+            Some(Subset::Mus(mus))
+        }
+    }
+}
+
+
+pub fn all_muses(clauses: &Clauses) -> Vec<Vec<usize>> {
+    Marco::new(clauses).filter_map(|s| if let Subset::Mus(mus) = s { Some(mus) } else { None }).collect()
+}
+
+
+pub fn all_mcses(clauses: &Clauses) -> Vec<Vec<usize>> {
+    Marco::new(clauses).filter_map(|s| if let Subset::Mcs(mcs) = s { Some(mcs) } else { None }).collect()
+}
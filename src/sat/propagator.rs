@@ -0,0 +1,56 @@
+use crate::sat::cdcl::Lit;
+
+
+/* Domain specific reasoning plugged into the CDCL search, in the style of IPASIR-UP
+ * (Fazekas, Niemetz, Preiner, Kirchweger, Szeider and Biere).
+ *
+ * The propagator sees the assignments of the variables it observes, see
+ * `Solver::add_observed_var`, and the backtracks undoing them, so it can keep a partial
+ * assignment of its own in sync with the trail. In return it may
+ *
+ *  - propagate literals. Their reasons are only asked for when conflict analysis needs
+ *    them, most propagations never need one.
+ *  - add clauses whenever the search reaches a fixpoint.
+ *  - reject a complete model, in which case it must add a clause the model violates.
+ *  - suggest the next decision.
+ *
+ * Everything but `add_reason_clause` has a default doing nothing, so a propagator only
+ * implements what it uses.
+ * */
+pub trait ExternalPropagator {
+    /* `lit` of an observed variable became true, `fixed` if it holds at decision level 0
+     * and is never undone. */
+    fn notify_assignment(&mut self, _lit: Lit, _fixed: bool) {}
+
+    fn notify_new_decision_level(&mut self) {}
+
+    /* Assignments above decision level `level` were undone. */
+    fn notify_backtrack(&mut self, _level: usize) {}
+
+    /* Called on every complete assignment, given as the list of true literals. Returning
+     * false rejects it, and `add_external_clause` must then give a clause it violates. */
+    fn check_found_model(&mut self, _model: &[Lit]) -> bool {
+        true
+    }
+
+    /* The next decision, `None` to leave it to the solver. Assigned literals are ignored. */
+    fn decide(&mut self) -> Option<Lit> {
+        None
+    }
+
+    /* A literal implied by the current assignment, `None` when there is nothing to add.
+     * Called until it returns `None` or a conflict shows up. */
+    fn propagate(&mut self) -> Option<Lit> {
+        None
+    }
+
+    /* A clause containing `propagated` whose other literals are false, the reason of an
+     * earlier `propagate`. */
+    fn add_reason_clause(&mut self, propagated: Lit) -> Vec<Lit>;
+
+    /* A clause to add to the problem, `None` when there is none. Called until it returns
+     * `None`. */
+    fn add_external_clause(&mut self) -> Option<Vec<Lit>> {
+        None
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead};
+
+use crate::sat::aig::*;
+use crate::sat::cdcl::*;
+use crate::sat::clauses::*;
+use crate::sat::qdimacs;
+
+
+/* Quantified Boolean formulas in prenex CNF.
+ *
+ * The prefix lists quantifier blocks outermost first. Variables of the matrix that no block
+ * binds are free, and treated as existentially quantified in front of the prefix, as
+ * QDIMACS prescribes.
+ * */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quantifier {
+    Exists,
+    Forall,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub quantifier: Quantifier,
+    pub vars: Vec<String>,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Qbf {
+    pub prefix: Vec<Block>,
+    pub matrix: Clauses,
+}
+
+
+/* How the winner of the game on the prefix plays the outermost block. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Strategy {
+    /* The outermost block belongs to the winner, its Skolem or Herbrand functions have no
+     * arguments and are just this assignment. */
+    Move(Model),
+    /* The outermost block belongs to the loser. The winner's functions for the second block
+     * are a decision list of cubes, partial assignments of the outermost block, each with
+     * the assignment of the second block that wins against every move agreeing with it.
+     * Every move agrees with some cube. Without a second block every move loses outright
+     * and the single response is empty. */
+    Responses(Vec<(Model, Model)>),
+}
+
+
+impl Strategy {
+    /* The winner's assignment of the block after `outer`, an assignment of the outermost
+     * block: the move itself, or the response of the first cube `outer` agrees with. */
+    pub fn respond(&self, outer: &Model) -> Option<&Model> {
+        match self {
+            Strategy::Move(values) => Some(values),
+            Strategy::Responses(responses) => responses
+                .iter()
+                .find(|(cube, _)| cube.iter().all(|(v, b)| outer.get(v) == Some(b)))
+                .map(|(_, response)| response),
+        }
+    }
+}
+
+
+/* Certificate of the value of a QBF: Skolem functions of the existential player when it is
+ * true, Herbrand functions of the universal player when it is false. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Certificate {
+    Skolem(Strategy),
+    Herbrand(Strategy),
+}
+
+
+impl Certificate {
+    pub fn value(&self) -> bool {
+        matches!(self, Certificate::Skolem(_))
+    }
+
+    pub fn strategy(&self) -> &Strategy {
+        match self {
+            Certificate::Skolem(s) | Certificate::Herbrand(s) => s,
+        }
+    }
+}
+
+
+impl Qbf {
+    pub fn from_qdimacs<R: BufRead>(reader: R) -> io::Result<Self> {
+        qdimacs::parse(reader)
+    }
+
+    /* The prefix with free variables bound in front, empty blocks dropped and neighbouring
+     * blocks of the same quantifier merged. */
+    pub fn normalized_prefix(&self) -> Vec<Block> {
+        let bound = self.prefix.iter().flat_map(|b| b.vars.iter()).collect::<HashSet<_>>();
+        let mut free = self.matrix
+            .iter()
+            .flat_map(|c| c.iter().map(|l| l.var_name()))
+            .filter(|v| !bound.contains(&v.to_string()))
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>();
+        free.sort();
+        free.dedup();
+
+        let mut blocks: Vec<Block> = Vec::new();
+        let free = Block { quantifier: Quantifier::Exists, vars: free };
+        for block in std::iter::once(&free).chain(self.prefix.iter()) {
+            if block.vars.is_empty() {
+                continue;
+            }
+            match blocks.last_mut() {
+                Some(last) if last.quantifier == block.quantifier => last.vars.extend(block.vars.iter().cloned()),
+                _ => blocks.push(block.clone()),
+            }
+        }
+        blocks
+    }
+
+    pub fn solve(&self) -> Certificate {
+        solve(self)
+    }
+
+    pub fn is_true(&self) -> bool {
+        solve(self).value()
+    }
+}
+
+
+/* A block of the game, its variables being inputs of the graph. */
+struct Level {
+    quantifier: Quantifier,
+    vars: Vec<AigLit>,
+}
+
+
+/* Decide a QBF by recursive abstraction refinement (RAReQS, Janota et al.), a CEGAR
+ * expansion of the outermost quantifier.
+ *
+ * The formula is a game: the player owning the outermost block moves first and the
+ * existential player wins iff the matrix ends up true. The mover guesses a move from an
+ * abstraction, the opponent answers by solving the rest of the formula under that move,
+ * and every winning answer is expanded into the abstraction: the second block is fixed to
+ * the answer and the inner blocks get fresh copies. An existential mover must beat all
+ * answers seen, so the copies are conjoined, a universal mover disjoins them. The
+ * abstraction has one alternation less, so it is solved the same way and a single block
+ * is a SAT call. Every level keeps its solver and abstraction between the moves it is
+ * asked for, the outer variables being fixed by assumptions.
+ * */
+pub fn solve(qbf: &Qbf) -> Certificate {
+    let blocks = qbf.normalized_prefix();
+    let mut aig = Aig::new();
+    let mut inputs = HashMap::new();
+    let levels = blocks
+        .iter()
+        .map(|b| Level {
+            quantifier: b.quantifier,
+            vars: b.vars.iter().map(|v| *inputs.entry(v.clone()).or_insert_with(|| aig.input())).collect(),
+        })
+        .collect::<Vec<_>>();
+    let matrix = encode(&mut aig, &inputs, &qbf.matrix);
+
+    let Some(first) = levels.first() else {
+        let strategy = Strategy::Move(Model::new());
+        return if matrix == AigLit::TRUE { Certificate::Skolem(strategy) } else { Certificate::Herbrand(strategy) };
+    };
+    let goal = if first.quantifier == Quantifier::Exists { matrix } else { matrix.negate() };
+    let mut game = Game::new(&aig, &levels, goal);
+    let wins = game.play(&mut aig, &[]);
+    let named = |block: &Block, values: &[bool]| block.vars.iter().cloned().zip(values.iter().cloned()).collect::<Model>();
+    let strategy = match wins {
+        Some(ref values) => Strategy::Move(named(&blocks[0], values)),
+        None if levels.len() == 1 => Strategy::Responses(vec![(Model::new(), Model::new())]),
+        None => Strategy::Responses(decision_list(&mut aig, &levels, matrix, &game.answers)
+            .into_iter()
+            .map(|(cube, answer)| {
+                let cube = cube.into_iter().map(|(i, b)| (blocks[0].vars[i].clone(), b)).collect();
+                (cube, named(&blocks[1], &game.answers[answer]))
+            })
+            .collect()),
+    };
+    if wins.is_some() == (first.quantifier == Quantifier::Exists) {
+        Certificate::Skolem(strategy)
+    } else {
+        Certificate::Herbrand(strategy)
+    }
+}
+
+
+/* The winner's decision list against the loser owning the outermost of `levels`, built
+ * from the answers that refuted the loser's moves. An answer is taken for every move no
+ * cube covers yet, the first that beats it: the answers refute every move, the abstraction
+ * of the loser being false. The move is then generalized to a cube by dropping every value
+ * the answer beats the move without. Cubes are values of variables of the first level by
+ * position, answers indices into `answers`.
+ * */
+fn decision_list(aig: &mut Aig, levels: &[Level], matrix: AigLit, answers: &[Vec<bool>]) -> Vec<(Vec<(usize, bool)>, usize)> {
+    let mut uncovered = Solver::new();
+    let vars = levels[0].vars.iter().map(|_| uncovered.new_var()).collect::<Vec<_>>();
+    let mut list = Vec::new();
+    while uncovered.solve() {
+        let mut cube = vars.iter().map(|v| uncovered.value(v.pos()).unwrap_or(false)).enumerate().collect::<Vec<_>>();
+        let answer = answers
+            .iter()
+            .position(|answer| beats(aig, levels, matrix, &cube, answer))
+            .expect("an answer refuting the move");
+        for i in 0..vars.len() {
+            let dropped = cube.iter().cloned().filter(|&(j, _)| j != i).collect::<Vec<_>>();
+            if dropped.len() < cube.len() && beats(aig, levels, matrix, &dropped, &answers[answer]) {
+                cube = dropped;
+            }
+        }
+        let blocking = cube.iter().map(|&(i, b)| Lit::new(vars[i], b)).collect::<Vec<_>>();
+        list.push((cube, answer));
+        if !uncovered.add_clause(&blocking) {
+            break;
+        }
+    }
+    list
+}
+
+
+/* Whether `answer`, values of the second of `levels`, wins against every move of the first
+ * level agreeing with `cube`. */
+fn beats(aig: &mut Aig, levels: &[Level], matrix: AigLit, cube: &[(usize, bool)], answer: &[bool]) -> bool {
+    let loser = levels[0].quantifier;
+    let mut fixed = cube.iter().map(|&(i, b)| (levels[0].vars[i].node(), constant(b))).collect::<HashMap<_, _>>();
+    fixed.extend(levels[1].vars.iter().map(|v| v.node()).zip(answer.iter().map(|&b| constant(b))));
+    // the free variables of the first level join the third, which is the loser's as well.
+    let free = levels[0].vars.iter().enumerate().filter(|(i, _)| !cube.iter().any(|(j, _)| i == j)).map(|(_, &v)| v);
+    let rest = std::iter::once(Level { quantifier: loser, vars: free.chain(levels.get(2).into_iter().flat_map(|l| l.vars.iter().cloned())).collect() })
+        .chain(levels.iter().skip(3).map(|l| Level { quantifier: l.quantifier, vars: l.vars.clone() }))
+        .collect();
+    let matrix = aig.compose(matrix, &fixed);
+    value(aig, rest, matrix) != (loser == Quantifier::Exists)
+}
+
+
+/* The value of the game on `levels` that the existential player wins iff `matrix` is true.
+ * Empty levels are dropped and neighbouring levels of the same quantifier merged. */
+fn value(aig: &mut Aig, levels: Vec<Level>, matrix: AigLit) -> bool {
+    if matrix == AigLit::TRUE || matrix == AigLit::FALSE {
+        return matrix == AigLit::TRUE;
+    }
+    let mut merged: Vec<Level> = Vec::new();
+    for level in levels.into_iter().filter(|l| !l.vars.is_empty()) {
+        match merged.last_mut() {
+            Some(last) if last.quantifier == level.quantifier => last.vars.extend(level.vars),
+            _ => merged.push(level),
+        }
+    }
+    let exists = merged[0].quantifier == Quantifier::Exists;
+    let goal = if exists { matrix } else { matrix.negate() };
+    Game::new(aig, &merged, goal).play(aig, &[]).is_some() == exists
+}
+
+
+/* The matrix as a balanced conjunction of balanced disjunctions. */
+fn encode(aig: &mut Aig, inputs: &HashMap<String, AigLit>, matrix: &Clauses) -> AigLit {
+    fn balance(aig: &mut Aig, lits: &[AigLit], unit: AigLit, op: fn(&mut Aig, AigLit, AigLit) -> AigLit) -> AigLit {
+        match lits {
+            [] => unit,
+            [lit] => *lit,
+            _ => {
+                let (left, right) = lits.split_at(lits.len() / 2);
+                let left = balance(aig, left, unit, op);
+                let right = balance(aig, right, unit, op);
+                op(aig, left, right)
+            },
+        }
+    }
+    let clauses = matrix
+        .iter()
+        .map(|c| {
+            let lits = c
+                .iter()
+                .map(|l| if l.is_negated() { inputs[l.var_name()].negate() } else { inputs[l.var_name()] })
+                .collect::<Vec<_>>();
+            balance(aig, &lits, AigLit::FALSE, Aig::or)
+        })
+        .collect::<Vec<_>>();
+    balance(aig, &clauses, AigLit::TRUE, Aig::and)
+}
+
+
+/* The game of `levels`, kept across the calls asking it for a move so that its SAT solver,
+ * or its abstraction and the game of its opponent, are refined incrementally instead of
+ * being rebuilt for every move.
+ * */
+struct Game {
+    vars: Vec<AigLit>,
+    // the matrix from the point of view of the owner of `vars`, which wins iff it is true.
+    goal: AigLit,
+    kind: Kind,
+    // answers of the opponent that refuted a move, in order.
+    answers: Vec<Vec<bool>>,
+}
+
+
+enum Kind {
+    /* A single level, one SAT solver on the Tseitin encoding of the goal. */
+    Sat { solver: Box<Solver>, encoded: HashSet<usize> },
+    /* The abstraction, a game with one alternation less on the expansions of the goal so
+     * far, and the game of the opponent on the inner `levels`, built again once the goal
+     * grows. */
+    Expand { levels: Vec<Level>, abstraction: Box<Game>, counter: Option<Box<Game>> },
+}
+
+
+impl Game {
+    fn new(aig: &Aig, levels: &[Level], goal: AigLit) -> Game {
+        let first = &levels[0];
+        let kind = if levels.len() == 1 {
+            let mut kind = Kind::Sat { solver: Box::new(Solver::new()), encoded: HashSet::new() };
+            kind.encode(aig, goal);
+            kind
+        } else {
+            // the abstraction starts empty, so the first move is arbitrary.
+            let abstract_levels = std::iter::once(Level { quantifier: first.quantifier, vars: first.vars.clone() })
+                .chain(levels.iter().skip(3).map(|l| Level { quantifier: l.quantifier, vars: Vec::new() }))
+                .collect::<Vec<_>>();
+            Kind::Expand {
+                levels: levels[1..].iter().map(|l| Level { quantifier: l.quantifier, vars: l.vars.clone() }).collect(),
+                abstraction: Box::new(Game::new(aig, &abstract_levels, AigLit::TRUE)),
+                counter: None,
+            }
+        };
+        Game { vars: first.vars.clone(), goal, kind, answers: Vec::new() }
+    }
+
+    /* A winning move of the owner of the first level when the variables of the outer levels
+     * have the values of `outer`, or `None` with the refuting answers in `answers`. Moves are
+     * values of the variables of a level, in order.
+     * */
+    fn play(&mut self, aig: &mut Aig, outer: &[(AigLit, bool)]) -> Option<Vec<bool>> {
+        let (levels, abstraction, counter) = match &mut self.kind {
+            Kind::Sat { solver, .. } => return satisfy(aig, solver, outer, &self.vars),
+            Kind::Expand { levels, abstraction, counter } => (levels, abstraction, counter),
+        };
+        loop {
+            let guess = abstraction.play(aig, outer)?[..self.vars.len()].to_vec();
+            let mut fixed = outer.to_vec();
+            fixed.extend(self.vars.iter().cloned().zip(guess.iter().cloned()));
+            if counter.is_none() {
+                *counter = Some(Box::new(Game::new(aig, levels, self.goal.negate())));
+            }
+            let Some(answer) = counter.as_mut().expect("a counter game").play(aig, &fixed) else {
+                return Some(guess);
+            };
+
+            // expand the goal by the answer, the inner levels merge into the copy.
+            let mut copy: HashMap<usize, AigLit> = levels[0].vars.iter().map(|v| v.node()).zip(answer.iter().map(|&b| constant(b))).collect();
+            let mut fresh = vec![Vec::new(); levels.len() - 1];
+            for (i, level) in levels[1..].iter().enumerate() {
+                for var in level.vars.iter() {
+                    let input = aig.input();
+                    copy.insert(var.node(), input);
+                    fresh[i].push(input);
+                }
+            }
+            let expanded = aig.compose(self.goal, &copy);
+            abstraction.strengthen(aig, expanded, fresh);
+            self.answers.push(answer);
+        }
+    }
+
+    /* Conjoin `conjunct` to the goal, `vars[i]` being new variables of its level `i`. */
+    fn strengthen(&mut self, aig: &mut Aig, conjunct: AigLit, vars: Vec<Vec<AigLit>>) {
+        let mut vars = vars.into_iter();
+        self.extend(vars.next().unwrap_or_default());
+        self.goal = aig.and(self.goal, conjunct);
+        match &mut self.kind {
+            Kind::Sat { .. } => self.kind.encode(aig, conjunct),
+            Kind::Expand { levels, counter, .. } => {
+                for (level, vars) in levels.iter_mut().zip(vars) {
+                    level.vars.extend(vars);
+                }
+                *counter = None;
+            },
+        }
+    }
+
+    /* Add variables to the first level, which the goal does not depend on yet. */
+    fn extend(&mut self, vars: Vec<AigLit>) {
+        if let Kind::Expand { abstraction, .. } = &mut self.kind {
+            abstraction.extend(vars.clone());
+        }
+        self.vars.extend(vars);
+    }
+}
+
+
+impl Kind {
+    /* Make the solver of a single level satisfy `goal` as well. */
+    fn encode(&mut self, aig: &Aig, goal: AigLit) {
+        if let Kind::Sat { solver, encoded } = self {
+            solver.add_clauses(&aig.tseitin_from(&[goal], None, encoded));
+            solver.add(&std::iter::once(aig.literal(goal, None)).collect());
+        }
+    }
+}
+
+
+fn constant(value: bool) -> AigLit {
+    if value { AigLit::TRUE } else { AigLit::FALSE }
+}
+
+
+/* Values of `vars` in a model of the goal of `solver` with the `outer` variables fixed,
+ * `None` if there is none. */
+fn satisfy(aig: &Aig, solver: &mut Solver, outer: &[(AigLit, bool)], vars: &[AigLit]) -> Option<Vec<bool>> {
+    // variables outside the cone of the goal are not in the solver and do not matter.
+    let assumptions = outer
+        .iter()
+        .filter_map(|&(v, value)| solver.lookup(aig.literal(v, None).var_name()).map(|var| Lit::new(var, !value)))
+        .collect::<Vec<_>>();
+    if !solver.solve_with(&assumptions) {
+        return None;
+    }
+    Some(vars
+        .iter()
+        .map(|&v| {
+            let literal = aig.literal(v, None);
+            solver.lookup(literal.var_name()).and_then(|var| solver.value(var.pos())).unwrap_or(false)
+        })
+        .collect())
+}
@@ -0,0 +1,102 @@
+use crate::sat::clauses::*;
+use crate::sat::dimacs;
+use crate::sat::qbf::*;
+use std::collections::HashSet;
+use std::io;
+use std::io::BufRead;
+
+
+/* Reader for QDIMACS, DIMACS with a quantifier prefix between the header and the clauses:
+ *
+ *   p cnf 3 2
+ *   a 1 0
+ *   e 2 3 0
+ *   1 -2 0
+ *   -1 3 0
+ *
+ * Every `a` or `e` line is one non-empty block, outermost first, and quantifies each of its
+ * variables at most once over the whole prefix. Variables are indices up to the count of the
+ * header, and every block and clause is ended by `0`.
+ * */
+pub fn parse<R: BufRead>(reader: R) -> io::Result<Qbf> {
+    let mut prefix = Vec::new();
+    let mut matrix = Clauses::new();
+    let mut vars = None;
+    let mut quantified = HashSet::new();
+    let mut clause = None;
+    for line in reader.lines() {
+        let line = line?;
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match fields.first() {
+            None | Some(&"c") => continue,
+            Some(&"p") => {
+                match dimacs::header(&line) {
+                    Some((n, _)) if vars.is_none() => vars = Some(n),
+                    _ => return Err(invalid(format!("invalid QDIMACS header {:?}", line))),
+                }
+                continue;
+            },
+            Some(&"%") => break,
+            _ => {},
+        }
+        let Some(vars) = vars else {
+            return Err(invalid(format!("expected QDIMACS header, got {:?}", line)));
+        };
+
+        let quantifier = match fields[0] {
+            "e" => Some(Quantifier::Exists),
+            "a" => Some(Quantifier::Forall),
+            _ => None,
+        };
+        if let Some(quantifier) = quantifier {
+            if !matrix.is_empty() || clause.is_some() {
+                return Err(invalid(format!("quantifier block after the clauses {:?}", line)));
+            }
+            let Some((&"0", fields)) = fields[1..].split_last() else {
+                return Err(invalid(format!("quantifier block without a closing 0 {:?}", line)));
+            };
+            if fields.is_empty() {
+                return Err(invalid(format!("empty quantifier block {:?}", line)));
+            }
+            let mut block = Vec::new();
+            for field in fields.iter() {
+                let var = variable(field, vars)?;
+                if !quantified.insert(var.clone()) {
+                    return Err(invalid(format!("variable {} quantified twice", var)));
+                }
+                block.push(var);
+            }
+            prefix.push(Block { quantifier, vars: block });
+            continue;
+        }
+
+        // clauses may span several lines, only `0` ends one.
+        for field in fields.iter() {
+            if *field == "0" {
+                matrix.push(clause.take().unwrap_or_default());
+                continue;
+            }
+            let literal = dimacs::literal(field);
+            variable(literal.var_name(), vars)?;
+            clause.get_or_insert_with(Clause::new).insert(literal);
+        }
+    }
+    if clause.is_some() {
+        return Err(invalid("last clause without a closing 0".to_string()));
+    }
+    Ok(Qbf { prefix, matrix })
+}
+
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+
+/* A variable index between 1 and the variable count of the header. */
+fn variable(field: &str, vars: usize) -> io::Result<String> {
+    match field.parse::<usize>() {
+        Ok(var) if 1 <= var && var <= vars => Ok(field.to_string()),
+        _ => Err(invalid(format!("invalid variable {:?}, expected 1 to {}", field, vars))),
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+
+use crate::sat::brute;
+use crate::sat::cdcl::Solver;
+use crate::sat::clauses::*;
+use crate::sat::features::Features;
+use crate::sat::fragment::{self, Fragment};
+use crate::sat::symmetry;
+
+
+/* Automatic engine selection: extract the features of an instance and let a `Selector`
+ * pick the engine and configuration to run on it.
+ *
+ * `Rules` is a small hand written decision list. A learned model plugs in through
+ * `Learned`, which takes a cost prediction for each candidate engine from the feature
+ * vector and runs the cheapest, the way SATzilla uses empirical hardness models.
+ * */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Engine {
+    /* The linear time algorithms for 2-CNF and renamable Horn clauses. */
+    Fragment,
+    BruteForce,
+    Cdcl(Config),
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Config {
+    pub inprocessing: bool,
+    /* Add lex-leader predicates for the symmetries of the clauses before solving. */
+    pub symmetry_breaking: bool,
+}
+
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { inprocessing: true, symmetry_breaking: false }
+    }
+}
+
+
+/* Every engine and configuration a selector may choose. */
+pub const ENGINES: [Engine; 6] = [
+    Engine::Fragment,
+    Engine::BruteForce,
+    Engine::Cdcl(Config { inprocessing: true, symmetry_breaking: false }),
+    Engine::Cdcl(Config { inprocessing: false, symmetry_breaking: false }),
+    Engine::Cdcl(Config { inprocessing: true, symmetry_breaking: true }),
+    Engine::Cdcl(Config { inprocessing: false, symmetry_breaking: true }),
+];
+
+
+// largest instance the rules hand to brute force.
+const BRUTE_VARS: usize = 12;
+
+
+impl Engine {
+    /* A model of `clauses` over their variables, `None` if they are unsatisfiable. Engines
+     * that do not apply fall back to CDCL: `Fragment` outside the fragments and
+     * `BruteForce` beyond `brute::MAX_VARS` variables.
+     * */
+    pub fn solve(&self, clauses: &Clauses) -> Option<Model> {
+        match *self {
+            Engine::Fragment => match fragment::solve(clauses) {
+                Some(model) => model,
+                None => Engine::Cdcl(Config::default()).solve(clauses),
+            },
+            Engine::BruteForce if count_vars(clauses) <= brute::MAX_VARS => brute::model(clauses),
+            Engine::BruteForce => Engine::Cdcl(Config::default()).solve(clauses),
+            Engine::Cdcl(config) => {
+                let broken;
+                let input = if config.symmetry_breaking {
+                    broken = symmetry::break_symmetries(clauses);
+                    &broken
+                } else {
+                    clauses
+                };
+                let mut solver = Solver::new();
+                solver.set_inprocessing(config.inprocessing);
+                solver.add_clauses(input);
+                if !solver.solve() {
+                    return None;
+                }
+                // the predicates' auxiliary variables are left out.
+                let mut model = solver.model();
+                if config.symmetry_breaking {
+                    let vars = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name())).collect::<HashSet<_>>();
+                    model.retain(|v, _| vars.contains(v.as_str()));
+                }
+                Some(model)
+            },
+        }
+    }
+}
+
+
+fn count_vars(clauses: &Clauses) -> usize {
+    let mut names = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name())).collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+    names.len()
+}
+
+
+pub trait Selector {
+    fn select(&self, features: &Features) -> Engine;
+}
+
+
+/* The default decision list.
+ *
+ *  - instances in a polynomial fragment go to the fragment algorithms.
+ *  - tiny instances are enumerated.
+ *  - instances the CDCL probe already decided, or local search satisfied, are easy or
+ *    random-like, and plain CDCL solves them without inprocessing overhead.
+ *  - uniform variable degrees hint at a highly symmetric, crafted instance such as the
+ *    pigeonhole principle, which symmetry breaking helps most.
+ *  - anything else is taken to be structured and gets inprocessing.
+ * */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rules;
+
+
+impl Selector for Rules {
+    fn select(&self, features: &Features) -> Engine {
+        if features.fragment != Fragment::General {
+            return Engine::Fragment;
+        }
+        if features.vars <= BRUTE_VARS {
+            return Engine::BruteForce;
+        }
+        let plain = Config { inprocessing: false, symmetry_breaking: false };
+        if features.search.result.is_some() || features.local.solved {
+            return Engine::Cdcl(plain);
+        }
+        if features.var_degree.variation() < 0.05 {
+            return Engine::Cdcl(Config { symmetry_breaking: true, ..plain });
+        }
+        Engine::Cdcl(Config::default())
+    }
+}
+
+
+/* A learned model predicting the cost of each of `candidates` from `Features::vector`,
+ * for example a regression model of the log runtime. The cheapest candidate is selected,
+ * the first of them on ties.
+ * */
+pub struct Learned<M: Fn(Engine, &[f64]) -> f64> {
+    pub candidates: Vec<Engine>,
+    pub model: M,
+}
+
+
+impl<M: Fn(Engine, &[f64]) -> f64> Learned<M> {
+    pub fn new(model: M) -> Self {
+        Learned { candidates: ENGINES.to_vec(), model }
+    }
+}
+
+
+impl<M: Fn(Engine, &[f64]) -> f64> Selector for Learned<M> {
+    fn select(&self, features: &Features) -> Engine {
+        let vector = features.vector();
+        let mut best = None;
+        for &engine in self.candidates.iter() {
+            let cost = (self.model)(engine, &vector);
+            if best.is_none_or(|(least, _)| cost < least) {
+                best = Some((cost, engine));
+            }
+        }
+        best.expect("no candidate engines").1
+    }
+}
+
+
+/* Extract the features of `clauses`, select an engine and run it. */
+pub fn solve(clauses: &Clauses, selector: &dyn Selector) -> (Engine, Option<Model>) {
+    let engine = selector.select(&Features::extract(clauses));
+    (engine, engine.solve(clauses))
+}
+
+
+pub fn satisfiable_auto(clauses: Clauses) -> bool {
+    solve(&clauses, &Rules).1.is_some()
+}
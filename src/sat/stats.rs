@@ -0,0 +1,169 @@
+use crate::sat::clauses::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+use std::time::Duration;
+
+
+/* Coarse grained phases of a search. Engines record the wall time they spend in
+ * each phase into `Stats`, so we can see where the time actually goes.
+ * */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    UnitPropagation,
+    PureLiteral,
+    Resolution,
+    Propagation,
+    Analysis,
+    ReduceDb,
+    Inprocessing,
+}
+
+
+/* Counters collected by a solver run.
+ * Not every engine fills every field, e.g `satisfiable_dp` never makes decisions
+ * and never learns clauses.
+ * */
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub decisions: u64,
+    pub propagations: u64,
+    pub conflicts: u64,
+    pub restarts: u64,
+    pub pure_literals: u64,
+    pub eliminated_vars: u64,
+    pub resolvents: u64,
+    pub inprocessings: u64,
+    pub substituted_vars: u64,
+    pub vivified_clauses: u64,
+    pub reduced_binaries: u64,
+    // histogram of learned clause sizes, size -> number of clauses learned with that size.
+    pub learned_sizes: BTreeMap<usize, u64>,
+    pub phase_times: HashMap<Phase, Duration>,
+}
+
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats::default()
+    }
+
+    pub fn learn(&mut self, size: usize) {
+        *self.learned_sizes.entry(size).or_insert(0) += 1;
+    }
+
+    pub fn learned(&self) -> u64 {
+        self.learned_sizes.values().sum()
+    }
+
+    pub fn mean_learned_size(&self) -> f64 {
+        let n = self.learned();
+        if n == 0 {
+            return 0.0;
+        }
+        let total: u64 = self.learned_sizes.iter().map(|(size, count)| *size as u64 * count).sum();
+        total as f64 / n as f64
+    }
+
+    pub fn record(&mut self, phase: Phase, elapsed: Duration) {
+        *self.phase_times.entry(phase).or_default() += elapsed;
+    }
+
+    pub fn time(&self, phase: Phase) -> Duration {
+        self.phase_times.get(&phase).cloned().unwrap_or_default()
+    }
+}
+
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "decisions       {}", self.decisions)?;
+        writeln!(f, "propagations    {}", self.propagations)?;
+        writeln!(f, "conflicts       {}", self.conflicts)?;
+        writeln!(f, "restarts        {}", self.restarts)?;
+        writeln!(f, "pure literals   {}", self.pure_literals)?;
+        writeln!(f, "eliminated vars {}", self.eliminated_vars)?;
+        writeln!(f, "resolvents      {}", self.resolvents)?;
+        writeln!(f, "inprocessings   {}", self.inprocessings)?;
+        writeln!(f, "substituted     {}", self.substituted_vars)?;
+        writeln!(f, "vivified        {}", self.vivified_clauses)?;
+        writeln!(f, "reduced binary  {}", self.reduced_binaries)?;
+        writeln!(f, "learned         {} (mean size {:.2})", self.learned(), self.mean_learned_size())?;
+        let mut phases = self.phase_times.iter().collect::<Vec<_>>();
+        phases.sort();
+        for (phase, time) in phases {
+            writeln!(f, "{:<16}{:?}", format!("{:?}", phase), time)?;
+        }
+        Ok(())
+    }
+}
+
+
+/* A single step of the search, as reported to an `EventSink`. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Decision { literal: Literal, level: usize },
+    Propagation { literal: Literal, level: usize },
+    Conflict { level: usize },
+    Restart,
+    Learned { size: usize },
+    PureLiteral { literal: Literal },
+    Eliminated { var: String, resolvents: usize },
+}
+
+
+impl Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Decision { literal, level } => write!(f, "{}decide {}", "  ".repeat(*level), literal),
+            Event::Propagation { literal, level } => write!(f, "{}propagate {}", "  ".repeat(*level), literal),
+            Event::Conflict { level } => write!(f, "{}conflict", "  ".repeat(*level)),
+            Event::Restart => write!(f, "restart"),
+            Event::Learned { size } => write!(f, "learned clause of size {}", size),
+            Event::PureLiteral { literal } => write!(f, "pure {}", literal),
+            Event::Eliminated { var, resolvents } => write!(f, "eliminate {} ({} resolvents)", var, resolvents),
+        }
+    }
+}
+
+
+/* Receives search events from an engine. Use `()` when nothing should be traced,
+ * `Recorder` to keep the events around and `Printer` to print them as they happen.
+ * */
+pub trait EventSink {
+    fn event(&mut self, event: &Event);
+}
+
+
+impl EventSink for () {
+    fn event(&mut self, _: &Event) {}
+}
+
+
+#[derive(Debug, Clone, Default)]
+pub struct Recorder(pub Vec<Event>);
+
+
+impl EventSink for Recorder {
+    fn event(&mut self, event: &Event) {
+        self.0.push(event.clone());
+    }
+}
+
+
+pub struct Printer<W: Write>(pub W);
+
+
+impl Printer<io::Stdout> {
+    pub fn stdout() -> Self {
+        Printer(io::stdout())
+    }
+}
+
+
+impl<W: Write> EventSink for Printer<W> {
+    fn event(&mut self, event: &Event) {
+        // tracing must never abort the search, so write errors are dropped.
+        let _ = writeln!(self.0, "{}", event);
+    }
+}
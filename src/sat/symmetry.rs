@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::sat::clauses::*;
+
+
+/* Symmetry detection and static symmetry breaking.
+ *
+ * A symmetry is a permutation of the literals, commuting with negation, that maps the
+ * clauses onto themselves. Symmetries are automorphisms of a coloured graph with one
+ * vertex per literal and one per clause: a literal is joined to its negation and to the
+ * clauses it occurs in, and clause vertices get a colour of their own.
+ *
+ * Generators of the automorphism group are found the way nauty and saucy do it. Colour
+ * refinement splits the vertices until neighbours no longer tell members of a cell apart,
+ * then a vertex of a non-trivial cell is individualized and the partition refined again,
+ * until every vertex is alone. Two leaves of this search tree line up vertex by vertex,
+ * and that mapping is an automorphism if it preserves the edges.
+ *
+ * A lex-leader predicate for a generator σ keeps the assignments that are no larger than
+ * their image under σ, in one fixed variable order for all generators. The least assignment
+ * in each orbit satisfies all of them, so breaking preserves satisfiability.
+ * */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symmetry {
+    /* Image of the positive literal of every variable moved, the image of its negation
+     * is the negated image. */
+    pub images: BTreeMap<String, Literal>,
+}
+
+
+impl Symmetry {
+    pub fn apply(&self, literal: &Literal) -> Literal {
+        match self.images.get(literal.var_name()) {
+            Some(image) if literal.is_negated() => image.negate(),
+            Some(image) => image.clone(),
+            None => literal.clone(),
+        }
+    }
+
+    pub fn apply_clause(&self, clause: &Clause) -> Clause {
+        clause.iter().map(|l| self.apply(l)).collect()
+    }
+
+    /* True if the symmetry maps `clauses` onto themselves. */
+    pub fn preserves(&self, clauses: &Clauses) -> bool {
+        let normal = |c: &Clause| {
+            let mut lits = c.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+            lits.sort();
+            lits
+        };
+        let mut before = clauses.iter().map(normal).collect::<Vec<_>>();
+        let mut after = clauses.iter().map(|c| normal(&self.apply_clause(c))).collect::<Vec<_>>();
+        before.sort();
+        after.sort();
+        before == after
+    }
+}
+
+
+// nodes of the search tree explored to find one automorphism before giving up.
+const SEARCH_BUDGET: usize = 10_000;
+
+
+// colour, or cell index, of every vertex. Cells are numbered in an order only depending on
+// the structure of the graph, so refining isomorphic inputs gives isomorphic outputs.
+type Colouring = Vec<usize>;
+
+
+struct Graph {
+    adjacency: Vec<Vec<usize>>,
+}
+
+
+impl Graph {
+    /* Split cells by the colours of the neighbours until it changes nothing. */
+    fn refine(&self, mut colours: Colouring) -> Colouring {
+        let mut cells = count(&colours);
+        loop {
+            let signatures = (0..colours.len())
+                .map(|v| {
+                    let mut around = self.adjacency[v].iter().map(|&u| colours[u]).collect::<Vec<_>>();
+                    around.sort_unstable();
+                    (colours[v], around)
+                })
+                .collect::<Vec<_>>();
+            let refined = rank(&signatures);
+            let refined_cells = count(&refined);
+            colours = refined;
+            if refined_cells == cells {
+                return colours;
+            }
+            cells = refined_cells;
+        }
+    }
+
+    /* Give `v` a cell of its own, just before the rest of its cell, and refine. */
+    fn individualize(&self, colours: &Colouring, v: usize) -> Colouring {
+        let keys = (0..colours.len()).map(|u| (colours[u], u != v)).collect::<Vec<_>>();
+        self.refine(rank(&keys))
+    }
+
+    fn is_automorphism(&self, perm: &[usize]) -> bool {
+        (0..perm.len()).all(|v| {
+            let mut mapped = self.adjacency[v].iter().map(|&u| perm[u]).collect::<Vec<_>>();
+            mapped.sort_unstable();
+            mapped == self.adjacency[perm[v]]
+        })
+    }
+}
+
+
+// dense ranks of the keys, equal keys sharing a rank.
+fn rank<K: Ord + Clone>(keys: &[K]) -> Colouring {
+    let mut sorted = keys.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    keys.iter().map(|k| sorted.binary_search(k).unwrap()).collect()
+}
+
+
+fn count(colours: &Colouring) -> usize {
+    colours.iter().max().map_or(0, |&c| c + 1)
+}
+
+
+// sizes of the cells in order, equal for nodes that may lead to the same leaves.
+fn shape(colours: &Colouring) -> Vec<usize> {
+    let mut sizes = vec![0; count(colours)];
+    for &c in colours.iter() {
+        sizes[c] += 1;
+    }
+    sizes
+}
+
+
+// first cell with more than one vertex, with its vertices.
+fn target(colours: &Colouring) -> Option<Vec<usize>> {
+    let sizes = shape(colours);
+    let cell = sizes.iter().position(|&s| s > 1)?;
+    Some((0..colours.len()).filter(|&v| colours[v] == cell).collect())
+}
+
+
+struct Orbits(Vec<usize>);
+
+
+impl Orbits {
+    fn find(&mut self, v: usize) -> usize {
+        let mut root = v;
+        while self.0[root] != root {
+            root = self.0[root];
+        }
+        let mut v = v;
+        while self.0[v] != root {
+            let next = self.0[v];
+            self.0[v] = root;
+            v = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.0[a.max(b)] = a.min(b);
+    }
+}
+
+
+struct Search<'a> {
+    graph: &'a Graph,
+    // shapes of the nodes along the first path, the first leaf last.
+    shapes: Vec<Vec<usize>>,
+    // vertex at every position of the first leaf.
+    first: Vec<usize>,
+    budget: usize,
+}
+
+
+impl<'a> Search<'a> {
+    /* An automorphism from the first leaf to a leaf below `colours`, a node at `depth`. */
+    fn automorphism(&mut self, colours: Colouring, depth: usize) -> Option<Vec<usize>> {
+        if self.budget == 0 || shape(&colours) != self.shapes[depth] {
+            return None;
+        }
+        self.budget -= 1;
+        let Some(cell) = target(&colours) else {
+            let mut perm = vec![0; colours.len()];
+            for (position, &v) in self.first.iter().enumerate() {
+                perm[v] = colours.iter().position(|&c| c == position).unwrap();
+            }
+            return if self.graph.is_automorphism(&perm) { Some(perm) } else { None };
+        };
+        for v in cell {
+            let child = self.graph.individualize(&colours, v);
+            if let Some(perm) = self.automorphism(child, depth + 1) {
+                return Some(perm);
+            }
+        }
+        None
+    }
+}
+
+
+/* Generators of the symmetry group of `clauses`, or of a subgroup if the search ran out of
+ * budget somewhere. Every returned symmetry is checked to preserve the clauses.
+ * */
+pub fn generators(clauses: &Clauses) -> Vec<Symmetry> {
+    let mut names = clauses.iter().flat_map(|c| c.iter().map(|l| l.var_name().to_string())).collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    let index = names.iter().enumerate().map(|(i, n)| (n.clone(), i)).collect::<HashMap<_, _>>();
+    let vertex = |l: &Literal| 2 * index[l.var_name()] + l.is_negated() as usize;
+
+    // literal 2v and 2v + 1 are v and ¬v, clause i is vertex 2n + i.
+    let literals = 2 * names.len();
+    let mut adjacency = vec![Vec::new(); literals + clauses.len()];
+    for v in 0..names.len() {
+        adjacency[2 * v].push(2 * v + 1);
+        adjacency[2 * v + 1].push(2 * v);
+    }
+    for (i, clause) in clauses.iter().enumerate() {
+        for literal in clause.iter() {
+            adjacency[vertex(literal)].push(literals + i);
+            adjacency[literals + i].push(vertex(literal));
+        }
+    }
+    for around in adjacency.iter_mut() {
+        around.sort_unstable();
+    }
+    let graph = Graph { adjacency };
+
+    let initial = (0..graph.adjacency.len()).map(|v| (v >= literals) as usize).collect::<Vec<_>>();
+    let mut colours = graph.refine(initial);
+    let mut path = Vec::new();
+    let mut shapes = vec![shape(&colours)];
+    while let Some(cell) = target(&colours) {
+        let next = graph.individualize(&colours, cell[0]);
+        path.push((colours, cell));
+        colours = next;
+        shapes.push(shape(&colours));
+    }
+    let mut first = vec![0; colours.len()];
+    for (v, &c) in colours.iter().enumerate() {
+        first[c] = v;
+    }
+
+    // deepest level first, so all generators found so far fix the vertices individualized
+    // above the current level, and their orbits tell which branches are already covered.
+    let mut search = Search { graph: &graph, shapes, first, budget: 0 };
+    let mut orbits = Orbits((0..graph.adjacency.len()).collect());
+    let mut perms = Vec::new();
+    for (depth, (colours, cell)) in path.iter().enumerate().rev() {
+        for &w in cell[1..].iter() {
+            if orbits.find(w) == orbits.find(cell[0]) {
+                continue;
+            }
+            search.budget = SEARCH_BUDGET;
+            if let Some(perm) = search.automorphism(graph.individualize(colours, w), depth + 1) {
+                for (v, &image) in perm.iter().enumerate() {
+                    orbits.union(v, image);
+                }
+                perms.push(perm);
+            }
+        }
+    }
+
+    let literal = |vertex: usize| {
+        let name = names[vertex / 2].clone();
+        if vertex % 2 == 1 { Literal::neg(name) } else { Literal::pos(name) }
+    };
+    perms
+        .into_iter()
+        .map(|perm| Symmetry {
+            images: (0..names.len())
+                .filter(|&v| perm[2 * v] != 2 * v)
+                .map(|v| (names[v].clone(), literal(perm[2 * v])))
+                .collect(),
+        })
+        .collect()
+}
+
+
+/* Lex-leader predicates for `generators` of the symmetries of `clauses`, comparing
+ * assignments in the order of the variable names. With `e_i` standing for "the first i
+ * variables equal their images", the clauses for generator g are `e_{i-1} → (x_i ≤ σ(x_i))`
+ * and `e_{i-1} ∧ (x_i = σ(x_i)) → e_i`, the `e_i` being fresh variables `_sb{g}_{i}`, with
+ * more leading underscores if a variable of `clauses` already starts with `_sb`.
+ * */
+pub fn lex_leader(clauses: &Clauses, generators: &[Symmetry]) -> Clauses {
+    let mut prefix = "_sb".to_string();
+    while clauses.iter().any(|c| c.iter().any(|l| l.var_name().starts_with(&prefix))) {
+        prefix.insert(0, '_');
+    }
+    let mut predicates = Clauses::new();
+    for (g, symmetry) in generators.iter().enumerate() {
+        let equal = |i: usize| Literal::pos(format!("{}{}_{}", prefix, g, i));
+        let moved = symmetry.images.iter().collect::<Vec<_>>();
+        for (i, &(name, image)) in moved.iter().enumerate() {
+            let x = Literal::pos(name.to_string());
+            // the prefix being equal is given for the first variable.
+            let prefix = if i == 0 { vec![] } else { vec![equal(i - 1).negate()] };
+            let clause = |lits: Vec<Literal>| prefix.iter().cloned().chain(lits).collect::<Clause>();
+            predicates.push(clause(vec![x.negate(), image.clone()]));
+            if i + 1 < moved.len() {
+                predicates.push(clause(vec![x.negate(), image.negate(), equal(i)]));
+                predicates.push(clause(vec![x.clone(), image.clone(), equal(i)]));
+            }
+        }
+    }
+    predicates.iter().filter(|c| !c.is_tautology()).cloned().collect()
+}
+
+
+/* `clauses` with lex-leader predicates for the generators of their symmetry group, which
+ * are satisfiable iff `clauses` are. */
+pub fn break_symmetries(clauses: &Clauses) -> Clauses {
+    let mut broken = clauses.clone();
+    broken.extend(lex_leader(clauses, &generators(clauses)).iter().cloned());
+    broken
+}
@@ -0,0 +1,72 @@
+extern crate theorem_prover;
+use theorem_prover::sat::aig::{Aig, AigLit};
+use theorem_prover::sat::bmc;
+use theorem_prover::sat::cdcl::Solver;
+use theorem_prover::sat::clauses::{Clause, Literal};
+use std::fs::File;
+
+
+fn load(path: &str) -> Aig {
+    Aig::from_aiger(File::open(path).unwrap()).expect("Failed to parse")
+}
+
+
+#[test]
+fn test_structural_hashing() {
+    let mut aig = Aig::new();
+    let a = aig.input();
+    let b = aig.input();
+    let x = aig.and(a, b);
+    assert_eq!(aig.and(b, a), x);
+    assert_eq!(aig.and(a, a.negate()), AigLit::FALSE);
+    assert_eq!(aig.and(a, AigLit::TRUE), a);
+    assert_eq!(aig.xor(a, b), aig.xor(a, b));
+    assert_eq!(aig.num_ands(), 4);
+
+    // x ∧ ¬a is unsatisfiable under the Tseitin encoding.
+    let mut clauses = aig.tseitin(&[x], None);
+    clauses.push(std::iter::once(aig.literal(x, None)).collect::<Clause>());
+    clauses.push(std::iter::once(Literal::neg(aig.var_name(a.node(), None))).collect::<Clause>());
+    assert!(!Solver::from_clauses(&clauses).solve());
+}
+
+
+#[test]
+fn test_aiger_ascii_and_binary() {
+    let ascii = load("tests/fixtures/aiger/counter.aag");
+    let binary = load("tests/fixtures/aiger/counter.aig");
+    for aig in [ascii, binary].iter() {
+        assert_eq!(aig.latches.len(), 2);
+        assert_eq!(aig.bad.len(), 1);
+        assert_eq!(aig.num_ands(), 4);
+        assert_eq!(aig.symbols.get("b0"), Some(&"both".to_string()));
+    }
+    assert!(Aig::from_aiger("aag 1 0 0 1 1\n2\n2 2 3\n".as_bytes()).is_err());
+    assert!(Aig::from_aiger("aag 2 0 0 1 2\n2\n2 4 1\n4 2 1\n".as_bytes()).is_err());
+    assert!(Aig::from_aiger("aag 1 1 0 0 0\n2\ni0 x\n".as_bytes()).is_ok());
+    assert!(Aig::from_aiger("aag 1 1 0 0 0\n2\né0 x\n".as_bytes()).is_err());
+    // a binary and gate whose delta runs past 64 bits.
+    let mut overflow = b"aig 1 0 0 0 1\n".to_vec();
+    overflow.extend([0x80; 12].iter().chain([0x01].iter()));
+    assert_eq!(Aig::from_aiger(&overflow[..]).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+
+#[test]
+fn test_bmc() {
+    let counter = load("tests/fixtures/aiger/counter.aag");
+    assert_eq!(bmc::bmc(&counter, 2), None);
+    let trace = bmc::bmc(&counter, 10).expect("counterexample");
+    assert_eq!(trace.len(), 4);
+    assert_eq!(trace.init, vec![false, false]);
+    assert!(trace.replay(&counter));
+    assert_eq!(trace.to_string(), "1\nb0\n00\n\n\n\n\n.\n");
+
+    let mut guarded = load("tests/fixtures/aiger/guarded.aag");
+    assert_eq!(bmc::bmc(&guarded, 5), None);
+    guarded.constraints.clear();
+    let trace = bmc::bmc(&guarded, 5).expect("counterexample");
+    assert_eq!(trace.len(), 2);
+    assert!(trace.inputs[0][1]);
+    assert!(trace.replay(&guarded));
+}
@@ -0,0 +1,4 @@
+% A is a subset of B, and B of C.
+axiom a_subset_b: forall x . A(x) => B(x).
+axiom b_subset_c:
+    forall x . B(x) => C(x).
@@ -0,0 +1,2 @@
+axiom ok: P(a()).
+axiom broken: P(a()) and.
@@ -0,0 +1,2 @@
+axiom p: P(a()).
+include 'cycle.p'.
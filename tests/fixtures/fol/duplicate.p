@@ -0,0 +1,2 @@
+include 'axioms/subset.p'.
+axiom b_subset_c: forall x . B(x) => C(x).
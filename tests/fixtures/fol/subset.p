@@ -0,0 +1,5 @@
+% subsets are transitive, on an element.
+include 'axioms/subset.p'.
+
+hypothesis a_in_A: A(a()).
+conjecture 'a in C': C(a()).  % follows from the axioms
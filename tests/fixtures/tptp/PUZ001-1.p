@@ -0,0 +1,5 @@
+% Clauses with implicit variables.
+cnf(agatha, hypothesis, lives(agatha)).
+cnf(killer, axiom, ( ~ killed(X, Y) | hates(X, Y) )).
+cnf(not_self, negated_conjecture, X != agatha | ~ hates(X, X)).
+cnf(numbers, theorem, less(1, 2) | "Agatha" = agatha).
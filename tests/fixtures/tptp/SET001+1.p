@@ -0,0 +1,7 @@
+% Subsets are transitive.
+include('Axioms/SET000-0.ax', [subset_def, equal_def]).
+
+fof(a_subset_b, hypothesis, subset(a, b)).
+fof(b_subset_c, assumption, subset(b, c)).
+fof('transitivity of subset', conjecture,
+    (subset(a, b) & subset(b, c)) => subset(a, c)).
@@ -0,0 +1 @@
+include('Axioms/SET000-0.ax', [subset_def, union_def]).
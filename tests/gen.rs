@@ -0,0 +1,88 @@
+extern crate theorem_prover;
+use theorem_prover::sat;
+use theorem_prover::sat::brute;
+use theorem_prover::sat::clauses::{Clauses, SATSolver};
+use theorem_prover::sat::gen::{self, Graph, Rng};
+
+
+fn solvers() -> Vec<(&'static str, SATSolver)> {
+    vec![
+        ("dp", SATSolver(sat::dp::satisfiable_dp)),
+        ("cdcl", SATSolver(sat::cdcl::satisfiable_cdcl)),
+        ("fragment", SATSolver(sat::fragment::satisfiable)),
+        ("auto", SATSolver(sat::selector::satisfiable_auto)),
+    ]
+}
+
+
+// every solver agrees with the brute force oracle.
+fn cross_check(clauses: &Clauses) -> bool {
+    let expect = brute::satisfiable_brute_force(clauses.clone());
+    for (name, solver) in solvers() {
+        assert_eq!(solver.0(clauses.clone()), expect, "{} on {}", name, clauses);
+    }
+    expect
+}
+
+
+#[test]
+fn test_random_ksat() {
+    let mut rng = Rng::new(7);
+    let clauses = gen::random_ksat(&mut rng, 3, 10, 4.26);
+    assert_eq!(clauses.len(), 43);
+    assert!(clauses.iter().all(|c| c.len() == 3));
+    // the same seed gives the same instance.
+    let sorted = |clauses: &Clauses| {
+        clauses.iter().map(|c| { let mut c = c.iter().map(|l| l.to_string()).collect::<Vec<_>>(); c.sort(); c }).collect::<Vec<_>>()
+    };
+    assert_eq!(sorted(&gen::random_ksat(&mut Rng::new(7), 3, 10, 4.26)), sorted(&clauses));
+
+    let (mut sat, mut unsat) = (0, 0);
+    for _ in 0..200 {
+        let n = 3 + rng.below(10);
+        let ratio = 3.0 + 3.0 * rng.float();
+        let clauses = gen::random_ksat(&mut rng, 3, n, ratio);
+        if cross_check(&clauses) { sat += 1 } else { unsat += 1 }
+    }
+    // around the threshold both answers are common.
+    assert!(sat > 20 && unsat > 20, "{} sat, {} unsat", sat, unsat);
+}
+
+
+#[test]
+fn test_planted_ksat() {
+    let mut rng = Rng::new(11);
+    for _ in 0..50 {
+        let (clauses, model) = gen::planted_ksat(&mut rng, 3, 12, 6.0);
+        assert!(clauses.is_satisfied_by(&model));
+        assert!(cross_check(&clauses));
+    }
+}
+
+
+#[test]
+fn test_structured_families() {
+    for holes in 1..=4 {
+        assert!(!cross_check(&gen::pigeonhole(holes)));
+    }
+
+    let charges = |graph: &Graph, odd: &[usize]| (0..graph.vertices).map(|v| odd.contains(&v)).collect::<Vec<_>>();
+    let cycle = Graph::cycle(6);
+    assert!(!cross_check(&gen::tseitin_parity(&cycle, &charges(&cycle, &[0]))));
+    assert!(cross_check(&gen::tseitin_parity(&cycle, &charges(&cycle, &[0, 3]))));
+    // a cycle has exactly two solutions for any even charge.
+    assert_eq!(brute::count(&gen::tseitin_parity(&cycle, &charges(&cycle, &[1, 2]))), 2);
+    let grid = Graph::grid(3, 2);
+    assert!(!cross_check(&gen::tseitin_parity(&grid, &charges(&grid, &[0, 2, 5]))));
+
+    assert!(!cross_check(&gen::colouring(&Graph::cycle(5), 2)));
+    assert!(cross_check(&gen::colouring(&Graph::cycle(6), 2)));
+    assert_eq!(brute::count(&gen::colouring(&Graph::cycle(5), 3)), 30);
+    assert!(!cross_check(&gen::colouring(&Graph::complete(4), 3)));
+
+    let mut rng = Rng::new(3);
+    let graph = Graph::random(&mut rng, 6, 9);
+    assert_eq!(graph.edges.len(), 9);
+    assert!(graph.edges.iter().all(|&(u, v)| u < v && v < 6));
+    cross_check(&gen::colouring(&graph, 3));
+}
@@ -0,0 +1,143 @@
+extern crate theorem_prover;
+use theorem_prover::sat::brute;
+use theorem_prover::sat::gen::{self, Rng};
+use theorem_prover::sat::ipasir::*;
+use theorem_prover::sat::clauses::Literal;
+use std::env;
+use std::ffi::CStr;
+use std::os::raw::{c_int, c_void};
+use std::process::Command;
+
+
+unsafe fn clause(solver: *mut c_void, lits: &[i32]) {
+    for &lit in lits {
+        ipasir_add(solver, lit);
+    }
+    ipasir_add(solver, 0);
+}
+
+
+fn dimacs(literal: &Literal) -> i32 {
+    let var = literal.var_name().parse::<i32>().unwrap();
+    if literal.is_negated() { -var } else { var }
+}
+
+
+unsafe extern "C" fn always(data: *mut c_void) -> c_int {
+    *(data as *mut usize) += 1;
+    1
+}
+
+
+unsafe extern "C" fn collect(data: *mut c_void, clause: *mut i32) {
+    let clauses = &mut *(data as *mut Vec<Vec<i32>>);
+    let mut lits = Vec::new();
+    let mut i = 0;
+    while *clause.add(i) != 0 {
+        lits.push(*clause.add(i));
+        i += 1;
+    }
+    clauses.push(lits);
+}
+
+
+#[test]
+fn test_ipasir_incremental() {
+    unsafe {
+        let signature = CStr::from_ptr(ipasir_signature()).to_str().unwrap();
+        assert!(signature.starts_with("theorem_prover"));
+
+        let s = ipasir_init();
+        clause(s, &[1, 2]);
+        clause(s, &[-1, 3]);
+        clause(s, &[-2, 3]);
+        assert_eq!(ipasir_solve(s), 10);
+        assert_eq!(ipasir_val(s, 3), 3);
+        assert_eq!(ipasir_val(s, -3), 3);
+        // never mentioned, so its value does not matter.
+        assert_eq!(ipasir_val(s, 7), 0);
+
+        ipasir_assume(s, -3);
+        ipasir_assume(s, 4);
+        assert_eq!(ipasir_solve(s), 20);
+        assert_eq!(ipasir_failed(s, -3), 1);
+        assert_eq!(ipasir_failed(s, 4), 0);
+
+        assert_eq!(ipasir_solve(s), 10);
+        clause(s, &[-3]);
+        assert_eq!(ipasir_solve(s), 20);
+        ipasir_release(s);
+    }
+}
+
+
+#[test]
+fn test_ipasir_callbacks() {
+    unsafe {
+        let s = ipasir_init();
+        for c in gen::pigeonhole(6).iter() {
+            clause(s, &c.iter().map(|l| {
+                // p{p}h{h} numbered from 1.
+                let name = l.var_name();
+                let h = name.find('h').unwrap();
+                let var = (name[1..h].parse::<i32>().unwrap() * 6 + name[h + 1..].parse::<i32>().unwrap()) + 1;
+                if l.is_negated() { -var } else { var }
+            }).collect::<Vec<_>>());
+        }
+        let mut polls = 0usize;
+        ipasir_set_terminate(s, &mut polls as *mut usize as *mut c_void, Some(always));
+        assert_eq!(ipasir_solve(s), 0);
+        assert_eq!(polls, 1);
+
+        let mut learnt: Vec<Vec<i32>> = Vec::new();
+        ipasir_set_terminate(s, std::ptr::null_mut(), None);
+        ipasir_set_learn(s, &mut learnt as *mut Vec<Vec<i32>> as *mut c_void, 4, Some(collect));
+        assert_eq!(ipasir_solve(s), 20);
+        assert!(!learnt.is_empty());
+        assert!(learnt.iter().all(|c| !c.is_empty() && c.len() <= 4 && c.iter().all(|&l| l != 0 && l.abs() <= 42)));
+        ipasir_release(s);
+    }
+}
+
+
+#[test]
+fn test_ipasir_random() {
+    let mut rng = Rng::new(38);
+    for _ in 0..50 {
+        let clauses = gen::random_ksat(&mut rng, 3, 12, 4.26);
+        let expected = brute::satisfiable_brute_force(clauses.clone());
+        unsafe {
+            let s = ipasir_init();
+            for c in clauses.iter() {
+                clause(s, &c.iter().map(dimacs).collect::<Vec<_>>());
+            }
+            assert_eq!(ipasir_solve(s), if expected { 10 } else { 20 });
+            if expected {
+                let satisfied = clauses.iter().all(|c| c.iter().any(|l| ipasir_val(s, dimacs(l)) == dimacs(l)));
+                assert!(satisfied);
+            }
+            ipasir_release(s);
+        }
+    }
+}
+
+
+#[test]
+fn test_ipasir_from_c() {
+    // cargo builds the static library next to the test binary, in target/<profile>/deps.
+    let exe = env::current_exe().unwrap();
+    let dir = exe.parent().unwrap();
+    let driver = dir.join("ipasir-driver");
+    let built = Command::new("cc")
+        .args(["-std=c99", "-Iinclude", "tests/ipasir/driver.c"])
+        .arg(dir.join("libtheorem_prover.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&driver)
+        .status()
+        .expect("the C driver needs a C compiler, cc");
+    assert!(built.success());
+    let run = Command::new(&driver).output().unwrap();
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    assert!(run.status.success(), "{}{}", stdout, String::from_utf8_lossy(&run.stderr));
+    assert!(stdout.starts_with("theorem_prover-") && stdout.ends_with("ok\n"), "{}", stdout);
+}
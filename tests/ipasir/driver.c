@@ -0,0 +1,105 @@
+/* Exercise the IPASIR interface from C. `test_ipasir_from_c` in tests/ipasir.rs builds and
+ * runs it, by hand:
+ *
+ *     cargo build
+ *     cc -Iinclude tests/ipasir/driver.c target/debug/libtheorem_prover.a -lpthread -ldl -lm -o driver
+ *     ./driver
+ *
+ * Prints the solver signature and exits with 0 if every check passes.
+ */
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "ipasir.h"
+
+#define CHECK(cond) do { \
+    if (!(cond)) { \
+        fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+        exit(1); \
+    } \
+} while (0)
+
+static void clause(void *s, const int32_t *lits) {
+    while (*lits) {
+        ipasir_add(s, *lits++);
+    }
+    ipasir_add(s, 0);
+}
+
+static int always(void *data) {
+    ++*(int *)data;
+    return 1;
+}
+
+static void count(void *data, int32_t *lits) {
+    CHECK(lits[0] != 0);
+    ++*(int *)data;
+}
+
+/* `holes + 1` pigeons in `holes` holes, variable p * holes + h + 1 putting pigeon p in h. */
+static void pigeonhole(void *s, int holes) {
+    for (int p = 0; p <= holes; p++) {
+        for (int h = 0; h < holes; h++) {
+            ipasir_add(s, p * holes + h + 1);
+        }
+        ipasir_add(s, 0);
+    }
+    for (int h = 0; h < holes; h++) {
+        for (int p = 0; p <= holes; p++) {
+            for (int q = p + 1; q <= holes; q++) {
+                int32_t lits[] = { -(p * holes + h + 1), -(q * holes + h + 1), 0 };
+                clause(s, lits);
+            }
+        }
+    }
+}
+
+int main(void) {
+    printf("%s\n", ipasir_signature());
+
+    /* incremental solving with assumptions. */
+    void *s = ipasir_init();
+    int32_t a[] = { 1, 2, 0 }, b[] = { -1, 3, 0 }, c[] = { -2, 3, 0 };
+    clause(s, a);
+    clause(s, b);
+    clause(s, c);
+    CHECK(ipasir_solve(s) == 10);
+    CHECK(ipasir_val(s, 3) == 3);
+    CHECK(ipasir_val(s, -3) == 3);
+
+    ipasir_assume(s, -3);
+    ipasir_assume(s, 4);
+    CHECK(ipasir_solve(s) == 20);
+    CHECK(ipasir_failed(s, -3));
+    CHECK(!ipasir_failed(s, 4));
+
+    /* assumptions are gone, a new clause narrows the models. */
+    CHECK(ipasir_solve(s) == 10);
+    int32_t d[] = { -3, -1, 0 };
+    clause(s, d);
+    CHECK(ipasir_solve(s) == 10);
+    CHECK(ipasir_val(s, 1) == -1 && ipasir_val(s, 2) == 2);
+    int32_t e[] = { -3, 0 };
+    clause(s, e);
+    CHECK(ipasir_solve(s) == 20);
+    ipasir_release(s);
+
+    /* a terminate callback stops the search at the first conflict. */
+    s = ipasir_init();
+    pigeonhole(s, 6);
+    int polls = 0;
+    ipasir_set_terminate(s, &polls, always);
+    CHECK(ipasir_solve(s) == 0);
+    CHECK(polls == 1);
+
+    /* without it the search finishes, reporting short learnt clauses on the way. */
+    int learnt = 0;
+    ipasir_set_terminate(s, NULL, NULL);
+    ipasir_set_learn(s, &learnt, 3, count);
+    CHECK(ipasir_solve(s) == 20);
+    CHECK(learnt > 0);
+    ipasir_release(s);
+
+    printf("ok\n");
+    return 0;
+}
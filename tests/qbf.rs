@@ -0,0 +1,139 @@
+extern crate theorem_prover;
+use theorem_prover::sat::clauses::{Clause, Clauses, Literal, Model};
+use theorem_prover::sat::qbf::{Block, Certificate, Qbf, Quantifier, Strategy};
+use std::fs::File;
+use std::io::BufReader;
+
+
+fn load(path: &str) -> Qbf {
+    Qbf::from_qdimacs(BufReader::new(File::open(path).unwrap())).expect("Failed to parse")
+}
+
+
+// value of the QBF with `model` fixing some variables, by expanding every quantifier.
+fn evaluate(prefix: &[Block], matrix: &Clauses, model: &mut Model) -> bool {
+    let vars = prefix.iter().flat_map(|b| b.vars.iter().map(move |v| (b.quantifier, v))).collect::<Vec<_>>();
+    fn go(vars: &[(Quantifier, &String)], matrix: &Clauses, model: &mut Model) -> bool {
+        let Some(&(quantifier, var)) = vars.first() else {
+            return matrix.is_satisfied_by(model);
+        };
+        if model.contains_key(var) {
+            return go(&vars[1..], matrix, model);
+        }
+        let mut results = [false, true].iter().map(|&b| {
+            model.insert(var.clone(), b);
+            let r = go(&vars[1..], matrix, model);
+            model.remove(var);
+            r
+        });
+        match quantifier {
+            Quantifier::Exists => results.any(|r| r),
+            Quantifier::Forall => results.all(|r| r),
+        }
+    }
+    go(&vars, matrix, model)
+}
+
+
+fn check(qbf: &Qbf, certificate: &Certificate) {
+    let prefix = qbf.normalized_prefix();
+    let expected = evaluate(&prefix, &qbf.matrix, &mut Model::new());
+    assert_eq!(certificate.value(), expected);
+    match certificate.strategy() {
+        Strategy::Move(values) => {
+            let mut model = values.clone();
+            assert_eq!(evaluate(&prefix, &qbf.matrix, &mut model), expected);
+        },
+        Strategy::Responses(_) => {
+            // the response the decision list picks beats every assignment of the outermost
+            // block.
+            let outer = &prefix[0].vars;
+            for bits in 0..1u32 << outer.len() {
+                let assignment = outer.iter().enumerate().map(|(i, v)| (v.clone(), bits >> i & 1 == 1)).collect::<Model>();
+                let response = certificate.strategy().respond(&assignment).expect("a cube covering the assignment");
+                let mut model = assignment.clone();
+                model.extend(response.clone());
+                assert_eq!(evaluate(&prefix, &qbf.matrix, &mut model), expected);
+            }
+        },
+    }
+}
+
+
+#[test]
+fn test_qdimacs() {
+    let qbf = load("tests/fixtures/qbf/true.qdimacs");
+    assert_eq!(qbf.prefix.len(), 2);
+    assert_eq!(qbf.prefix[0], Block { quantifier: Quantifier::Forall, vars: vec!["1".to_string()] });
+    assert_eq!(qbf.matrix.len(), 2);
+    let certificate = qbf.solve();
+    assert!(certificate.value());
+    check(&qbf, &certificate);
+
+    // the same matrix with the blocks swapped.
+    let qbf = load("tests/fixtures/qbf/false.qdimacs");
+    let certificate = qbf.solve();
+    assert!(!certificate.value());
+    assert!(matches!(certificate, Certificate::Herbrand(Strategy::Responses(_))));
+    // x answers y with its negation.
+    for y in [false, true] {
+        let outer = std::iter::once(("2".to_string(), y)).collect::<Model>();
+        let response = certificate.strategy().respond(&outer).expect("a response");
+        assert_eq!(response.get("1"), Some(&!y));
+    }
+    check(&qbf, &certificate);
+}
+
+
+#[test]
+fn test_qdimacs_errors() {
+    let parse = |text: &str| Qbf::from_qdimacs(text.as_bytes());
+    assert!(parse("p cnf 2 1\na 1 0\ne 2 0\n1 -2 0\n").is_ok());
+    assert!(parse("p cnf 2 1\na 1 0\ne 2 0\n1\n-2 0\n").is_ok());
+    // literals beyond the variable count of the header or not integers.
+    assert!(parse("p cnf 2 1\na 1 0\ne 2 0\n1 -3 0\n").is_err());
+    assert!(parse("p cnf 2 1\na 1 0\ne 2 0\n1 -x 0\n").is_err());
+    assert!(parse("p cnf 2 1\na 1 0\ne 3 0\n1 0\n").is_err());
+    // the last clause without its closing 0.
+    assert!(parse("p cnf 2 1\na 1 0\ne 2 0\n1 -2\n").is_err());
+    // malformed prefixes.
+    assert!(parse("p cnf 2 1\na 1\ne 2 0\n1 -2 0\n").is_err());
+    assert!(parse("p cnf 2 1\na 0 1 0\ne 2 0\n1 -2 0\n").is_err());
+    assert!(parse("p cnf 2 1\na 0\ne 1 2 0\n1 -2 0\n").is_err());
+    assert!(parse("p cnf 2 1\na 1 0\ne 1 2 0\n1 -2 0\n").is_err());
+    assert!(parse("p cnf 2 1\na 1 0\n1 -2 0\ne 2 0\n").is_err());
+    assert!(parse("p cnf x 1\na 1 0\n1 0\n").is_err());
+}
+
+
+#[test]
+fn test_qbf_matches_expansion() {
+    // small random formulas with up to four alternations, from a fixed linear congruential
+    // sequence.
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = |n: u64| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) % n
+    };
+    for _ in 0..300 {
+        let n_vars = 2 + next(5) as usize;
+        let prefix = (1..=n_vars)
+            .map(|v| Block {
+                quantifier: if next(2) == 0 { Quantifier::Exists } else { Quantifier::Forall },
+                vars: vec![v.to_string()],
+            })
+            .collect::<Vec<_>>();
+        let matrix = (0..1 + next(8))
+            .map(|_| {
+                (0..1 + next(3))
+                    .map(|_| {
+                        let var = (1 + next(n_vars as u64)).to_string();
+                        if next(2) == 0 { Literal::pos(var) } else { Literal::neg(var) }
+                    })
+                    .collect::<Clause>()
+            })
+            .collect::<Clauses>();
+        let qbf = Qbf { prefix, matrix };
+        check(&qbf, &qbf.solve());
+    }
+}
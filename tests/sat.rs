@@ -0,0 +1,937 @@
+extern crate theorem_prover;
+use theorem_prover::fol::parser;
+use theorem_prover::sat::approxmc::{self, Sampler};
+use theorem_prover::sat::backbone::{self, Implications};
+use theorem_prover::sat::bdd::{self, Bdd, Manager};
+use theorem_prover::sat::brute;
+use theorem_prover::sat::dnnf::Dnnf;
+use theorem_prover::sat::features::{self, Features};
+use theorem_prover::sat::forget;
+use theorem_prover::sat::implicant::{self, Implicants};
+use theorem_prover::sat::fragment::{self, Fragment};
+use theorem_prover::sat::gen::{self, Graph, Rng};
+use theorem_prover::sat::cdcl::{Lit, Solver, Var};
+use theorem_prover::sat::clauses::{Clause, Clauses, Literal, Model, SATSolver};
+use theorem_prover::sat::mus::{self, Shrink};
+use theorem_prover::sat::propagator::ExternalPropagator;
+use theorem_prover::sat::selector::{self, Config, Engine, Learned, Rules, Selector};
+use theorem_prover::sat::symmetry;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::rc::Rc;
+
+
+fn cnf(dimacs: &str) -> Clauses {
+    Clauses::from_dimacs(dimacs.as_bytes()).expect("Failed to parse")
+}
+
+
+fn lits(names: &[&str]) -> Vec<Literal> {
+    names.iter().map(|s| match s.strip_prefix('-') {
+        Some(name) => Literal::neg(name.to_string()),
+        None => Literal::pos(s.to_string()),
+    }).collect()
+}
+
+
+#[test]
+fn test_cdcl_assumptions_and_core() {
+    let clauses = cnf("1 2 0\n-1 3 0\n-2 3 0\n-3 4 5 0");
+    let mut solver = Solver::from_clauses(&clauses);
+    assert!(solver.solve());
+    assert!(clauses.is_satisfied_by(&solver.model()));
+
+    let assumptions = lits(&["-3", "4", "5"]).iter().map(|l| solver.lit(l)).collect::<Vec<_>>();
+    assert!(!solver.solve_with(&assumptions));
+    assert_eq!(solver.core(), &assumptions[..1]);
+
+    // assumptions only hold for one call.
+    assert!(solver.solve());
+}
+
+
+/* Exactly k of `vars` are true, kept by propagation alone. The propagator mirrors the
+ * solver's trail from the notifications and explains propagations from it. */
+struct ExactlyK {
+    vars: Vec<Var>,
+    k: usize,
+    trail: Vec<Lit>,
+    levels: Vec<usize>,
+    rejected: Option<Vec<Lit>>,
+}
+
+
+impl ExternalPropagator for ExactlyK {
+    fn notify_assignment(&mut self, lit: Lit, _fixed: bool) {
+        assert!(self.trail.iter().all(|l| l.var() != lit.var()), "{:?} assigned twice", lit);
+        self.trail.push(lit);
+    }
+
+    fn notify_new_decision_level(&mut self) {
+        self.levels.push(self.trail.len());
+    }
+
+    fn notify_backtrack(&mut self, level: usize) {
+        if level < self.levels.len() {
+            self.trail.truncate(self.levels[level]);
+            self.levels.truncate(level);
+        }
+    }
+
+    fn check_found_model(&mut self, model: &[Lit]) -> bool {
+        let ours = model.iter().filter(|l| self.vars.contains(&l.var())).cloned().collect::<Vec<_>>();
+        if ours.iter().filter(|l| !l.is_negated()).count() == self.k {
+            return true;
+        }
+        self.rejected = Some(ours.iter().map(|l| l.negate()).collect());
+        false
+    }
+
+    fn propagate(&mut self) -> Option<Lit> {
+        let ones = self.trail.iter().filter(|l| !l.is_negated()).count();
+        let zeros = self.trail.len() - ones;
+        let free = self.vars.iter().find(|&&v| self.trail.iter().all(|l| l.var() != v))?;
+        if ones == self.k {
+            Some(free.neg())
+        } else if zeros == self.vars.len() - self.k {
+            Some(free.pos())
+        } else {
+            None
+        }
+    }
+
+    fn add_reason_clause(&mut self, propagated: Lit) -> Vec<Lit> {
+        let at = self.trail.iter().position(|&l| l == propagated).expect("explaining an unknown propagation");
+        let mut clause = vec![propagated];
+        clause.extend(self.trail[..at].iter().filter(|l| l.is_negated() != propagated.is_negated()).map(|l| l.negate()));
+        clause
+    }
+
+    fn add_external_clause(&mut self) -> Option<Vec<Lit>> {
+        self.rejected.take()
+    }
+}
+
+
+/* Accepts only models where an even number of `vars` is true, and nothing else. */
+struct EvenParity(Vec<Var>, Option<Vec<Lit>>);
+
+
+impl ExternalPropagator for EvenParity {
+    fn check_found_model(&mut self, model: &[Lit]) -> bool {
+        let ours = model.iter().filter(|l| self.0.contains(&l.var())).cloned().collect::<Vec<_>>();
+        if ours.iter().filter(|l| !l.is_negated()).count() % 2 == 0 {
+            return true;
+        }
+        self.1 = Some(ours.iter().map(|l| l.negate()).collect());
+        false
+    }
+
+    fn add_reason_clause(&mut self, _: Lit) -> Vec<Lit> {
+        unreachable!("never propagates")
+    }
+
+    fn add_external_clause(&mut self) -> Option<Vec<Lit>> {
+        self.1.take()
+    }
+}
+
+
+// every model of the solver projected on `vars`, found by blocking them one by one.
+fn enumerate(solver: &mut Solver, vars: &[Var]) -> Vec<Vec<bool>> {
+    let mut models = Vec::new();
+    while solver.solve() {
+        let model = vars.iter().map(|&v| solver.value(v.pos()).unwrap()).collect::<Vec<_>>();
+        let blocking = vars.iter().zip(model.iter()).map(|(&v, &b)| Lit::new(v, b)).collect::<Vec<_>>();
+        models.push(model);
+        solver.add_clause(&blocking);
+    }
+    models
+}
+
+
+#[test]
+fn test_external_propagator() {
+    // two of six, one of them 1 or 2: 15 pairs minus the 6 avoiding both.
+    let mut solver = Solver::from_clauses(&cnf("1 2 0"));
+    let vars = (1..=6).map(|v| solver.var(&v.to_string())).collect::<Vec<_>>();
+    for &v in vars.iter() {
+        solver.add_observed_var(v);
+    }
+    solver.connect_propagator(Box::new(ExactlyK { vars: vars.clone(), k: 2, trail: Vec::new(), levels: Vec::new(), rejected: None }));
+    let models = enumerate(&mut solver, &vars);
+    assert_eq!(models.len(), 9);
+    assert!(models.iter().all(|m| m.iter().filter(|&&b| b).count() == 2 && (m[0] || m[1])));
+
+    // the reason of ¬3 is asked for to find the core.
+    let mut solver = Solver::new();
+    let vars = (1..=4).map(|v| solver.var(&v.to_string())).collect::<Vec<_>>();
+    for &v in vars.iter() {
+        solver.add_observed_var(v);
+    }
+    solver.connect_propagator(Box::new(ExactlyK { vars: vars.clone(), k: 2, trail: Vec::new(), levels: Vec::new(), rejected: None }));
+    let assumptions = vars[..3].iter().map(|v| v.pos()).collect::<Vec<_>>();
+    assert!(!solver.solve_with(&assumptions));
+    let mut core = solver.core().to_vec();
+    core.sort();
+    assert_eq!(core, assumptions);
+    assert!(solver.solve_with(&assumptions[..2]));
+    assert_eq!(solver.value(vars[3].pos()), Some(false));
+
+    // only models are checked: odd ones are vetoed after the fact.
+    let mut solver = Solver::from_clauses(&cnf("1 2 3 4 0"));
+    let vars = (1..=4).map(|v| solver.var(&v.to_string())).collect::<Vec<_>>();
+    solver.connect_propagator(Box::new(EvenParity(vars.clone(), None)));
+    let models = enumerate(&mut solver, &vars);
+    assert_eq!(models.len(), 7);
+    assert!(models.iter().all(|m| m.iter().filter(|&&b| b).count() % 2 == 0));
+    assert!(solver.disconnect_propagator().is_some());
+
+    // lazy reasons in conflict analysis against brute force.
+    let mut rng = Rng::new(37);
+    for _ in 0..50 {
+        let clauses = gen::random_ksat(&mut rng, 3, 9, 2.5);
+        let mut solver = Solver::from_clauses(&clauses);
+        let vars = (1..=9).map(|v| solver.var(&v.to_string())).collect::<Vec<_>>();
+        for &v in vars.iter() {
+            solver.add_observed_var(v);
+        }
+        solver.connect_propagator(Box::new(ExactlyK { vars: vars.clone(), k: 4, trail: Vec::new(), levels: Vec::new(), rejected: None }));
+        let expected = brute::models(&clauses).iter().filter(|m| m.values().filter(|&&b| b).count() == 4).count();
+        assert_eq!(enumerate(&mut solver, &vars).len(), expected, "{}", clauses);
+    }
+}
+
+
+#[test]
+fn test_inprocessing() {
+    let mut solver = Solver::new();
+    let vars = (1..=7).map(|v| solver.var(&v.to_string())).collect::<Vec<_>>();
+    let clauses = cnf("-1 2 0\n1 -2 0\n-2 -3 0\n2 3 0\n1 4 5 0\n-4 5 0\n-5 6 0\n-4 6 0\n-4 6 7 0");
+    solver.add_clauses(&clauses);
+    assert!(solver.inprocess());
+    // 2 and ¬3 are equivalent to 1, -4 ∨ 6 follows from 4 → 5 → 6 and shortens -4 ∨ 6 ∨ 7.
+    assert!(solver.is_substituted(vars[1]) && solver.is_substituted(vars[2]));
+    assert_eq!(solver.representative(vars[2].pos()), vars[0].neg());
+    assert_eq!(solver.stats.substituted_vars, 2);
+    assert!(solver.stats.reduced_binaries >= 1);
+    assert!(solver.stats.vivified_clauses >= 1);
+
+    assert!(solver.solve());
+    let model = solver.model();
+    assert_eq!(model.len(), 7);
+    assert!(clauses.is_satisfied_by(&model));
+
+    // assumptions on substituted variables are translated, and so is the core.
+    let assumptions = [vars[1].pos(), vars[2].pos(), vars[6].pos()];
+    assert!(!solver.solve_with(&assumptions));
+    let mut core = solver.core().to_vec();
+    core.sort();
+    assert_eq!(core, assumptions[..2]);
+    solver.add_clause(&[vars[1].neg()]);
+    assert!(solver.solve());
+    assert_eq!((solver.value(vars[0].pos()), solver.value(vars[2].pos())), (Some(false), Some(true)));
+    assert!(solver.fixed().contains(&vars[2].pos()));
+
+    // observing a variable brings it back.
+    solver.add_observed_var(vars[2]);
+    assert!(!solver.is_substituted(vars[2]));
+    assert!(solver.solve_with(&[vars[2].pos()]));
+    assert!(!solver.solve_with(&[vars[2].neg()]));
+
+    // incremental use with a round of inprocessing between calls.
+    let mut rng = Rng::new(39);
+    for _ in 0..30 {
+        let mut clauses = gen::random_ksat(&mut rng, 3, 16, 2.0);
+        for _ in 0..6 {
+            let (a, b) = (1 + rng.below(16), 1 + rng.below(16));
+            if a != b {
+                let sign = if rng.bool() { "" } else { "-" };
+                clauses.extend(cnf(&format!("-{} {}{} 0\n{} {}{} 0", a, sign, b, a, if sign.is_empty() { "-" } else { "" }, b)).iter().cloned());
+            }
+        }
+        let mut solver = Solver::from_clauses(&clauses);
+        let mut all = clauses.clone();
+        for _ in 0..6 {
+            solver.inprocess();
+            let sat = solver.solve();
+            assert_eq!(sat, brute::satisfiable_brute_force(all.clone()), "{}", all);
+            if !sat {
+                break;
+            }
+            assert!(all.is_satisfied_by(&solver.model()));
+            let assumptions = (0..3).map(|_| Literal::pos((1 + rng.below(16)).to_string())).collect::<Vec<_>>();
+            let lits = assumptions.iter().map(|l| solver.lit(l)).collect::<Vec<_>>();
+            if !solver.solve_with(&lits) {
+                let mut refuted = all.clone();
+                for &lit in solver.core() {
+                    refuted.push(vec![solver.literal(lit)].into_iter().collect());
+                }
+                assert!(!brute::satisfiable_brute_force(refuted));
+            }
+            let extra = gen::random_ksat(&mut rng, 3, 16, 0.5);
+            solver.add_clauses(&extra);
+            all.extend(extra.iter().cloned());
+        }
+    }
+}
+
+
+#[test]
+fn test_checkpoint() {
+    // a run checkpointed along the way, every checkpoint resumes to the same end.
+    let clauses = gen::random_ksat(&mut Rng::new(41), 3, 150, 4.26);
+    let run = |checkpoints: Option<&Rc<RefCell<Vec<Vec<u8>>>>>| {
+        let mut solver = Solver::from_clauses(&clauses);
+        if let Some(checkpoints) = checkpoints {
+            let checkpoints = checkpoints.clone();
+            solver.set_checkpoint(100, Some(Box::new(move |solver: &Solver| {
+                let mut bytes = Vec::new();
+                solver.save(&mut bytes).expect("Failed to save");
+                checkpoints.borrow_mut().push(bytes);
+            })));
+        }
+        let sat = solver.solve_limited(&[], Some(20_000));
+        (sat, solver.stats.conflicts, solver.stats.decisions, sat.filter(|&s| s).map(|_| solver.model()))
+    };
+    let checkpoints = Rc::new(RefCell::new(Vec::new()));
+    let expected = run(None);
+    assert!(expected.0.is_some());
+    assert_eq!(run(Some(&checkpoints)), expected);
+    assert!(checkpoints.borrow().len() >= 3);
+    for bytes in checkpoints.borrow().iter() {
+        let mut solver = Solver::load(&bytes[..]).expect("Failed to load");
+        let sat = solver.resume();
+        let model = sat.filter(|&s| s).map(|_| solver.model());
+        assert_eq!((sat, solver.stats.conflicts, solver.stats.decisions, model), expected);
+    }
+
+    // saved between incremental calls, the copy answers like the original.
+    let mut rng = Rng::new(41);
+    let mut solver = Solver::from_clauses(&gen::random_ksat(&mut rng, 3, 40, 4.0));
+    solver.solve();
+    let mut bytes = Vec::new();
+    solver.save(&mut bytes).unwrap();
+    let mut copy = Solver::load(&bytes[..]).unwrap();
+    assert_eq!(copy.resume(), None);
+    for _ in 0..20 {
+        let assumptions = (0..4).map(|_| solver.lit(&Literal::pos((1 + rng.below(40)).to_string()))).collect::<Vec<_>>();
+        assert_eq!(copy.solve_with(&assumptions), solver.solve_with(&assumptions));
+        assert_eq!((copy.model(), copy.core()), (solver.model(), solver.core()));
+        assert_eq!(copy.stats.conflicts, solver.stats.conflicts);
+    }
+
+    // foreign, newer, truncated and corrupt files are refused. Files breaking the invariants
+    // of the search are checked next to the solver.
+    let error = |bytes: &[u8]| Solver::load(bytes).err().expect("Loaded a broken checkpoint").kind();
+    assert_eq!(error(b"DIMACS"), io::ErrorKind::InvalidData);
+    let mut newer = bytes.clone();
+    newer[4] += 1;
+    assert_eq!(error(&newer), io::ErrorKind::InvalidData);
+    assert_eq!(error(&bytes[..bytes.len() / 2]), io::ErrorKind::UnexpectedEof);
+    let mut corrupt = bytes.clone();
+    // the first name's length, one byte too long makes every later field shift.
+    corrupt[16] += 1;
+    assert!(Solver::load(&corrupt[..]).is_err());
+}
+
+
+#[test]
+fn test_features() {
+    let clauses = cnf("1 -2 0\n-1 -3 0\n2 3 -4 0\n4 0\n-1 -2 -3 -4 0");
+    let features = Features::extract(&clauses);
+    assert_eq!((features.vars, features.clauses, features.ratio), (4, 5, 1.25));
+    assert_eq!((features.clause_length.min, features.clause_length.max, features.clause_length.mean), (1.0, 4.0, 2.4));
+    assert_eq!((features.unit, features.binary, features.ternary, features.horn), (0.2, 0.4, 0.2, 0.8));
+    // every variable occurs three times, once positively.
+    assert_eq!((features.var_degree.mean, features.var_degree.std), (3.0, 0.0));
+    assert_eq!((features.var_balance.min, features.var_balance.max), (1.0 / 3.0, 1.0 / 3.0));
+    assert_eq!(features.fragment, Fragment::RenamableHorn(vec!["3".to_string()]));
+    assert_eq!(features.search.result, Some(true));
+    assert!(features.local.solved);
+    assert_eq!(features.vector().len(), features::NAMES.len());
+    // extraction does not depend on the order of clauses or literals.
+    let reversed = Clauses(clauses.iter().rev().cloned().collect());
+    assert_eq!(Features::extract(&reversed), features);
+    // nor on hashing, which differs between processes: these values were taken in another
+    // one.
+    let random = Features::extract(&gen::random_ksat(&mut Rng::new(7), 3, 200, 4.26));
+    assert_eq!((random.search.result, random.search.conflicts), (None, 101));
+    assert_eq!((random.search.propagations_per_decision, random.search.mean_learned_size), (27.586466165413533, 11.554455445544555));
+    assert_eq!((random.local.solved, random.local.best), (false, 0.004988262910798121));
+
+    let pigeons = Features::extract(&gen::pigeonhole(6));
+    assert_eq!(pigeons.var_degree.std, 0.0);
+    assert_eq!(pigeons.search.result, None);
+    assert!(!pigeons.local.solved && pigeons.local.best > 0.0);
+    let empty = Features::extract(&Clauses::new());
+    assert_eq!((empty.vars, empty.ratio, empty.search.result, empty.local.solved), (0, 0.0, Some(true), true));
+    let refuted = Features::extract(&Clauses(vec![Clause::new()]));
+    assert_eq!((refuted.search.result, refuted.local.solved, refuted.local.best), (Some(false), false, 1.0));
+}
+
+
+#[test]
+fn test_engine_selection() {
+    let plain = Config { inprocessing: false, symmetry_breaking: false };
+    let select = |clauses: &Clauses| Rules.select(&Features::extract(clauses));
+    let mut rng = Rng::new(42);
+    assert_eq!(select(&gen::random_ksat(&mut rng, 2, 50, 1.0)), Engine::Fragment);
+    assert_eq!(select(&gen::random_ksat(&mut rng, 3, 10, 4.26)), Engine::BruteForce);
+    assert_eq!(select(&gen::random_ksat(&mut rng, 3, 100, 3.0)), Engine::Cdcl(plain));
+    assert_eq!(select(&gen::pigeonhole(6)), Engine::Cdcl(Config { symmetry_breaking: true, ..plain }));
+    assert_eq!(select(&gen::random_ksat(&mut rng, 3, 200, 4.26)), Engine::Cdcl(Config::default()));
+
+    // every engine gives models of the clauses and agrees on satisfiability.
+    for _ in 0..20 {
+        let clauses = gen::random_ksat(&mut rng, 3, 14, 4.26);
+        let expected = brute::satisfiable_brute_force(clauses.clone());
+        for engine in selector::ENGINES {
+            let model = engine.solve(&clauses);
+            assert_eq!(model.is_some(), expected, "{:?} on {}", engine, clauses);
+            assert!(model.is_none_or(|m| clauses.is_satisfied_by(&m)));
+        }
+    }
+    let (engine, model) = selector::solve(&gen::pigeonhole(5), &Rules);
+    assert!(matches!(engine, Engine::Cdcl(Config { symmetry_breaking: true, .. })));
+    assert!(model.is_none());
+
+    // a learned model only sees the feature vector, and its cheapest candidate wins.
+    let learned = Learned::new(|engine: Engine, vector: &[f64]| match engine {
+        Engine::Cdcl(config) if !config.inprocessing && !config.symmetry_breaking => vector[0],
+        Engine::BruteForce => 100.0,
+        _ => f64::INFINITY,
+    });
+    assert_eq!(learned.select(&Features::extract(&gen::pigeonhole(3))), Engine::Cdcl(plain));
+    assert_eq!(learned.select(&Features::extract(&gen::pigeonhole(10))), Engine::BruteForce);
+    let (engine, model) = selector::solve(&gen::random_ksat(&mut rng, 3, 30, 2.0), &learned);
+    assert_eq!(engine, Engine::Cdcl(plain));
+    assert!(model.is_some());
+}
+
+
+// the assignments to `keep` that extend to models of `clauses`, by brute force.
+fn projection(clauses: &Clauses, keep: &[String]) -> Vec<Vec<bool>> {
+    let mut projected = brute::models(clauses)
+        .iter()
+        .map(|m| keep.iter().map(|v| m.get(v).cloned().unwrap_or(false)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    // variables missing from the clauses are free.
+    let free = keep.iter().enumerate().filter(|(_, v)| !clauses.iter().any(|c| c.iter().any(|l| l.var_name() == v.as_str()))).map(|(i, _)| i).collect::<Vec<_>>();
+    for &i in free.iter() {
+        let flipped = projected.iter().map(|values| { let mut values = values.clone(); values[i] = !values[i]; values }).collect::<Vec<_>>();
+        projected.extend(flipped);
+    }
+    projected.sort();
+    projected.dedup();
+    projected
+}
+
+
+fn assignments(keep: &[String]) -> Vec<Model> {
+    (0..1u32 << keep.len()).map(|bits| keep.iter().enumerate().map(|(i, v)| (v.clone(), bits >> i & 1 == 1)).collect()).collect()
+}
+
+
+#[test]
+fn test_forgetting() {
+    let clauses = cnf("1 3 0\n-3 2 0\n-3 -4 0\n4 2 0");
+    let forgotten = forget::forget(&clauses, &["3".to_string(), "4".to_string()]);
+    assert_eq!(forgotten.len(), 1);
+    assert_eq!(forgotten[0].0, lits(&["1", "2"]).into_iter().collect());
+    // forgetting everything leaves nothing, or the empty clause.
+    assert!(forget::project(&clauses, &[]).is_empty());
+    assert_eq!(forget::project(&cnf("1 0\n-1 0"), &[]).iter().map(|c| c.len()).collect::<Vec<_>>(), vec![0]);
+
+    let mut rng = Rng::new(43);
+    for round in 0..40 {
+        let clauses = gen::random_ksat(&mut rng, 3, 10, [2.0, 3.5, 4.5][round % 3]);
+        let mut keep = (1..=10).filter(|_| rng.bool()).map(|v| v.to_string()).collect::<Vec<_>>();
+        keep.sort();
+        let vars = (1..=10).map(|v| v.to_string()).filter(|v| !keep.contains(v)).collect::<Vec<_>>();
+        let expected = projection(&clauses, &keep);
+        let resolved = forget::forget_bounded(&clauses, &vars, usize::MAX);
+        let primes = forget::forget_bounded(&clauses, &vars, 0);
+        let mixed = forget::forget_bounded(&clauses, &vars, clauses.len() + 4);
+        for result in [&resolved, &primes, &mixed, &forget::project(&clauses, &keep)] {
+            assert!(result.iter().all(|c| c.iter().all(|l| keep.iter().any(|v| v == l.var_name()))));
+            let mut satisfying = assignments(&keep)
+                .iter()
+                .filter(|m| result.is_satisfied_by(m))
+                .map(|m| keep.iter().map(|v| m[v]).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            satisfying.sort();
+            assert_eq!(satisfying, expected, "{} without {:?}", clauses, vars);
+        }
+        // every implicate is prime: dropping any literal lets some projected model falsify it.
+        for clause in primes.iter() {
+            for literal in clause.iter() {
+                let mut weaker = clause.clone();
+                weaker.remove(literal);
+                assert!(assignments(&keep).iter().any(|m| {
+                    !weaker.is_satisfied_by(m) && expected.contains(&keep.iter().map(|v| m[v]).collect::<Vec<_>>())
+                }));
+            }
+        }
+    }
+}
+
+
+#[test]
+fn test_prime_implicants() {
+    let clauses = cnf("1 2 0\n-1 3 0\n2 3 4 0");
+    let model = [("1", true), ("2", true), ("3", true), ("4", false)].iter().map(|&(v, b)| (v.to_string(), b)).collect::<Model>();
+    assert_eq!(implicant::prime_implicant(&clauses, &model), Some(lits(&["2", "3"])));
+    let mut falsifying = model.clone();
+    falsifying.insert("3".to_string(), false);
+    assert_eq!(implicant::prime_implicant(&clauses, &falsifying), None);
+
+    // implicants of random clauses are implicants, and dropping any literal breaks that.
+    let mut rng = Rng::new(44);
+    for _ in 0..30 {
+        let clauses = gen::random_ksat(&mut rng, 3, 10, 3.0);
+        let mut implicants = Implicants::new(&clauses);
+        let vars = (1..=10).map(|v| v.to_string()).collect::<Vec<_>>();
+        let covers = |cube: &[Literal]| assignments(&vars)
+            .iter()
+            .filter(|m| cube.iter().all(|l| m[l.var_name()] != l.is_negated()))
+            .all(|m| clauses.is_satisfied_by(m));
+        for model in brute::models(&clauses).iter().take(5) {
+            let prime = implicants.shrink(model).unwrap();
+            assert!(prime.iter().all(|l| model[l.var_name()] != l.is_negated()));
+            assert!(covers(&prime) && implicants.is_implicant(&prime));
+            for i in 0..prime.len() {
+                let mut smaller = prime.clone();
+                smaller.remove(i);
+                assert!(!implicants.is_implicant(&smaller));
+            }
+        }
+        // exhausted, the implicants cover every model.
+        let all = implicants.enumerate(usize::MAX);
+        for model in brute::models(&clauses) {
+            assert!(all.iter().any(|cube| cube.iter().all(|l| model[l.var_name()] != l.is_negated())));
+        }
+    }
+
+    // a formula, with implicant checks done by BDDs.
+    let formula = parser::parse("((P(a) and Q(b)) or (P(a) and R(c))) or ((not P(a)) and R(c))").unwrap();
+    let mut m = Manager::new();
+    let f = m.from_formula(&formula);
+    let cube = |m: &mut Manager, cube: &[Literal]| cube.iter().fold(Bdd::TRUE, |acc, l| {
+        let v = m.var(l.var_name());
+        let v = if l.is_negated() { m.not(v) } else { v };
+        m.and(acc, v)
+    });
+    let mut implicants = Implicants::from_formula(&formula);
+    let model = [("P(a)", true), ("Q(b)", true), ("R(c)", true)].iter().map(|&(v, b)| (v.to_string(), b)).collect::<Model>();
+    let prime = implicants.shrink(&model).unwrap();
+    assert!(prime == lits(&["P(a)", "Q(b)"]) || prime == lits(&["R(c)"]), "{:?}", prime);
+    let all = implicants.enumerate(10);
+    assert!(all.len() < 10);
+    let mut union = Bdd::FALSE;
+    for prime in all.iter() {
+        let c = cube(&mut m, prime);
+        assert_eq!(m.implies(c, f), Bdd::TRUE);
+        for i in 0..prime.len() {
+            let mut smaller = prime.clone();
+            smaller.remove(i);
+            let c = cube(&mut m, &smaller);
+            assert_ne!(m.implies(c, f), Bdd::TRUE);
+        }
+        union = m.or(union, c);
+    }
+    assert_eq!(union, f);
+    assert_eq!(Implicants::from_formula(&parser::parse("P(a) and (not P(a))").unwrap()).enumerate(3).len(), 0);
+}
+
+
+#[test]
+fn test_backbone() {
+    // 3 is forced by both branches of 1 ∨ 2, 6 only through a unit.
+    let clauses = cnf("1 2 0\n-1 3 0\n-2 3 0\n-3 4 5 0\n6 0\n-6 -7 0");
+    assert_eq!(backbone::backbone(&clauses), Some(lits(&["3", "6", "-7"])));
+    assert_eq!(backbone::backbone(&cnf("1 0\n-1 0")), None);
+
+    let mut implications = Implications::new(&clauses);
+    assert_eq!(implications.backbone(&lits(&["-4"])), Some(lits(&["3", "-4", "5", "6", "-7"])));
+    assert_eq!(implications.backbone(&lits(&["-3"])), None);
+    assert!(implications.is_implied(&lits(&["-1"]), &Literal::pos("2".to_string())));
+    assert!(!implications.is_implied(&[], &Literal::pos("2".to_string())));
+    assert!(implications.is_implied(&lits(&["-4"]), &Literal::pos("5".to_string())));
+}
+
+
+#[test]
+fn test_mus_and_mcs() {
+    // muses {0, 1} and {0, 2, 3}, clause 4 is never needed.
+    let clauses = cnf("1 0\n-1 0\n-1 2 0\n-2 0\n3 -2 0");
+    let core = mus::unsat_core(&clauses).unwrap();
+    for method in [Shrink::Deletion, Shrink::QuickXplain].iter() {
+        let found = mus::shrink(&clauses, &core, *method);
+        assert!(found == vec![0, 1] || found == vec![0, 2, 3], "{:?}", found);
+        assert_eq!(mus::shrink(&clauses, &[0, 2, 3, 4], *method), vec![0, 2, 3]);
+    }
+    assert_eq!(mus::mus(&cnf("1 2 0\n-1 0")), None);
+
+    let mut muses = mus::all_muses(&clauses);
+    muses.sort();
+    assert_eq!(muses, vec![vec![0, 1], vec![0, 2, 3]]);
+    let mut mcses = mus::all_mcses(&clauses);
+    mcses.sort();
+    assert_eq!(mcses, vec![vec![0], vec![1, 2], vec![1, 3]]);
+}
+
+
+#[test]
+fn test_bdd_equivalence() {
+    let equivalent = |a: &str, b: &str| bdd::equivalent(&parser::parse(a).unwrap(), &parser::parse(b).unwrap());
+    assert!(equivalent("P(x) => Q(x)", "(not P(x)) or Q(x)"));
+    assert!(equivalent("not (P(x) and Q(y))", "(not P(x)) or (not Q(y))"));
+    assert!(equivalent("P(x) <=> Q(x)", "(P(x) => Q(x)) and (Q(x) => P(x))"));
+    assert!(equivalent("P(x) or (not P(x))", "Q(a) => Q(a)"));
+    assert!(!equivalent("P(x) => Q(x)", "Q(x) => P(x)"));
+    assert!(!equivalent("P(x)", "P(y)"));
+}
+
+
+#[test]
+fn test_bdd_operations() {
+    let mut m = Manager::new();
+    let clauses = cnf("1 2 0\n-1 3 0\n-2 -3 0");
+    let f = m.from_clauses(&clauses);
+    assert_eq!(m.count(f), 2);
+    let model = m.any_model(f).unwrap();
+    assert!(clauses.iter().all(|c| c.iter().any(|l| model.get(l.var_name()).is_some_and(|&b| b != l.is_negated()))));
+    assert_eq!(m.from_clauses(&m.to_clauses(f)), f);
+
+    let formula = m.to_formula(f);
+    assert_eq!(m.from_formula(&formula), f);
+
+    let one = m.var("1");
+    let three = m.var("3");
+    let two = m.var("2");
+    let not_two = m.not(two);
+    let expected = m.and(three, not_two);
+    assert_eq!(m.restrict(f, "1", true), expected);
+    assert_eq!(m.exists(f, &["2", "3"]), Bdd::TRUE);
+    assert_eq!(m.forall(f, &["1"]), Bdd::FALSE);
+    let expected = m.xor(two, three);
+    assert_eq!(m.exists(f, &["1"]), expected);
+    let not_one = m.not(one);
+    let contradiction = m.and(one, not_one);
+    assert!(m.any_model(contradiction).is_none());
+}
+
+
+#[test]
+fn test_bdd_count_saturates() {
+    let mut m = Manager::new();
+    let vars = (0..128).map(|i| m.var(&format!("x{}", i))).collect::<Vec<_>>();
+    let last = m.not(vars[127]);
+    assert_eq!(m.count(vars[0]), 1 << 127);
+    assert_eq!(m.count(last), 1 << 127);
+    assert_eq!(m.count(Bdd::FALSE), 0);
+    // 2^128 models, and 2^128 - 1 for the or of the variables.
+    assert_eq!(m.count(Bdd::TRUE), u128::MAX);
+    let any = vars.iter().fold(Bdd::FALSE, |f, &x| m.or(f, x));
+    assert_eq!(m.count(any), u128::MAX);
+    m.var("x128");
+    assert_eq!(m.count(vars[0]), u128::MAX);
+}
+
+
+#[test]
+fn test_dnnf_count_saturates() {
+    let clause = |n: usize| (1..=n).map(|v| v.to_string()).collect::<Vec<_>>().join(" ") + " 0";
+    let tautologies = |n: usize| (1..=n).map(|v| format!("{} -{} 0", v, v)).collect::<Vec<_>>().join("\n");
+    assert_eq!(Dnnf::compile(&cnf(&clause(127))).count(), (1 << 127) - 1);
+    assert_eq!(Dnnf::compile(&cnf(&clause(128))).count(), u128::MAX);
+    assert_eq!(Dnnf::compile(&cnf(&clause(130))).count(), u128::MAX);
+    assert_eq!(Dnnf::compile(&cnf(&tautologies(127))).count(), 1 << 127);
+    assert_eq!(Dnnf::compile(&cnf(&tautologies(130))).count(), u128::MAX);
+    // 2^129 models conditioned down to 2^127.
+    let dnnf = Dnnf::compile(&cnf(&tautologies(129)));
+    assert_eq!(dnnf.condition(&lits(&["1", "-2"])).count(), 1 << 127);
+}
+
+
+#[test]
+fn test_bdd_sifting() {
+    // (a1 ∧ b1) ∨ (a2 ∧ b2) ∨ (a3 ∧ b3) ∨ (a4 ∧ b4) is exponential in this order, linear when
+    // each pair is adjacent.
+    let mut m = Manager::new();
+    let vars = ["a1", "a2", "a3", "a4", "b1", "b2", "b3", "b4"].iter().map(|n| m.var(n)).collect::<Vec<_>>();
+    let mut f = Bdd::FALSE;
+    for i in 0..4 {
+        let pair = m.and(vars[i], vars[i + 4]);
+        f = m.or(f, pair);
+    }
+    let count = m.count(f);
+    let before = m.size(&[f]);
+    m.sift(&[f]);
+    assert!(m.size(&[f]) < before, "{} -> {}", before, m.size(&[f]));
+    assert_eq!(m.size(&[f]), 10);
+    assert_eq!(m.count(f), count);
+
+    // the rewritten nodes still denote the same function.
+    let mut g = Bdd::FALSE;
+    for i in 0..4 {
+        let pair = m.and(vars[i], vars[i + 4]);
+        g = m.or(g, pair);
+    }
+    assert_eq!(f, g);
+}
+
+
+#[test]
+fn test_fragments() {
+    assert_eq!(fragment::classify(&cnf("1 -2 0\n2 0")), Fragment::TwoCnf);
+    assert_eq!(fragment::classify(&cnf("-1 -2 3 0\n1 0\n-3 -1 -4 0")), Fragment::Horn);
+    let clauses = cnf("1 2 -3 0\n-1 0\n-2 3 -4 0");
+    let Fragment::RenamableHorn(flipped) = fragment::classify(&clauses) else { panic!("renamable Horn") };
+    let renamed = clauses
+        .iter()
+        .map(|c| c.iter().map(|l| if flipped.iter().any(|v| v == l.var_name()) { l.negate() } else { l.clone() }).collect::<Clause>())
+        .collect::<Clauses>();
+    assert!(fragment::is_horn(&renamed));
+    assert_eq!(fragment::classify(&cnf("1 2 3 0\n-1 -2 -3 0\n1 -2 0")), Fragment::General);
+
+    // ¬3 ∧ (3 ∨ ¬4) ∧ (4 ∨ ¬1 ∨ ¬2) ∧ 1 ∧ 2 needs all four steps of marking.
+    assert!(fragment::horn_sat(&cnf("-3 0\n3 -4 0\n4 -1 -2 0\n1 0\n2 0")).is_none());
+    let model = fragment::horn_sat(&cnf("3 -4 0\n4 -1 -2 0\n1 0\n2 0")).unwrap();
+    assert!(model.values().all(|&b| b));
+
+    let mut rng = Rng::new(17);
+    for _ in 0..300 {
+        let n = 2 + rng.below(10);
+        let ratio = 0.5 + 2.0 * rng.float();
+        let clauses = gen::random_ksat(&mut rng, 2.min(n), n, ratio);
+        assert_eq!(fragment::classify(&clauses), Fragment::TwoCnf);
+        let model = fragment::two_sat(&clauses);
+        assert_eq!(model.is_some(), brute::satisfiable_brute_force(clauses.clone()), "{}", clauses);
+        assert!(model.is_none_or(|m| clauses.is_satisfied_by(&m)));
+
+        // Horn, then renamed by flipping a random subset of the variables.
+        let horn = gen::random_ksat(&mut rng, 3.min(n), n, 2.0 + 3.0 * ratio)
+            .iter()
+            .map(|c| {
+                let mut head = false;
+                c.iter().map(|l| if l.is_negated() || head { Literal::neg(l.var_name().to_string()) } else { head = true; l.clone() }).collect::<Clause>()
+            })
+            .collect::<Clauses>();
+        let flips = (1..=n).filter(|_| rng.bool()).map(|v| v.to_string()).collect::<Vec<_>>();
+        let renamed = horn
+            .iter()
+            .map(|c| c.iter().map(|l| if flips.contains(&l.var_name().to_string()) { l.negate() } else { l.clone() }).collect::<Clause>())
+            .collect::<Clauses>();
+        for clauses in [horn, renamed] {
+            let expect = brute::satisfiable_brute_force(clauses.clone());
+            let solved = fragment::solve(&clauses).expect("a polynomial fragment");
+            assert_eq!(solved.is_some(), expect, "{}", clauses);
+            assert!(solved.is_none_or(|m| clauses.is_satisfied_by(&m)));
+        }
+    }
+
+    let general = gen::pigeonhole(3);
+    assert_eq!(fragment::classify(&general), Fragment::General);
+    assert!(fragment::solve(&general).is_none());
+}
+
+
+#[test]
+fn test_fragment_dispatch() {
+    // fragments never reach the given solver, general clauses do.
+    let refuse = SATSolver(|clauses| panic!("solver called on {}", clauses));
+    assert!(cnf("1 -2 0\n2 3 0").is_satisfiable(SATSolver(refuse.0)));
+    assert!(!cnf("1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0").is_satisfiable(SATSolver(refuse.0)));
+    assert!(!cnf("-3 0\n3 -4 0\n4 -1 -2 0\n1 0\n2 0").is_satisfiable(SATSolver(refuse.0)));
+    assert!(cnf("1 2 -3 0\n-1 0\n-2 3 -4 0").is_satisfiable(SATSolver(refuse.0)));
+    assert!(cnf("1 -1 0").is_valid(refuse));
+    // the wrong answer shows it comes from the solver.
+    assert!(gen::pigeonhole(3).is_satisfiable(SATSolver(|_| true)));
+}
+
+
+#[test]
+fn test_dnnf() {
+    // (1 ∨ 2) ∧ (3 ∨ 4): two components, each with three models.
+    let dnnf = Dnnf::compile(&cnf("1 2 0\n3 4 0"));
+    assert_eq!(dnnf.count(), 9);
+    assert_eq!(dnnf.condition(&lits(&["-1"])).count(), 3);
+    assert_eq!(dnnf.min_cardinality().map(|(n, _)| n), Some(2));
+    let nnf = dnnf.to_nnf();
+    let lines = nnf.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("nnf ") && lines[0].ends_with(" 4"));
+    assert_eq!(lines.len() - 1, lines[0].split(' ').nth(1).unwrap().parse::<usize>().unwrap());
+    assert!(lines[1..].iter().all(|l| l.starts_with("L ") || l.starts_with("A ") || l.starts_with("O ")));
+    assert_eq!(Dnnf::compile(&cnf("1 0\n-1 0")).to_nnf(), "nnf 1 0 1\nO 0 0\n");
+
+    let mut rng = Rng::new(23);
+    for _ in 0..100 {
+        let n = 3 + rng.below(8);
+        let ratio = 1.0 + 3.0 * rng.float();
+        let clauses = gen::random_ksat(&mut rng, 3, n, ratio);
+        let dnnf = Dnnf::compile(&clauses);
+        let models = brute::models(&clauses);
+        assert_eq!(dnnf.count(), models.len() as u128, "{}", clauses);
+        assert_eq!(dnnf.is_satisfiable(), !models.is_empty());
+
+        let mut enumerated = dnnf.models().iter().map(|m| format!("{:?}", m.iter().collect::<BTreeMap<_, _>>())).collect::<Vec<_>>();
+        let mut expected = models.iter().map(|m| format!("{:?}", m.iter().collect::<BTreeMap<_, _>>())).collect::<Vec<_>>();
+        enumerated.sort();
+        expected.sort();
+        assert_eq!(enumerated, expected);
+
+        let cardinality = |m: &HashMap<String, bool>| m.values().filter(|&&b| b).count();
+        match dnnf.min_cardinality() {
+            Some((k, model)) => {
+                assert!(clauses.is_satisfied_by(&model));
+                assert_eq!(cardinality(&model), k);
+                assert_eq!(Some(k), models.iter().map(cardinality).min());
+            },
+            None => assert!(models.is_empty()),
+        }
+
+        let vars = dnnf.vars();
+        if vars.is_empty() {
+            continue;
+        }
+        let var = vars[rng.below(vars.len())].to_string();
+        let literal = if rng.bool() { Literal::pos(var.clone()) } else { Literal::neg(var.clone()) };
+        let conditioned = dnnf.condition(std::slice::from_ref(&literal));
+        assert_eq!(conditioned.count(), models.iter().filter(|m| literal.is_satisfied_by(m)).count() as u128);
+        assert!(!conditioned.vars().contains(&var.as_str()));
+    }
+}
+
+
+#[test]
+fn test_symmetry_breaking() {
+    // S_4 on the pigeons times S_3 on the holes: every pigeon can go anywhere.
+    let clauses = gen::pigeonhole(3);
+    let generators = symmetry::generators(&clauses);
+    assert!(generators.len() >= 2);
+    assert!(generators.iter().all(|g| g.preserves(&clauses)));
+    let mut orbit = HashSet::new();
+    let mut queue = vec![Literal::pos("p0h0".to_string())];
+    while let Some(literal) = queue.pop() {
+        if orbit.insert(literal.to_string()) {
+            queue.extend(generators.iter().map(|g| g.apply(&literal)));
+        }
+    }
+    assert_eq!(orbit.len(), 12);
+    assert!(orbit.iter().all(|l| !l.starts_with('¬')));
+
+    // breaking keeps satisfiability and makes pigeonhole easy.
+    let clauses = gen::pigeonhole(8);
+    let mut solver = Solver::from_clauses(&symmetry::break_symmetries(&clauses));
+    assert!(!solver.solve());
+    assert!(solver.stats.conflicts < 200, "{} conflicts", solver.stats.conflicts);
+
+    // 18 proper 3-colourings of a 4-cycle, fewer are left but not none.
+    let clauses = gen::colouring(&Graph::cycle(4), 3);
+    let mut solver = Solver::from_clauses(&symmetry::break_symmetries(&clauses));
+    let vars = (0..4).flat_map(|v| (0..3).map(move |c| format!("v{}c{}", v, c))).map(|n| solver.var(&n)).collect::<Vec<_>>();
+    let projected = enumerate(&mut solver, &vars);
+    assert_eq!(brute::count(&clauses), 18);
+    assert!(!projected.is_empty() && projected.len() < 18, "{} models left", projected.len());
+
+    // fresh variables stay apart from the ones of the clauses, even named like them.
+    let clauses = cnf("1 2 0\n-1 -2 0\n_sb0_0 0\n_sb0_1 _sb1_0 0");
+    let generators = symmetry::generators(&clauses);
+    let predicates = symmetry::lex_leader(&clauses, &generators);
+    assert!(!predicates.is_empty());
+    let names = predicates.iter().flat_map(|c| c.iter().map(|l| l.var_name().to_string())).collect::<HashSet<_>>();
+    let moved = |n: &String| generators.iter().any(|g| g.apply(&Literal::pos(n.clone())) != Literal::pos(n.clone()));
+    assert!(names.iter().all(|n| moved(n) || n.starts_with("__sb")), "{:?}", names);
+    assert!(names.contains("__sb0_0") && !names.contains("_sb0_0"));
+
+    let mut rng = Rng::new(40);
+    let mut instances = vec![gen::colouring(&Graph::complete(4), 3), gen::colouring(&Graph::cycle(5), 2)];
+    instances.push(gen::tseitin_parity(&Graph::cycle(6), &[true, false, false, false, false, false]));
+    instances.push(gen::tseitin_parity(&Graph::cycle(6), &[true, true, false, false, false, false]));
+    for _ in 0..40 {
+        instances.push(gen::random_ksat(&mut rng, 2, 6, 1.5));
+    }
+    for clauses in instances {
+        let generators = symmetry::generators(&clauses);
+        assert!(generators.iter().all(|g| g.preserves(&clauses)), "{}", clauses);
+        let broken = symmetry::break_symmetries(&clauses);
+        let mut solver = Solver::from_clauses(&broken);
+        assert_eq!(solver.solve(), brute::satisfiable_brute_force(clauses.clone()), "{}", clauses);
+        if solver.is_ok() && solver.solve() {
+            assert!(clauses.is_satisfied_by(&solver.model()));
+        }
+    }
+}
+
+
+#[test]
+fn test_approximate_counting() {
+    let mut rng = Rng::new(29);
+    let within = |estimate: u128, exact: u64, epsilon: f64| {
+        let (estimate, exact) = (estimate as f64, exact as f64);
+        exact / (1.0 + epsilon) <= estimate && estimate <= exact * (1.0 + epsilon)
+    };
+
+    // few models are counted exactly.
+    let clauses = cnf("1 2 0\n-1 -2 0\n3 0");
+    assert_eq!(approxmc::approx_count(&clauses, 0.8, 0.2, &mut rng), 2);
+    assert_eq!(approxmc::approx_count(&cnf("1 0\n-1 0"), 0.8, 0.2, &mut rng), 0);
+
+    for _ in 0..2 {
+        let (clauses, _) = gen::planted_ksat(&mut rng, 3, 16, 1.5);
+        let exact = brute::count(&clauses);
+        let estimate = approxmc::approx_count(&clauses, 0.8, 0.2, &mut rng);
+        assert!(within(estimate, exact, 0.8), "{} for {} models", estimate, exact);
+    }
+
+    // projected on 1..=4, the other variables only multiply the models.
+    let clauses = cnf("1 2 0\n3 4 5 6 7 8 9 10 11 12 0");
+    let sampling = (1..=4).map(|v| v.to_string()).collect::<Vec<_>>();
+    assert_eq!(approxmc::approx_count_projected(&clauses, Some(&sampling), 0.8, 0.2, &mut rng), 12);
+    let estimate = approxmc::approx_count(&clauses, 0.8, 0.2, &mut rng);
+    assert!(within(estimate, brute::count(&clauses), 0.8), "{}", estimate);
+}
+
+
+#[test]
+fn test_counting_many_variables() {
+    // 2^10 models over 140 variables, 130 of them fixed: the hashes range over all 140.
+    let mut rng = Rng::new(31);
+    let source = (11..=140).map(|v| format!("{} 0", v)).collect::<Vec<_>>().join("\n");
+    let sampling = (1..=140).map(|v| v.to_string()).collect::<Vec<_>>();
+    let clauses = cnf(&source);
+    let estimate = approxmc::approx_count_projected(&clauses, Some(&sampling), 0.8, 0.2, &mut rng);
+    assert!((1024.0 / 1.8..=1024.0 * 1.8).contains(&(estimate as f64)), "{}", estimate);
+
+    let mut sampler = Sampler::projected(&clauses, Some(&sampling), 3.0, Rng::new(3));
+    let samples = sampler.samples(20);
+    assert!(samples.len() >= 15);
+    assert!(samples.iter().all(|m| m.len() == 140 && clauses.is_satisfied_by(m)));
+}
+
+
+#[test]
+fn test_uniform_sampling() {
+    // with few models the sampler picks among all of them.
+    let clauses = cnf("1 2 0\n-1 -2 0");
+    let mut sampler = Sampler::new(&clauses, 3.0, Rng::new(1));
+    let samples = sampler.samples(200);
+    assert_eq!(samples.len(), 200);
+    let ones = samples.iter().filter(|m| m["1"]).count();
+    assert!((70..130).contains(&ones), "{}", ones);
+
+    // 2^11 - 2^8 models: cells have to be cut by hashing. Half the models have 1 true.
+    let clauses = cnf("1 2 3 0\n4 5 6 7 8 9 10 11 0");
+    let mut sampler = Sampler::new(&clauses, 3.0, Rng::new(2));
+    let samples = sampler.samples(100);
+    assert!(samples.len() >= 90);
+    assert!(samples.iter().all(|m| clauses.is_satisfied_by(m)));
+    let ones = samples.iter().filter(|m| m["1"]).count() as f64 / samples.len() as f64;
+    let exact = brute::models(&clauses).iter().filter(|m| m["1"]).count() as f64 / brute::count(&clauses) as f64;
+    assert!((ones - exact).abs() < 0.2, "{} against {}", ones, exact);
+    let distinct = samples.iter().map(|m| format!("{:?}", m.iter().collect::<BTreeMap<_, _>>())).collect::<HashSet<_>>();
+    assert!(distinct.len() > 50, "{} distinct samples", distinct.len());
+}
@@ -1,20 +1,35 @@
 extern crate theorem_prover;
 use theorem_prover::sat;
-use theorem_prover::sat::clauses::SATSolver;
+use theorem_prover::sat::clauses::{Clause, Clauses, Literal, SATSolver};
+use theorem_prover::sat::dp::{self, Certificate};
+use theorem_prover::sat::gen::{self, Rng};
 use theorem_prover::sat::dimacs;
+use theorem_prover::sat::stats::{Event, Recorder, Stats};
+use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 
 
+fn solvers() -> Vec<(&'static str, SATSolver)> {
+    vec![
+        ("dp", SATSolver(sat::dp::satisfiable_dp)),
+        ("cdcl", SATSolver(sat::cdcl::satisfiable_cdcl)),
+        ("brute", SATSolver(sat::brute::satisfiable_brute_force)),
+    ]
+}
+
+
 fn satlib_runfile(path: &Path, expect: bool) {
     println!("CNF file: {}", path.display());
     let reader = BufReader::new(File::open(path).unwrap());
     let clauses = dimacs::parse(reader).expect("Failed to parse");
-    let sat = clauses.is_satisfiable(SATSolver(sat::dp::satisfiable_dp));
-    println!("  |-sat-----> {:?}, should be {:?}", sat, expect);
-    assert_eq!(sat, expect);
+    for (name, solver) in solvers() {
+        let sat = solver.0(clauses.clone());
+        println!("  |-{:-<8}> {:?}, should be {:?}", name, sat, expect);
+        assert_eq!(sat, expect, "{} on {}", name, path.display());
+    }
 }
 
 
@@ -29,8 +44,135 @@ fn satlib_run(dir: &Path, expect: bool) {
 }
 
 
+#[test]
+fn test_simple_sat() {
+    let dir = Path::new("tests/fixtures/simple-sat") ;
+    satlib_run(dir, true);
+}
+
+
 #[test]
 fn test_simple_unsat() {
     let dir = Path::new("tests/fixtures/simple-unsat") ;
-    satlib_run(&dir, false);
+    satlib_run(dir, false);
+}
+
+
+#[test]
+fn test_dp_stats_match_trace() {
+    for entry in fs::read_dir("tests/fixtures/simple-unsat").unwrap() {
+        let path = entry.unwrap().path();
+        let reader = BufReader::new(File::open(&path).unwrap());
+        let clauses = dimacs::parse(reader).expect("Failed to parse");
+        let mut stats = Stats::new();
+        let mut recorder = Recorder::default();
+        let sat = sat::dp::satisfiable_dp_with(clauses, &mut stats, &mut recorder);
+        assert!(!sat, "{}", path.display());
+        assert_eq!(stats.conflicts, 1);
+        assert_eq!(stats.decisions, 0);
+        let count = |f: fn(&Event) -> bool| recorder.0.iter().filter(|e| f(e)).count() as u64;
+        assert_eq!(count(|e| matches!(e, Event::Propagation { .. })), stats.propagations);
+        assert_eq!(count(|e| matches!(e, Event::PureLiteral { .. })), stats.pure_literals);
+        assert_eq!(count(|e| matches!(e, Event::Eliminated { .. })), stats.eliminated_vars);
+        assert_eq!(recorder.0.last(), Some(&Event::Conflict { level: 0 }));
+    }
+}
+
+
+#[test]
+fn test_dp_counts_kept_resolvents() {
+    // of the four pairs on either variable, two resolve to tautologies and are not counted.
+    let clauses = dimacs::parse("1 2 0\n-1 -2 0\n1 -2 0\n-1 2 0".as_bytes()).unwrap();
+    let mut stats = Stats::new();
+    let mut recorder = Recorder::default();
+    assert!(!sat::dp::satisfiable_dp_with(clauses, &mut stats, &mut recorder));
+    let eliminated = recorder.0.iter().filter(|e| matches!(e, Event::Eliminated { .. })).collect::<Vec<_>>();
+    assert!(!eliminated.is_empty());
+    assert!(eliminated.iter().all(|e| matches!(e, Event::Eliminated { resolvents: 2, .. })));
+    assert_eq!(stats.resolvents, 2 * eliminated.len() as u64);
+}
+
+
+fn check_certificate(clauses: &Clauses) -> bool {
+    match dp::certify_dp(clauses.clone()) {
+        Certificate::Sat { model, .. } => {
+            assert!(clauses.is_satisfied_by(&model), "{:?} is no model of {}", model, clauses);
+            true
+        },
+        Certificate::Unsat(refutation) => {
+            assert!(refutation.check(clauses), "invalid refutation of {}\n{}", clauses, refutation);
+            false
+        },
+    }
+}
+
+
+#[test]
+fn test_dp_certificates() {
+    for (dir, expect) in [("tests/fixtures/simple-sat", true), ("tests/fixtures/simple-unsat", false)] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let clauses = dimacs::parse(BufReader::new(File::open(&path).unwrap())).expect("Failed to parse");
+            assert_eq!(check_certificate(&clauses), expect, "{}", path.display());
+        }
+    }
+
+    let mut rng = Rng::new(5);
+    for _ in 0..100 {
+        let clauses = gen::random_ksat(&mut rng, 3, 8, 4.5);
+        assert_eq!(check_certificate(&clauses), sat::brute::satisfiable_brute_force(clauses.clone()));
+    }
+    assert!(!check_certificate(&gen::pigeonhole(3)));
+
+    // a tampered proof is rejected.
+    if let Certificate::Unsat(mut refutation) = dp::certify_dp(gen::pigeonhole(2)) {
+        let last = refutation.0.len() - 1;
+        if let dp::Derivation::Resolvent { parents, .. } = &mut refutation.0[last] {
+            parents.1 = parents.0;
+        }
+        assert!(!refutation.check(&gen::pigeonhole(2)));
+    } else {
+        panic!("pigeonhole is unsatisfiable");
+    }
+}
+
+
+// clauses over `n` variables that may hold a variable in both polarities.
+fn random_with_tautologies(rng: &mut Rng, n: usize, m: usize) -> Clauses {
+    (0..m)
+        .map(|_| (0..1 + rng.below(3))
+            .map(|_| {
+                let var = format!("x{}", rng.below(n));
+                if rng.bool() { Literal::pos(var) } else { Literal::neg(var) }
+            })
+            .collect::<Clause>())
+        .collect()
+}
+
+
+#[test]
+fn test_dp_tautological_input() {
+    // satisfiable with the third variable false, the tautologies must not be resolved on.
+    let clauses = dimacs::parse("1 -3 -2 0\n1 -1 0\n1 -3 0\n-3 -1 0\n3 -3 0".as_bytes()).unwrap();
+    assert!(sat::dp::satisfiable_dp(clauses.clone()));
+    assert!(check_certificate(&clauses));
+
+    let mut rng = Rng::new(11);
+    for _ in 0..2000 {
+        let m = 1 + rng.below(6);
+        let clauses = random_with_tautologies(&mut rng, 3, m);
+        assert_eq!(check_certificate(&clauses), sat::brute::satisfiable_brute_force(clauses.clone()), "{}", clauses);
+    }
+
+    // resolving a tautology with a clause on its own variable gives back that clause, not
+    // the empty clause.
+    let p = || Literal::pos("p".to_string());
+    let n = || Literal::neg("p".to_string());
+    let clauses = Clauses(vec![Clause(vec![p(), n()].into_iter().collect()), Clause(vec![n()].into_iter().collect())]);
+    let refutation = dp::Refutation(vec![
+        dp::Derivation::Input(clauses.0[0].clone()),
+        dp::Derivation::Input(clauses.0[1].clone()),
+        dp::Derivation::Resolvent { clause: Clause(HashSet::new()), pivot: "p".to_string(), parents: (0, 1) },
+    ]);
+    assert!(!refutation.check(&clauses));
 }
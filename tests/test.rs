@@ -1,6 +1,8 @@
 extern crate theorem_prover;
+use std::path::Path;
 use theorem_prover::fol::ast::Formula;
-use theorem_prover::fol::parser;
+use theorem_prover::fol::parser::{self, ErrorKind};
+use theorem_prover::fol::problem::{Problem, ProblemError, Role};
 use theorem_prover::fol::ast;
 use theorem_prover::fol::ast::Cnf;
 use theorem_prover::fol::ast::Grounded;
@@ -25,6 +27,155 @@ fn test_parse() {
 }
 
 
+#[test]
+fn test_precedence() {
+    type F = Formula<ast::Raw>;
+    let p = |name: &str| F::pred(name, vec![ast::Term::var("x")]);
+    let x = || ast::Var::from_string("x".to_string());
+    // the display parenthesizes every connective, so equal strings are equal trees.
+    let parses_as = |input: &str, expected: F| assert_eq!(parser::parse(input).unwrap().to_string(), expected.to_string(), "{}", input);
+
+    parses_as("P(x) and Q(x) or R(x)", F::or(F::and(p("P"), p("Q")), p("R")));
+    parses_as("P(x) or Q(x) and R(x)", F::or(p("P"), F::and(p("Q"), p("R"))));
+    parses_as("not P(x) or Q(x)", F::or(F::not(p("P")), p("Q")));
+    parses_as("not not P(x) and Q(x)", F::and(F::not(F::not(p("P"))), p("Q")));
+    parses_as("P(x) or Q(x) => R(x) and S(x)", F::implies(F::or(p("P"), p("Q")), F::and(p("R"), p("S"))));
+    parses_as("P(x) => Q(x) <=> R(x) => S(x)", F::iff(F::implies(p("P"), p("Q")), F::implies(p("R"), p("S"))));
+    // `=>` associates to the right, the others to the left.
+    parses_as("P(x) => Q(x) => R(x)", F::implies(p("P"), F::implies(p("Q"), p("R"))));
+    parses_as("P(x) and Q(x) and R(x)", F::and(F::and(p("P"), p("Q")), p("R")));
+    parses_as("P(x) or Q(x) or R(x)", F::or(F::or(p("P"), p("Q")), p("R")));
+    parses_as("P(x) <=> Q(x) <=> R(x)", F::iff(F::iff(p("P"), p("Q")), p("R")));
+    parses_as("(P(x) => Q(x)) => R(x)", F::implies(F::implies(p("P"), p("Q")), p("R")));
+    parses_as("P(x) and (Q(x) or R(x))", F::and(p("P"), F::or(p("Q"), p("R"))));
+    // quantifier bodies extend as far right as possible.
+    parses_as("forall x . P(x) and Q(x)", F::forall(x(), F::and(p("P"), p("Q"))));
+    parses_as("P(x) and exists x . Q(x) or R(x)", F::and(p("P"), F::exists(x(), F::or(p("Q"), p("R")))));
+    parses_as("not forall x . P(x) => Q(x)", F::not(F::forall(x(), F::implies(p("P"), p("Q")))));
+    parses_as("(forall x . P(x)) and Q(x)", F::and(F::forall(x(), p("P")), p("Q")));
+
+    assert!(parser::parse("P(x) and").is_err());
+    assert!(parser::parse("P(x) Q(x)").is_err());
+    assert!(parser::parse("(P(x) or Q(x)").is_err());
+    assert!(parser::parse("P(x))").is_err());
+}
+
+
+#[test]
+fn test_parser_errors() {
+    let error = |input: &str| parser::parse(input).unwrap_err();
+    let at = |e: &parser::ParserError| (e.span.line, e.span.column, e.expected.join(" "));
+
+    let e = error("forall x P(x)");
+    assert_eq!(e.kind, ErrorKind::Unexpected("P".to_string()));
+    assert_eq!(at(&e), (1, 10, ".".to_string()));
+    let e = error("exists x . P(x) Q(x)");
+    assert_eq!(at(&e), (1, 17, "<=> <end of input> => and or".to_string()));
+    assert_eq!((e.span.start, e.span.end), (16, 17));
+    let e = error("P(x) and\n  Q(x");
+    assert_eq!(e.kind, ErrorKind::EndOfTokens);
+    assert_eq!(at(&e), (2, 6, ") ,".to_string()));
+    assert_eq!(e.span.start, 14);
+    let e = error("P(x) or\n  forall f(y) . Q(y)");
+    assert_eq!(e.kind, ErrorKind::WrongTerm);
+    assert_eq!(at(&e), (2, 10, "<variable>".to_string()));
+    assert_eq!(e.span.end - e.span.start, 4);
+    let e = error("not");
+    assert_eq!(at(&e), (1, 4, "( <identifier> exists forall not".to_string()));
+    let e = error("(P(x) or Q(x)");
+    assert_eq!(at(&e), (1, 14, ") <=> => and or".to_string()));
+    let e = error("P(x,)");
+    assert_eq!(at(&e), (1, 5, "<term>".to_string()));
+    // columns count characters, spans bytes.
+    let e = error("P(x) ∧ Q(x)");
+    assert_eq!(e.kind, ErrorKind::LexerError('∧'));
+    assert_eq!((e.span.column, e.span.start, e.span.end), (6, 5, 8));
+    assert!(e.expected.is_empty());
+
+    assert_eq!(error("P(x) Q(x)").to_string(), "1:6: unexpected \"Q\", expected one of \"<=>\", <end of input>, \"=>\", \"and\", \"or\"");
+    let source = "forall x .\n\tP(x) and f(x, y)) or Q(y)";
+    assert_eq!(error(source).render(source), [
+        "error: unexpected \")\", expected one of \"<=>\", <end of input>, \"=>\", \"and\", \"or\"",
+        "  --> line 2, column 18",
+        "  |",
+        "2 | \tP(x) and f(x, y)) or Q(y)",
+        "  | \t                ^",
+        "",
+    ].join("\n"));
+}
+
+
+#[test]
+fn test_constants() {
+    let display = |input: &str| parser::parse(input).unwrap().to_string();
+    let free = |input: &str| {
+        let mut vars = parser::parse(input).unwrap().free_vars().into_iter().map(|v| v.name).collect::<Vec<_>>();
+        vars.sort();
+        vars
+    };
+    assert_eq!(display("forall x1 . P_2(x1, f'(x1), 0, 'John Smith', a())"), "(∀x1.P_2(x1,f'(x1),0,'John Smith',a()))");
+    assert_eq!(display("'and'(_x) or 'it\\'s'('a\\\\b')"), "('and'(_x) ∨ it's('a\\\\b'))");
+    assert_eq!(display("P()=>Q()<=>R(x)"), "((P → Q) ⇔  R(x))");
+    // nullary predicates are displayed without parentheses and read back the same.
+    assert_eq!(display("P => Q() <=> R(x)"), "((P → Q) ⇔  R(x))");
+    for atom in ["P()", "'and'()", "'John Smith'()"] {
+        assert_eq!(display(&display(atom)), display(atom));
+    }
+    assert_eq!(free("P(x, a(), 0, 'b') and Q(f(y), 'g'(z))"), ["x", "y", "z"]);
+    assert_eq!(free("forall x . P(x, c())"), Vec::<String>::new());
+
+    let e = parser::parse("P('a").unwrap_err();
+    assert_eq!((e.kind, e.span.column, e.span.end), (ErrorKind::LexerError('\''), 3, 4));
+    assert_eq!(e.expected, ["'"]);
+    for bound in ["forall 0 . P(0)", "exists a() . P(a())", "forall 'x' . P(x)"] {
+        assert_eq!(parser::parse(bound).unwrap_err().kind, ErrorKind::WrongTerm);
+    }
+
+    let skolemized = |input: &str| parser::parse(input).unwrap().to_nnf().to_pnf().skolemize();
+    let t = skolemized("exists x . P(x)");
+    assert_eq!(t.to_string(), "P(sk0())");
+    assert!(t.free_vars().is_empty());
+    assert_eq!(skolemized("exists x . P(x) and Q(sk0(), sk2(x))").to_string(), "(P(sk1()) ∧ Q(sk0(),sk2(sk1())))");
+    assert_eq!(skolemized("forall y . exists x . R(x, y)").to_string(), "(∀y.R(sk0(y),y))");
+    satisfiable("P(a()) and not P(b())", true);
+}
+
+
+#[test]
+fn test_problem() {
+    let problem = Problem::load("tests/fixtures/fol/subset.p").unwrap();
+    let names = problem.statements.iter().map(|s| (s.role, s.name.as_str())).collect::<Vec<_>>();
+    assert_eq!(names, [
+        (Role::Axiom, "a_subset_b"),
+        (Role::Axiom, "b_subset_c"),
+        (Role::Hypothesis, "a_in_A"),
+        (Role::Conjecture, "a in C"),
+    ]);
+    let b_subset_c = problem.get("b_subset_c").unwrap();
+    assert_eq!(b_subset_c.file.as_deref(), Some(Path::new("tests/fixtures/fol/axioms/subset.p")));
+    assert_eq!((b_subset_c.span.line, b_subset_c.span.column), (3, 1));
+    assert_eq!(b_subset_c.formula.to_string(), "(∀x.(B(x) → C(x)))");
+    let goal = problem.get("a in C").unwrap();
+    assert_eq!((goal.span.line, goal.span.column, goal.span.end - goal.span.start), (5, 1, 28));
+    assert_eq!(problem.assumptions().count(), 3);
+    assert_eq!(problem.to_formula().unwrap().to_string(), "((((∀x.(A(x) → B(x))) ∧ (∀x.(B(x) → C(x)))) ∧ A(a())) → C(a()))");
+
+    let problem = Problem::parse("axiom p: P(a()). % no conjecture\nnegated_conjecture q: not P(a()).").unwrap();
+    assert!(problem.get("q").unwrap().file.is_none());
+    assert_eq!(problem.to_formula().unwrap().to_string(), "(P(a()) ∧ ¬P(a()))");
+    assert!(Problem::parse("% nothing").unwrap().to_formula().is_none());
+
+    let error = |result: Result<Problem, ProblemError>| result.unwrap_err().to_string();
+    assert_eq!(error(Problem::parse("P(a()).")), "1:1: unexpected \"P\", expected one of \"axiom\", \"conjecture\", \"definition\", \"hypothesis\", \"include\", \"lemma\", \"negated_conjecture\"");
+    assert_eq!(error(Problem::parse("axiom p: P(a())")), "1:16: unexpected end of input, expected one of \".\", \"<=>\", \"=>\", \"and\", \"or\"");
+    assert_eq!(error(Problem::parse("axiom: P(a()).")), "1:6: unexpected \":\", expected <name>");
+    assert_eq!(error(Problem::load("tests/fixtures/fol/broken.p")), "tests/fixtures/fol/broken.p:2:25: unexpected \".\", expected one of \"(\", <identifier>, \"exists\", \"forall\", \"not\"");
+    assert_eq!(error(Problem::load("tests/fixtures/fol/duplicate.p")), "tests/fixtures/fol/duplicate.p:2:1: duplicate name \"b_subset_c\"");
+    assert_eq!(error(Problem::load("tests/fixtures/fol/cycle.p")), "tests/fixtures/fol/cycle.p: included by itself");
+    assert!(matches!(Problem::parse("include 'tests/fixtures/fol/missing.p'."), Err(ProblemError::Io { .. })));
+}
+
+
 #[test]
 fn test_substitution() {
     fn substitute(input: &str, from: &str, to: &str) {
@@ -79,7 +230,7 @@ fn satisfiable(input: &str, is_satisfiable: bool) {
     let sat = clauses.is_satisfiable(SATSolver(sat::dp::satisfiable_dp));
     println!("  +-sat-----> {:?}, should be {:?}", sat, is_satisfiable);
     assert_eq!(sat, is_satisfiable);
-    println!("");
+    println!();
 }
 
 
@@ -90,7 +241,7 @@ fn tautology(input: &str, is_valid: bool) {
     let valid = clauses.is_valid(SATSolver(sat::dp::satisfiable_dp));
     println!("  +-taut----> {:?}, should be {:?}", valid, is_valid);
     assert_eq!(valid, is_valid);
-    println!("");
+    println!();
 }
 
 
@@ -112,5 +263,63 @@ fn test_satisfiable_unsatisfiable() {
     satisfiable("(P(x)) and (not P(x) or Q(x)) and (not Q(x))", false);
     satisfiable("(P(x) or Q(x)) and (not P(x) or Q(x)) and (P(x) or not Q(x)) and (not P(x) or not Q(x))", false);
     satisfiable("(P(x) or Q(x)) and (not Q(x)) and (not P(x))", false);
-    satisfiable("(P(x) or not P(x)) and (not P(a) or P(b)) and P(a)", false);
+    satisfiable("(P(x) or not P(x)) and (not P(a) or P(b)) and P(a)", true);
+    satisfiable("(P(x) or not P(x)) and (not P(a) or P(b)) and P(a) and not P(b)", false);
+}
+
+
+#[test]
+fn test_distinct_atoms() {
+    // atoms that differ in names, arguments or variables stay different symbols.
+    satisfiable("P_2(a()) and not P(2, a())", true);
+    satisfiable("P(a_b()) and not P(a(b()))", true);
+    satisfiable("P(x1) and not P(x1())", true);
+}
+
+
+#[test]
+fn test_clauses_formula_roundtrip() {
+    let clauses = sat::clauses::Clauses::from_formula(to_cnf("(P(x) or not Q(a)) and R(f(x), y)"));
+    let symbols = |c: &sat::clauses::Clauses| {
+        let mut symbols = c.iter().flat_map(|c| c.iter().map(|l| l.to_string())).collect::<Vec<_>>();
+        symbols.sort();
+        symbols
+    };
+    assert_eq!(symbols(&clauses), ["-Q(a)", "P(x)", "R(f(x),y)"]);
+    let read = sat::clauses::Clauses::from_formula(clauses.to_formula());
+    assert_eq!(symbols(&read), symbols(&clauses));
+}
+
+
+#[test]
+fn test_satisfiable_with_resolution() {
+    // resolving on P and Q against the same clauses at once derives Q ∨ ¬Q and P ∨ ¬P,
+    // losing the models P = Q.
+    satisfiable("((not P(x)) or Q(x)) and ((not Q(x)) or P(x))", true);
+    satisfiable("(P(x) or Q(x)) and ((not P(x)) or R(x)) and ((not Q(x)) or (not R(x)))", true);
+    satisfiable("(P(x) or Q(x)) and ((not P(x)) or R(x)) and ((not Q(x)) or R(x)) and ((not R(x)) or P(x))", true);
+}
+
+
+#[test]
+fn test_resolution_rule() {
+    let cnf = |s: &str| sat::clauses::Clauses::from_dimacs(s.as_bytes()).unwrap();
+    let names = |c: &sat::clauses::Clauses| {
+        let mut names = c.iter().map(|c| {
+            let mut literals = c.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+            literals.sort();
+            literals.join(" ")
+        }).collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+    // one symbol per application, the one with the fewest resolvents: 2 has none.
+    let resolved = sat::dp::resolution_rule(cnf("1 2 0\n-1 3 0\n-3 4 0")).unwrap();
+    assert_eq!(names(&resolved), ["-1 3", "-3 4"]);
+    // 3 goes with its clause, and 2 subsumes 1 ∨ 2.
+    let resolved = sat::dp::resolution_rule(cnf("1 2 0\n-1 -2 0\n2 0\n2 3 0")).unwrap();
+    assert_eq!(names(&resolved), ["-1 -2", "2"]);
+    // the only resolvent of 1 ∨ 2 and ¬1 ∨ ¬2 is a tautology.
+    assert!(sat::dp::resolution_rule(cnf("1 2 0\n-1 -2 0")).unwrap().is_empty());
+    assert!(sat::dp::resolution_rule(cnf("")).is_err());
 }
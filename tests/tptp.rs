@@ -0,0 +1,201 @@
+extern crate theorem_prover;
+use theorem_prover::fol::ast::{Formula, Raw};
+use theorem_prover::fol::parser::{self, ErrorKind};
+use theorem_prover::fol::problem::{Problem, ProblemError, Role};
+use theorem_prover::fol::szs::{self, Status};
+use theorem_prover::fol::tptp;
+use theorem_prover::sat;
+use theorem_prover::sat::clauses::{Clause, Clauses, Literal, SATSolver};
+use std::path::Path;
+
+
+fn show(input: &str) -> String {
+    tptp::parse_formula(input).unwrap().to_string()
+}
+
+
+// for propositional formulas, where grounding is exact.
+fn valid(formula: Formula<Raw>) -> bool {
+    let negated = Formula::not(formula).to_nnf().to_pnf().skolemize().ground().to_cnf();
+    !Clauses::from_formula(negated).is_satisfiable(SATSolver(sat::dp::satisfiable_dp))
+}
+
+
+#[test]
+fn test_connectives() {
+    assert_eq!(show("p & q & r"), "((p ∧ q) ∧ r)");
+    assert_eq!(show("p | (q & r) | s"), "((p ∨ (q ∧ r)) ∨ s)");
+    assert_eq!(show("p => q"), "(p → q)");
+    assert_eq!(show("p <= q"), "(q → p)");
+    assert_eq!(show("p <=> q"), "(p ⇔  q)");
+    assert_eq!(show("p <~> q"), "¬(p ⇔  q)");
+    assert_eq!(show("p ~| q"), "¬(p ∨ q)");
+    assert_eq!(show("p ~& q"), "¬(p ∧ q)");
+    assert_eq!(show("~ ~p & q"), "(¬¬p ∧ q)");
+    // quantifiers and negation take a unitary formula.
+    assert_eq!(show("! [X, Y] : p(X, Y) & q"), "((∀X.(∀Y.p(X,Y))) ∧ q)");
+    assert_eq!(show("? [X] : ~ (p(X) | q(f(X)))"), "(∃X.¬(p(X) ∨ q(f(X))))");
+    assert_eq!(show("~ a = b"), "¬'='(a(),b())");
+
+    for (input, at, expected) in [
+        ("p & q | r", 7, "& <= <=> <end of input> <~> => | ~& ~|"),
+        ("p => q => r", 8, "& <= <=> <end of input> <~> => | ~& ~|"),
+        ("(p <=> q", 9, "& ) <= <=> <~> => | ~& ~|"),
+        ("! X : p(X)", 3, "["),
+        ("! [x] : p(x)", 4, "<variable>"),
+        ("X & p", 3, "!= ="),
+        ("& p", 1, "! ( <atom> ? ~"),
+        ("p(a,)", 5, "<term>"),
+    ] {
+        let e = tptp::parse_formula(input).unwrap_err();
+        assert_eq!((e.span.column, e.expected.join(" ")), (at, expected.to_string()), "{}", input);
+    }
+    let e = tptp::parse_formula("p('a").unwrap_err();
+    assert_eq!((e.kind, e.span.column), (ErrorKind::LexerError('\''), 3));
+    let e = tptp::parse_formula("p /* q").unwrap_err();
+    assert_eq!(e.expected, ["*/"]);
+}
+
+
+#[test]
+fn test_terms() {
+    // variables are closed over, names are constants.
+    assert_eq!(show("p(X, a, f(X, 'b c'), 'Y')"), "(∀X.p(X,a(),f(X,'b c'),Y()))");
+    assert_eq!(show("X = a | f(X) != g(Y)"), "(∀X.(∀Y.('='(X,a()) ∨ ¬'='(f(X),g(Y)))))");
+    assert_eq!(show("p(1, -2.5e3, 3/4, +7, \"Obj\", $sum(1, 2))"), "p(1,'-2.5e3','3/4','+7','\"Obj\"','$sum'(1,2))");
+    assert_eq!(show("'p q'(a) & 'r'"), "('p q'(a()) ∧ r)");
+
+    assert_eq!(show("$true & ~ $false"), "(('$truth' ∨ ¬'$truth') ∧ ¬('$truth' ∧ ¬'$truth'))");
+    assert!(valid(tptp::parse_formula("$true").unwrap()));
+    assert!(valid(tptp::parse_formula("~ $false").unwrap()));
+    assert!(valid(tptp::parse_formula("(p => $false) <=> ~ p").unwrap()));
+    assert!(!valid(tptp::parse_formula("$false | p").unwrap()));
+    assert!(valid(tptp::parse_formula("(p <~> q) <=> ((p ~& q) & ~ (p ~| q))").unwrap()));
+    assert!(valid(tptp::parse_formula("(p <= q) <=> (~ p => ~ q)").unwrap()));
+}
+
+
+#[test]
+fn test_problems() {
+    let problem = tptp::load("tests/fixtures/tptp/SET001+1.p").unwrap();
+    let names = problem.statements.iter().map(|s| (s.role, s.name.as_str())).collect::<Vec<_>>();
+    assert_eq!(names, [
+        (Role::Axiom, "subset_def"),
+        (Role::Axiom, "equal_def"),
+        (Role::Hypothesis, "a_subset_b"),
+        (Role::Hypothesis, "b_subset_c"),
+        (Role::Conjecture, "transitivity of subset"),
+    ]);
+    let equal_def = problem.get("equal_def").unwrap();
+    assert_eq!(equal_def.file.as_deref(), Some(Path::new("tests/fixtures/tptp/Axioms/SET000-0.ax")));
+    assert_eq!((equal_def.span.line, equal_def.span.column), (7, 1));
+    assert_eq!(equal_def.formula.to_string(), "(∀A.(∀B.('='(A,B) ⇔  (subset(A,B) ∧ subset(B,A)))))");
+    let conjecture = problem.conjectures().next().unwrap();
+    assert_eq!(conjecture.formula.to_string(), "((subset(a(),b()) ∧ subset(b(),c())) → subset(a(),c()))");
+
+    let problem = tptp::load("tests/fixtures/tptp/PUZ001-1.p").unwrap();
+    let clauses = problem.statements.iter().map(|s| (s.role, s.formula.to_string())).collect::<Vec<_>>();
+    assert_eq!(clauses, [
+        (Role::Hypothesis, "lives(agatha())".to_string()),
+        (Role::Axiom, "(∀X.(∀Y.(¬killed(X,Y) ∨ hates(X,Y))))".to_string()),
+        (Role::NegatedConjecture, "(∀X.(¬'='(X,agatha()) ∨ ¬hates(X,X)))".to_string()),
+        (Role::Lemma, "(less(1,2) ∨ '='('\"Agatha\"',agatha()))".to_string()),
+    ]);
+
+    let error = |source: &str| tptp::parse(source).unwrap_err().to_string();
+    assert_eq!(error("fof(a, axiom, p)"), "1:17: unexpected end of input, expected \".\"");
+    assert_eq!(error("tff(a, type, p: $o)."), "1:1: unexpected \"tff\", expected one of \"cnf\", \"fof\", \"include\"");
+    assert_eq!(error("cnf(a, axiom, p & q)."), "1:17: unexpected \"&\", expected one of \")\", \",\", \"|\"");
+    assert_eq!(error("fof(a, claim, p)."), "1:8: unexpected \"claim\", expected one of \"assumption\", \"axiom\", \"conjecture\", \"corollary\", \"definition\", \"hypothesis\", \"lemma\", \"negated_conjecture\", \"plain\", \"theorem\", \"unknown\"");
+    assert_eq!(error("fof(A, axiom, p)."), "1:5: unexpected \"A\", expected <name>");
+    assert!(tptp::parse("fof(a, axiom, p). fof(a, axiom, q).").unwrap_err().to_string().contains("duplicate name \"a\""));
+    assert!(matches!(
+        tptp::load("tests/fixtures/tptp/missing.p"),
+        Err(ProblemError::Missing { ref name, .. }) if name == "union_def"
+    ));
+}
+
+
+#[test]
+fn test_writer() {
+    let write = |input: &str| tptp::write_formula(&parser::parse(input).unwrap());
+    assert_eq!(write("forall x . P(x, a()) => exists y . Q(y, f(x))"), "! [X] : ('P'(X, a) => ? [Y] : 'Q'(Y, f(X)))");
+    assert_eq!(write("forall x . forall y . exists z . R(x, y, z)"), "! [X, Y] : ? [Z] : 'R'(X, Y, Z)");
+    // free variables are closed over, and variables that would collide numbered apart.
+    assert_eq!(write("forall X . P(X, x, _y) and not Q()"), "! [X_y, X1] : ! [X] : ('P'(X, X1, X_y) & ~ 'Q')");
+    assert_eq!(write("p(1, '\"Obj\"', 'John Smith', '$sum'(1, 2), 'it\\'s'()) <=> q()"), "(p(1, \"Obj\", 'John Smith', $sum(1, 2), 'it\\'s') <=> q)");
+
+    let rewrite = |input: &str| tptp::write_formula(&tptp::parse_formula(input).unwrap());
+    assert_eq!(rewrite("$true & a != b & ~ $false"), "(($true & a != b) & ~ $false)");
+    assert_eq!(rewrite("p <~> ~ (X = f(Y))"), "! [X, Y] : ~ (p <=> X != f(Y))");
+    assert_eq!(rewrite("p(-2.5e3, 3/4)"), "p(-2.5e3, 3/4)");
+    // signs alone are not numbers, they are quoted and read back as the same constants.
+    let written = write("p('-'(), '+'(), '-1'())");
+    assert_eq!(written, "p('-', '+', -1)");
+    assert_eq!(rewrite(&written), written);
+    // predicates and functors with arguments that would read as numbers or distinct objects
+    // are quoted, constants are not.
+    assert_eq!(write("'0'(a())"), "'0'(a)");
+    assert_eq!(tptp::parse_formula("'0'(a)").unwrap().to_string(), parser::parse("'0'(a())").unwrap().to_string());
+    let written = write("'0'('1'('\"o\"'())) and '\"p\"'('-2'(), '1'('3'()))");
+    assert_eq!(written, "('0'('1'(\"o\")) & '\"p\"'(-2, '1'(3)))");
+    assert_eq!(rewrite(&written), written);
+
+    // written problems read back the same.
+    for path in ["tests/fixtures/tptp/SET001+1.p", "tests/fixtures/tptp/PUZ001-1.p"] {
+        let problem = tptp::load(path).unwrap();
+        let written = tptp::write_problem(&problem);
+        let read = tptp::parse(&written).unwrap();
+        let statements = |p: &Problem| p.statements.iter().map(|s| (s.name.clone(), s.role, s.formula.to_string())).collect::<Vec<_>>();
+        assert_eq!(statements(&read), statements(&problem), "{}", written);
+        assert_eq!(tptp::write_problem(&read), written);
+    }
+    let problem = tptp::load("tests/fixtures/tptp/SET001+1.p").unwrap();
+    assert_eq!(
+        tptp::write_annotated(&problem.statements[4].name, Role::Conjecture, &problem.statements[4].formula),
+        "fof('transitivity of subset', conjecture, ((subset(a, b) & subset(b, c)) => subset(a, c)))."
+    );
+
+    let clauses = Clauses(vec![
+        vec![Literal::neg("q".to_string()), Literal::pos("P_a".to_string())].into_iter().collect::<Clause>(),
+        Clause::new(),
+    ]);
+    assert_eq!(tptp::write_clauses(&clauses, Role::NegatedConjecture), "cnf(c1, negated_conjecture, 'P_a' | ~ q).\ncnf(c2, negated_conjecture, $false).\n");
+    let read = tptp::parse(&tptp::write_clauses(&clauses, Role::Axiom)).unwrap();
+    assert_eq!(read.statements[0].formula.to_string(), "(P_a ∨ ¬q)");
+    // DIMACS variables are numbers, formula names may be integers.
+    let clauses = Clauses(vec![vec![Literal::neg("1".to_string()), Literal::pos("2".to_string())].into_iter().collect::<Clause>()]);
+    let written = tptp::write_clauses(&clauses, Role::Axiom);
+    assert_eq!(written, "cnf(c1, axiom, ~ '1' | '2').\n");
+    assert_eq!(tptp::write_problem(&tptp::parse(&written).unwrap()), "fof(c1, axiom, (~ '1' | '2')).\n");
+    assert_eq!(tptp::write_annotated("1", Role::Axiom, &parser::parse("'\"o\"'()").unwrap()), "fof(1, axiom, '\"o\"').");
+}
+
+
+#[test]
+fn test_szs() {
+    assert_eq!(Status::Theorem.line("SET001+1"), "% SZS status Theorem for SET001+1");
+    for status in Status::ALL {
+        assert_eq!(Status::from_name(&status.to_string()), Some(status));
+    }
+    assert!(!Status::Timeout.is_success() && Status::CounterSatisfiable.is_success());
+
+    // decide the propositional problems by validity or satisfiability of their formula.
+    let status = |source: &str| {
+        let problem = tptp::parse(source).unwrap();
+        let formula = problem.to_formula().unwrap();
+        let holds = if problem.conjectures().next().is_some() { valid(formula) } else { !valid(Formula::not(formula)) };
+        Status::of(&problem, Some(holds))
+    };
+    assert_eq!(status("fof(a, axiom, p => q). fof(b, axiom, p). fof(c, conjecture, q)."), Status::Theorem);
+    assert_eq!(status("fof(a, axiom, p => q). fof(c, conjecture, q)."), Status::CounterSatisfiable);
+    assert_eq!(status("cnf(a, axiom, p | q). cnf(b, negated_conjecture, ~ p)."), Status::Satisfiable);
+    assert_eq!(status("cnf(a, axiom, p). cnf(b, negated_conjecture, ~ p)."), Status::Unsatisfiable);
+    assert_eq!(Status::of(&Problem::default(), None), Status::GaveUp);
+
+    assert_eq!(
+        szs::output("ListOfFormulae", "p", "fof(a, axiom, p)."),
+        "% SZS output start ListOfFormulae for p\nfof(a, axiom, p).\n% SZS output end ListOfFormulae for p\n"
+    );
+    assert_eq!(szs::output("Model", "p", ""), "% SZS output start Model for p\n% SZS output end Model for p\n");
+}